@@ -0,0 +1,49 @@
+#![cfg(windows)]
+
+use std::{env, path::PathBuf, process};
+
+use ursa_minor_ffb::hid::hidapi_backend::HidapiBackend;
+use ursa_minor_ffb::hid::lab::{run_cli, LabLog, CLI_USAGE};
+use ursa_minor_ffb::hid::protocol::WW_VID;
+use ursa_minor_ffb::LogBuffer;
+
+fn main() {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let mut log_path: Option<PathBuf> = None;
+    if let Some(i) = args.iter().position(|a| a == "--log") {
+        if i + 1 >= args.len() {
+            eprintln!("missing --log value\n\n{CLI_USAGE}");
+            process::exit(2);
+        }
+        log_path = Some(PathBuf::from(args.remove(i + 1)));
+        args.remove(i);
+    }
+    if args.is_empty() || args[0] == "--help" || args[0] == "-h" {
+        println!("{CLI_USAGE}");
+        return;
+    }
+
+    let logs = LogBuffer::default();
+    let mut backend = match HidapiBackend::new(logs) {
+        Ok(b) => b,
+        Err(e) => {
+            eprintln!("HidApi init failed: {e}");
+            process::exit(1);
+        }
+    };
+
+    let mut lab = LabLog::default();
+    let mut out = String::new();
+    let result = run_cli(&mut backend, WW_VID, &args, &mut lab, &mut out);
+    print!("{out}");
+
+    if let Some(path) = log_path {
+        if let Err(e) = lab.export_to(&path) {
+            eprintln!("could not write {}: {e}", path.display());
+        }
+    }
+    if let Err(e) = result {
+        eprintln!("{e}");
+        process::exit(1);
+    }
+}
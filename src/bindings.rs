@@ -0,0 +1,345 @@
+//! Stick button bindings and the runtime output controls (master gain, muted effects) they drive.
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use crossbeam_channel::Sender;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::{preset::PresetStore, HidCmd, LogBuffer};
+
+pub const GAIN_STEP_PCT: u8 = 10;
+pub const MAX_GAIN_PCT: u8 = 100;
+
+/// One rumble contribution that can be muted on its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Effect {
+    Base,
+    GroundRoll,
+    Flaps,
+    Gear,
+    Stall,
+    Bank,
+    Spoilers,
+    Engine,
+    /// Every effect defined under `custom_effects:` in the preset.
+    Custom,
+    /// Every native plugin in the `plugins/` folder.
+    Plugins,
+}
+
+impl Effect {
+    pub const ALL: [Effect; 10] = [
+        Effect::Base,
+        Effect::GroundRoll,
+        Effect::Flaps,
+        Effect::Gear,
+        Effect::Stall,
+        Effect::Bank,
+        Effect::Spoilers,
+        Effect::Engine,
+        Effect::Custom,
+        Effect::Plugins,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Effect::Base => "Base (airspeed)",
+            Effect::GroundRoll => "Ground Roll",
+            Effect::Flaps => "Flaps (bump)",
+            Effect::Gear => "Landing Gear (bump)",
+            Effect::Stall => "Stall",
+            Effect::Bank => "Bank / Turb",
+            Effect::Spoilers => "Spoilers",
+            Effect::Engine => "Engine",
+            Effect::Custom => "Custom effects",
+            Effect::Plugins => "Plugin effects",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    ToggleHold,
+    GainUp,
+    GainDown,
+    CyclePreset,
+    Mute(Effect),
+    /// Flip between the two sides of an A/B comparison (saved vs. edited if none is running).
+    ToggleAb,
+}
+
+impl Action {
+    /// Every action, with `Mute` represented by its first effect.
+    pub const ALL: [Action; 6] = [
+        Action::ToggleHold,
+        Action::GainUp,
+        Action::GainDown,
+        Action::CyclePreset,
+        Action::Mute(Effect::Base),
+        Action::ToggleAb,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::ToggleHold => "Toggle hold",
+            Action::GainUp => "Master gain up",
+            Action::GainDown => "Master gain down",
+            Action::CyclePreset => "Cycle preset",
+            Action::Mute(_) => "Mute effect",
+            Action::ToggleAb => "A/B toggle",
+        }
+    }
+
+    /// Same action kind, ignoring which effect a `Mute` targets.
+    pub fn same_kind(self, other: Action) -> bool {
+        std::mem::discriminant(&self) == std::mem::discriminant(&other)
+    }
+}
+
+/// `settings.yml` entry, e.g. `{ button: 4, action: gain_up }` or `{ button: 7, action: { mute: stall } }`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ButtonBinding {
+    /// Bit index in the stick's button field (0-based).
+    pub button: u8,
+    #[serde(with = "serde_yaml::with::singleton_map")]
+    pub action: Action,
+}
+
+pub fn actions_for(bindings: &[ButtonBinding], button: u8) -> impl Iterator<Item = Action> + '_ {
+    bindings
+        .iter()
+        .filter(move |b| b.button == button)
+        .map(|b| b.action)
+}
+
+pub type BindingsShared = Arc<Mutex<Vec<ButtonBinding>>>;
+
+/// What a button press did, for the UI to show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ButtonOutcome {
+    Held(bool),
+    Gain(u8),
+    Muted(Effect, bool),
+    /// Works on the preset editor's state, so the UI runs it when it next draws.
+    Deferred(Action),
+}
+
+/// Runs bound actions as presses arrive, away from the UI thread (which draws no frames while
+/// the window is minimized). Hold, gain and mutes take effect here; the UI only reflects them.
+pub struct ButtonDispatcher {
+    pub bindings: BindingsShared,
+    /// Set while the bindings panel waits to learn a button; presses then do nothing here.
+    pub learning: Arc<AtomicBool>,
+    pub controls: ControlsShared,
+    pub hold: Arc<AtomicBool>,
+    pub tx_hid: Sender<HidCmd>,
+    /// Where a changed master gain is saved.
+    pub store: PresetStore,
+    pub logs: LogBuffer,
+}
+
+impl ButtonDispatcher {
+    /// Run every action bound to `button` and acknowledge them with a pulse on the stick.
+    pub fn dispatch(&self, button: u8) -> Vec<ButtonOutcome> {
+        if self.learning.load(Ordering::Relaxed) {
+            return Vec::new();
+        }
+        let actions: Vec<Action> = actions_for(&self.bindings.lock(), button).collect();
+        let outcomes: Vec<ButtonOutcome> = actions.into_iter().map(|a| self.run(a)).collect();
+        if !outcomes.is_empty() {
+            let _ = self.tx_hid.send(HidCmd::AckPulse);
+        }
+        outcomes
+    }
+
+    fn run(&self, action: Action) -> ButtonOutcome {
+        match action {
+            Action::ToggleHold => {
+                let held = !self.hold.fetch_xor(true, Ordering::Relaxed);
+                let _ = self.tx_hid.send(HidCmd::SetHold(held));
+                ButtonOutcome::Held(held)
+            }
+            Action::GainUp | Action::GainDown => {
+                let gain = self.controls.lock().step_gain(action == Action::GainUp);
+                if let Err(e) = self.store.update_settings(|s| s.master_gain_pct = gain) {
+                    self.logs
+                        .push(format!("Could not save the master gain: {e}"));
+                }
+                for note in self.store.take_notes() {
+                    self.logs.push(note);
+                }
+                ButtonOutcome::Gain(gain)
+            }
+            Action::Mute(effect) => {
+                ButtonOutcome::Muted(effect, self.controls.lock().toggle_mute(effect))
+            }
+            Action::CyclePreset | Action::ToggleAb => ButtonOutcome::Deferred(action),
+        }
+    }
+}
+
+/// Master gain and mutes applied on top of the active preset; not saved with it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputControls {
+    pub gain_pct: u8,
+    muted: Vec<Effect>,
+}
+
+impl Default for OutputControls {
+    fn default() -> Self {
+        Self {
+            gain_pct: MAX_GAIN_PCT,
+            muted: Vec::new(),
+        }
+    }
+}
+
+pub type ControlsShared = Arc<Mutex<OutputControls>>;
+
+impl OutputControls {
+    pub fn with_gain(gain_pct: u8) -> Self {
+        Self {
+            gain_pct: gain_pct.min(MAX_GAIN_PCT),
+            ..Self::default()
+        }
+    }
+
+    pub fn is_muted(&self, effect: Effect) -> bool {
+        self.muted.contains(&effect)
+    }
+
+    /// Returns whether `effect` is muted afterwards.
+    pub fn toggle_mute(&mut self, effect: Effect) -> bool {
+        if let Some(i) = self.muted.iter().position(|e| *e == effect) {
+            self.muted.remove(i);
+            false
+        } else {
+            self.muted.push(effect);
+            true
+        }
+    }
+
+    /// Move the master gain one step and return the new value.
+    pub fn step_gain(&mut self, up: bool) -> u8 {
+        self.gain_pct = if up {
+            self.gain_pct
+                .saturating_add(GAIN_STEP_PCT)
+                .min(MAX_GAIN_PCT)
+        } else {
+            self.gain_pct.saturating_sub(GAIN_STEP_PCT)
+        };
+        self.gain_pct
+    }
+
+    pub fn scale(&self, value: f64) -> f64 {
+        value * f64::from(self.gain_pct) / 100.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bindings_round_trip_through_yaml() {
+        let bindings = vec![
+            ButtonBinding {
+                button: 4,
+                action: Action::GainUp,
+            },
+            ButtonBinding {
+                button: 7,
+                action: Action::Mute(Effect::Stall),
+            },
+        ];
+        let text = serde_yaml::to_string(&bindings).unwrap();
+        assert!(text.contains("action: gain_up"), "{text}");
+        assert!(text.contains("mute: stall"), "{text}");
+
+        let parsed: Vec<ButtonBinding> = serde_yaml::from_str(
+            "- { button: 4, action: gain_up }\n- { button: 7, action: { mute: stall } }\n",
+        )
+        .unwrap();
+        assert_eq!(parsed, bindings);
+        assert_eq!(
+            actions_for(&parsed, 7).collect::<Vec<_>>(),
+            vec![Action::Mute(Effect::Stall)]
+        );
+    }
+
+    #[test]
+    fn dispatch_acts_without_the_ui_and_acknowledges() {
+        let dir = std::env::temp_dir().join(format!("ursa-buttons-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let (tx_hid, rx_hid) = crossbeam_channel::unbounded();
+        let dispatcher = ButtonDispatcher {
+            bindings: Arc::new(Mutex::new(vec![
+                ButtonBinding {
+                    button: 1,
+                    action: Action::ToggleHold,
+                },
+                ButtonBinding {
+                    button: 1,
+                    action: Action::GainDown,
+                },
+                ButtonBinding {
+                    button: 2,
+                    action: Action::CyclePreset,
+                },
+            ])),
+            learning: Arc::default(),
+            controls: Arc::new(Mutex::new(OutputControls::default())),
+            hold: Arc::default(),
+            tx_hid,
+            store: PresetStore::new(dir.clone()),
+            logs: LogBuffer::default(),
+        };
+
+        assert_eq!(
+            dispatcher.dispatch(1),
+            vec![ButtonOutcome::Held(true), ButtonOutcome::Gain(90)]
+        );
+        assert!(dispatcher.hold.load(Ordering::Relaxed));
+        assert!(matches!(rx_hid.try_recv(), Ok(HidCmd::SetHold(true))));
+        assert!(matches!(rx_hid.try_recv(), Ok(HidCmd::AckPulse)));
+        assert_eq!(
+            dispatcher.store.load_settings().unwrap().master_gain_pct,
+            90
+        );
+
+        assert_eq!(
+            dispatcher.dispatch(2),
+            vec![ButtonOutcome::Deferred(Action::CyclePreset)]
+        );
+        assert!(dispatcher.dispatch(3).is_empty());
+
+        dispatcher.learning.store(true, Ordering::Relaxed);
+        assert!(
+            dispatcher.dispatch(1).is_empty(),
+            "the panel is learning a button"
+        );
+        assert!(dispatcher.hold.load(Ordering::Relaxed));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn gain_steps_clamp_and_mutes_toggle() {
+        let mut c = OutputControls::with_gain(95);
+        assert_eq!(c.step_gain(true), 100);
+        assert_eq!(c.step_gain(false), 90);
+        c.gain_pct = 5;
+        assert_eq!(c.step_gain(false), 0);
+        assert_eq!(c.scale(200.0), 0.0);
+
+        assert!(c.toggle_mute(Effect::Gear));
+        assert!(c.is_muted(Effect::Gear));
+        assert!(!c.toggle_mute(Effect::Gear));
+        assert!(!c.is_muted(Effect::Gear));
+    }
+}
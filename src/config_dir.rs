@@ -0,0 +1,275 @@
+//! Where presets, settings and the log live.
+//!
+//! In order of preference: `--config-dir <path>`; portable mode, when a `portable.txt` sits next to
+//! the executable (everything stays beside it, as in older versions); otherwise the per-user folders,
+//! `%APPDATA%` and `%LOCALAPPDATA%` on Windows, `$XDG_CONFIG_HOME` and `$XDG_STATE_HOME` elsewhere.
+
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Marker file next to the executable that selects portable mode.
+pub const PORTABLE_MARKER: &str = "portable.txt";
+
+#[cfg(windows)]
+const APP_DIR: &str = "UrsaMinorFFB";
+#[cfg(not(windows))]
+const APP_DIR: &str = "ursa-minor-ffb";
+
+const LOG_FILE: &str = "UrsaMinorFFB.log";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigSource {
+    CommandLine,
+    Portable,
+    User,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigDirs {
+    /// Presets and settings (roaming on Windows).
+    pub config: PathBuf,
+    /// Log file (machine-local).
+    pub data: PathBuf,
+    pub source: ConfigSource,
+}
+
+impl ConfigDirs {
+    pub fn presets_dir(&self) -> PathBuf {
+        self.config.join("presets")
+    }
+
+    /// Native effect plugins, loaded at startup.
+    pub fn plugins_dir(&self) -> PathBuf {
+        self.config.join("plugins")
+    }
+
+    pub fn log_path(&self) -> PathBuf {
+        self.data.join(LOG_FILE)
+    }
+
+    /// Folders for this process, from `args` (without the program name), the executable's folder
+    /// and the environment.
+    pub fn from_env(args: impl IntoIterator<Item = String>) -> Self {
+        let exe_dir = exe_dir();
+        Self::resolve(config_dir_arg(args), exe_dir.as_deref(), |key| {
+            std::env::var_os(key)
+        })
+    }
+
+    pub fn resolve(
+        cli: Option<PathBuf>,
+        exe_dir: Option<&Path>,
+        env: impl Fn(&str) -> Option<OsString>,
+    ) -> Self {
+        if let Some(dir) = cli {
+            return Self::single(dir, ConfigSource::CommandLine);
+        }
+        if let Some(exe) = exe_dir.filter(|d| d.join(PORTABLE_MARKER).exists()) {
+            return Self::single(exe.to_path_buf(), ConfigSource::Portable);
+        }
+        match user_dirs(&env) {
+            Some((config, data)) => Self {
+                config: config.join(APP_DIR),
+                data: data.join(APP_DIR),
+                source: ConfigSource::User,
+            },
+            // No per-user folder known: behave as portable rather than scatter files in the cwd.
+            None => Self::single(
+                exe_dir.map_or_else(|| PathBuf::from("."), Path::to_path_buf),
+                ConfigSource::Portable,
+            ),
+        }
+    }
+
+    fn single(dir: PathBuf, source: ConfigSource) -> Self {
+        Self {
+            config: dir.clone(),
+            data: dir,
+            source,
+        }
+    }
+
+    /// First start with per-user folders: copy `presets/` (and a loose `settings.yml`) from the
+    /// executable's folder. Runs only while the per-user `presets/` does not exist yet, and leaves
+    /// the originals in place. Returns the copied files.
+    pub fn migrate_from_exe_dir(&self, exe_dir: &Path) -> io::Result<Vec<PathBuf>> {
+        let target = self.presets_dir();
+        if self.source != ConfigSource::User || target.exists() {
+            return Ok(Vec::new());
+        }
+        let old_presets = exe_dir.join("presets");
+        let loose_settings = exe_dir.join("settings.yml");
+        if !old_presets.is_dir() && !loose_settings.is_file() {
+            return Ok(Vec::new());
+        }
+
+        // Copy into a staging folder and rename it, so an interrupted copy is retried next start.
+        let staging = self.config.join("presets.migrating");
+        let _ = fs::remove_dir_all(&staging);
+        fs::create_dir_all(&staging)?;
+        let mut copied = Vec::new();
+        if old_presets.is_dir() {
+            copy_tree(&old_presets, &staging, &mut copied)?;
+        }
+        if loose_settings.is_file() && !staging.join("settings.yml").exists() {
+            fs::copy(&loose_settings, staging.join("settings.yml"))?;
+            copied.push(loose_settings);
+        }
+        fs::rename(&staging, &target)?;
+        Ok(copied)
+    }
+}
+
+pub fn exe_dir() -> Option<PathBuf> {
+    std::env::current_exe()
+        .ok()
+        .and_then(|p| p.parent().map(Path::to_path_buf))
+}
+
+/// Value of `--config-dir <path>` or `--config-dir=<path>`; other arguments are ignored.
+pub fn config_dir_arg(args: impl IntoIterator<Item = String>) -> Option<PathBuf> {
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--config-dir" {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(value) = arg.strip_prefix("--config-dir=") {
+            return Some(PathBuf::from(value));
+        }
+    }
+    None
+}
+
+#[cfg(windows)]
+fn user_dirs(env: &impl Fn(&str) -> Option<OsString>) -> Option<(PathBuf, PathBuf)> {
+    let config = PathBuf::from(env("APPDATA")?);
+    let data = env("LOCALAPPDATA").map_or_else(|| config.clone(), PathBuf::from);
+    Some((config, data))
+}
+
+#[cfg(not(windows))]
+fn user_dirs(env: &impl Fn(&str) -> Option<OsString>) -> Option<(PathBuf, PathBuf)> {
+    let home = env("HOME").map(PathBuf::from);
+    let xdg = |key: &str, fallback: &str| {
+        env(key)
+            .map(PathBuf::from)
+            .filter(|p| p.is_absolute())
+            .or_else(|| home.as_ref().map(|h| h.join(fallback)))
+    };
+    Some((
+        xdg("XDG_CONFIG_HOME", ".config")?,
+        xdg("XDG_STATE_HOME", ".local/state")?,
+    ))
+}
+
+fn copy_tree(from: &Path, to: &Path, copied: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let path = entry.path();
+        let dest = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            fs::create_dir_all(&dest)?;
+            copy_tree(&path, &dest, copied)?;
+        } else {
+            fs::copy(&path, &dest)?;
+            copied.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn config_dir_arg_accepts_both_forms() {
+        assert_eq!(
+            config_dir_arg(args(&["--config-dir", "/cfg"])),
+            Some(PathBuf::from("/cfg"))
+        );
+        assert_eq!(
+            config_dir_arg(args(&["--verbose", "--config-dir=/cfg"])),
+            Some(PathBuf::from("/cfg"))
+        );
+        assert_eq!(config_dir_arg(args(&["--config-dir"])), None);
+        assert_eq!(config_dir_arg(args(&[])), None);
+    }
+
+    #[test]
+    fn command_line_wins_then_portable_then_user() {
+        let exe = std::env::temp_dir().join(format!("ursa-portable-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&exe);
+        fs::create_dir_all(&exe).unwrap();
+        let env = |key: &str| match key {
+            "APPDATA" | "XDG_CONFIG_HOME" => Some(OsString::from("/home/u/.config")),
+            "LOCALAPPDATA" | "XDG_STATE_HOME" => Some(OsString::from("/home/u/.local/state")),
+            _ => None,
+        };
+
+        let user = ConfigDirs::resolve(None, Some(&exe), env);
+        assert_eq!(user.source, ConfigSource::User);
+        assert_eq!(user.config, Path::new("/home/u/.config").join(APP_DIR));
+        assert_eq!(user.data, Path::new("/home/u/.local/state").join(APP_DIR));
+
+        fs::write(exe.join(PORTABLE_MARKER), "").unwrap();
+        let portable = ConfigDirs::resolve(None, Some(&exe), env);
+        assert_eq!(portable.source, ConfigSource::Portable);
+        assert_eq!(portable.presets_dir(), exe.join("presets"));
+
+        let cli = ConfigDirs::resolve(Some(PathBuf::from("/cfg")), Some(&exe), env);
+        assert_eq!(cli.source, ConfigSource::CommandLine);
+        assert_eq!(cli.log_path(), Path::new("/cfg").join(LOG_FILE));
+
+        let _ = fs::remove_dir_all(&exe);
+    }
+
+    #[test]
+    fn first_user_start_copies_exe_dir_files_once() {
+        let root = std::env::temp_dir().join(format!("ursa-migrate-dirs-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let exe = root.join("exe");
+        fs::create_dir_all(exe.join("presets").join("user")).unwrap();
+        fs::write(exe.join("presets").join("fighter.yml"), "kind: fighter\n").unwrap();
+        fs::write(
+            exe.join("presets").join("user").join("cub.yml"),
+            "name: Cub\n",
+        )
+        .unwrap();
+        fs::write(exe.join("settings.yml"), "active: fighter\n").unwrap();
+        let dirs = ConfigDirs {
+            config: root.join("config"),
+            data: root.join("data"),
+            source: ConfigSource::User,
+        };
+
+        let copied = dirs.migrate_from_exe_dir(&exe).unwrap();
+        assert_eq!(copied.len(), 3);
+        let presets = dirs.presets_dir();
+        assert!(presets.join("user").join("cub.yml").exists());
+        assert_eq!(
+            fs::read_to_string(presets.join("settings.yml")).unwrap(),
+            "active: fighter\n"
+        );
+        assert!(
+            exe.join("presets").join("fighter.yml").exists(),
+            "originals stay"
+        );
+
+        fs::write(presets.join("fighter.yml"), "kind: fighter\nrumble: {}\n").unwrap();
+        assert!(dirs.migrate_from_exe_dir(&exe).unwrap().is_empty());
+        assert_eq!(
+            fs::read_to_string(presets.join("fighter.yml")).unwrap(),
+            "kind: fighter\nrumble: {}\n",
+            "later starts never copy over user edits"
+        );
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
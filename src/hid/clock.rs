@@ -0,0 +1,108 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{Receiver, RecvTimeoutError, TryRecvError};
+use parking_lot::Mutex;
+
+use crate::HidCmd;
+
+/// Time source for the HID session so cadence logic can run against a fake clock.
+pub trait Clock {
+    fn now(&self) -> Instant;
+
+    /// Wait up to `timeout` for the next command.
+    fn recv_cmd(
+        &self,
+        rx: &Receiver<HidCmd>,
+        timeout: Duration,
+    ) -> Result<HidCmd, RecvTimeoutError> {
+        rx.recv_timeout(timeout)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Clock that only moves when told to, or when `HidSession::run` waits for a command.
+/// Clones share the same time.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    origin: Instant,
+    now: Arc<Mutex<Instant>>,
+    /// Commands to deliver to `recv_cmd` at a given time, earliest first.
+    scheduled: Arc<Mutex<Vec<(Instant, HidCmd)>>>,
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        let origin = Instant::now();
+        Self {
+            origin,
+            now: Arc::new(Mutex::new(origin)),
+            scheduled: Arc::default(),
+        }
+    }
+
+    /// Deliver `cmd` to `recv_cmd` once the clock reaches `at` since its creation.
+    pub fn schedule(&self, at: Duration, cmd: HidCmd) {
+        let at = self.origin + at;
+        let mut scheduled = self.scheduled.lock();
+        let i = scheduled.partition_point(|(t, _)| *t <= at);
+        scheduled.insert(i, (at, cmd));
+    }
+
+    pub fn advance(&self, by: Duration) {
+        *self.now.lock() += by;
+    }
+
+    /// Time advanced since the clock was created.
+    pub fn elapsed(&self) -> Duration {
+        self.now.lock().duration_since(self.origin)
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        *self.now.lock()
+    }
+
+    /// Commands already in `rx` arrive at once; otherwise time jumps to the next scheduled
+    /// command, or by `timeout` when none is due sooner. Disconnected once `rx` is and nothing
+    /// is scheduled.
+    fn recv_cmd(
+        &self,
+        rx: &Receiver<HidCmd>,
+        timeout: Duration,
+    ) -> Result<HidCmd, RecvTimeoutError> {
+        let mut scheduled = self.scheduled.lock();
+        match rx.try_recv() {
+            Ok(cmd) => return Ok(cmd),
+            Err(TryRecvError::Disconnected) if scheduled.is_empty() => {
+                return Err(RecvTimeoutError::Disconnected)
+            }
+            Err(_) => {}
+        }
+        let mut now = self.now.lock();
+        let deadline = *now + timeout;
+        if scheduled.first().is_some_and(|(at, _)| *at <= deadline) {
+            let (at, cmd) = scheduled.remove(0);
+            *now = at.max(*now);
+            Ok(cmd)
+        } else {
+            *now = deadline;
+            Err(RecvTimeoutError::Timeout)
+        }
+    }
+}
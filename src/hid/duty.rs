@@ -0,0 +1,195 @@
+//! Motor duty-cycle limiter: tracks the intensity delivered over a rolling window and scales
+//! sustained high output down, so long stall warnings or a stuck stream cannot run the vibration
+//! motor near full for minutes. Short transients barely move the window average and pass as is.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// `motor_duty:` in `settings.yml`. Percentages are of full intensity averaged over the window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DutyLimits {
+    pub enabled: bool,
+    pub window_s: u32,
+    /// Average where limiting starts.
+    pub onset_pct: u8,
+    /// Average where output is scaled all the way down to `floor_pct`.
+    pub full_pct: u8,
+    /// Lowest share of the requested intensity let through while limiting.
+    pub floor_pct: u8,
+}
+
+impl Default for DutyLimits {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window_s: 60,
+            onset_pct: 45,
+            full_pct: 75,
+            floor_pct: 35,
+        }
+    }
+}
+
+impl DutyLimits {
+    /// The same limits forced into workable ranges (`onset_pct` below `full_pct`, and so on).
+    pub fn sanitized(self) -> Self {
+        let onset_pct = self.onset_pct.clamp(1, 99);
+        Self {
+            enabled: self.enabled,
+            window_s: self.window_s.clamp(5, 600),
+            onset_pct,
+            full_pct: self.full_pct.clamp(onset_pct + 1, 100),
+            floor_pct: self.floor_pct.min(100),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct DutyLimiter {
+    limits: DutyLimits,
+    origin: Option<Instant>,
+    /// Intensity-seconds (0–255 scale) per whole second since `origin`, oldest first.
+    buckets: VecDeque<(u64, f64)>,
+    /// Level the motor has run at since `since`.
+    level: u8,
+    since: Option<Instant>,
+}
+
+impl Default for DutyLimiter {
+    fn default() -> Self {
+        Self::new(DutyLimits::default())
+    }
+}
+
+impl DutyLimiter {
+    pub fn new(limits: DutyLimits) -> Self {
+        Self {
+            limits: limits.sanitized(),
+            origin: None,
+            buckets: VecDeque::new(),
+            level: 0,
+            since: None,
+        }
+    }
+
+    pub fn limits(&self) -> DutyLimits {
+        self.limits
+    }
+
+    /// New limits; the window's history is kept.
+    pub fn set_limits(&mut self, limits: DutyLimits) {
+        self.limits = limits.sanitized();
+    }
+
+    /// The motor runs at `level` from `now` on.
+    pub fn set_level(&mut self, now: Instant, level: u8) {
+        self.advance(now);
+        self.level = level;
+    }
+
+    /// Account for the current level up to `now`.
+    pub fn advance(&mut self, now: Instant) {
+        let window = Duration::from_secs(u64::from(self.limits.window_s));
+        let origin = *self.origin.get_or_insert(now);
+        let mut from = self.since.unwrap_or(now);
+        if let Some(window_start) = now.checked_sub(window) {
+            from = from.max(window_start);
+        }
+        self.since = Some(now);
+
+        let level = f64::from(self.level);
+        while from < now {
+            let second = from.saturating_duration_since(origin).as_secs();
+            let to = now.min(origin + Duration::from_secs(second + 1));
+            let energy = level * to.saturating_duration_since(from).as_secs_f64();
+            match self.buckets.back_mut() {
+                Some((last, e)) if *last == second => *e += energy,
+                _ => self.buckets.push_back((second, energy)),
+            }
+            from = to;
+        }
+
+        let oldest = now
+            .saturating_duration_since(origin)
+            .as_secs()
+            .saturating_sub(u64::from(self.limits.window_s));
+        while self
+            .buckets
+            .front()
+            .is_some_and(|(second, _)| *second < oldest)
+        {
+            self.buckets.pop_front();
+        }
+    }
+
+    /// Average intensity over the window, in percent of full.
+    pub fn average_pct(&self) -> f64 {
+        let energy: f64 = self.buckets.iter().map(|(_, e)| e).sum();
+        energy * 100.0 / (255.0 * f64::from(self.limits.window_s))
+    }
+
+    /// Share of the requested intensity to let through (1 when not limiting).
+    pub fn gain(&self) -> f64 {
+        let l = self.limits;
+        let avg = self.average_pct();
+        if !l.enabled || avg <= f64::from(l.onset_pct) {
+            return 1.0;
+        }
+        let t = ((avg - f64::from(l.onset_pct)) / f64::from(l.full_pct - l.onset_pct)).min(1.0);
+        1.0 - t * (1.0 - f64::from(l.floor_pct) / 100.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(limiter: &mut DutyLimiter, start: Instant, secs: u64, level: u8) -> Instant {
+        limiter.set_level(start, level);
+        let end = start + Duration::from_secs(secs);
+        limiter.advance(end);
+        end
+    }
+
+    #[test]
+    fn sustained_output_is_limited_progressively() {
+        let t0 = Instant::now();
+        let mut limiter = DutyLimiter::default();
+        let t = run(&mut limiter, t0, 60, 255);
+        assert!((limiter.average_pct() - 100.0).abs() < 1e-9);
+        assert!((limiter.gain() - 0.35).abs() < 1e-9, "floor");
+
+        let t = run(&mut limiter, t, 60, 0);
+        assert_eq!(limiter.average_pct(), 0.0, "window rolled past");
+        assert_eq!(limiter.gain(), 1.0);
+
+        run(&mut limiter, t, 60, 153);
+        assert!((limiter.average_pct() - 60.0).abs() < 1e-9);
+        assert!(
+            (limiter.gain() - (1.0 - 0.5 * 0.65)).abs() < 1e-9,
+            "half way"
+        );
+    }
+
+    #[test]
+    fn transients_pass_and_limits_are_sanitized() {
+        let t0 = Instant::now();
+        let mut limiter = DutyLimiter::default();
+        let t = run(&mut limiter, t0, 20, 100);
+        run(&mut limiter, t, 1, 255);
+        assert_eq!(limiter.gain(), 1.0, "a bump on moderate output");
+
+        let odd = DutyLimits {
+            window_s: 0,
+            onset_pct: 80,
+            full_pct: 50,
+            floor_pct: 150,
+            ..Default::default()
+        }
+        .sanitized();
+        assert_eq!((odd.window_s, odd.full_pct, odd.floor_pct), (5, 81, 100));
+    }
+}
@@ -0,0 +1,96 @@
+use std::ffi::CString;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::time::Duration;
+
+use hidapi::{HidApi, HidDevice};
+
+use crate::hid::transport::{HidBackend, HidDeviceInfo, HidTransport, TransportError};
+use crate::hid::win32::{hid_query_caps_from_path, spawn_device_change_listener};
+use crate::LogBuffer;
+
+pub struct HidapiBackend {
+    api: HidApi,
+    logs: LogBuffer,
+    device_changed: Option<Arc<AtomicBool>>,
+}
+
+impl HidapiBackend {
+    pub fn new(logs: LogBuffer) -> Result<Self, TransportError> {
+        let api = HidApi::new().map_err(|e| TransportError::Backend(e.to_string()))?;
+        let device_changed = spawn_device_change_listener(&logs);
+        Ok(Self {
+            api,
+            logs,
+            device_changed,
+        })
+    }
+}
+
+impl HidBackend for HidapiBackend {
+    type Device = HidapiDevice;
+
+    fn enumerate(&mut self) -> Result<Vec<HidDeviceInfo>, TransportError> {
+        // A failed refresh still leaves the previous list usable.
+        if let Err(e) = self.api.refresh_devices() {
+            self.logs.push(format!("HID: refresh_devices FAILED: {e}"));
+        }
+        Ok(self
+            .api
+            .device_list()
+            .map(|d| HidDeviceInfo {
+                path: d.path().to_string_lossy().to_string(),
+                vendor_id: d.vendor_id(),
+                product_id: d.product_id(),
+                interface_number: d.interface_number(),
+                usage_page: d.usage_page(),
+                usage: d.usage(),
+                serial: d.serial_number().unwrap_or_default().to_string(),
+            })
+            .collect())
+    }
+
+    fn open(&mut self, info: &HidDeviceInfo) -> Result<HidapiDevice, TransportError> {
+        let path =
+            CString::new(info.path.as_str()).map_err(|e| TransportError::Backend(e.to_string()))?;
+        self.api
+            .open_path(&path)
+            .map(HidapiDevice)
+            .map_err(|e| TransportError::Backend(e.to_string()))
+    }
+
+    fn query_caps(&mut self, info: &HidDeviceInfo) -> Option<(u16, u8)> {
+        hid_query_caps_from_path(&info.path, &self.logs)
+    }
+
+    fn take_device_change(&mut self) -> bool {
+        self.device_changed
+            .as_ref()
+            .is_some_and(|f| f.swap(false, Ordering::Relaxed))
+    }
+}
+
+pub struct HidapiDevice(HidDevice);
+
+impl HidTransport for HidapiDevice {
+    fn write(&mut self, data: &[u8]) -> Result<usize, TransportError> {
+        self.0
+            .write(data)
+            .map_err(|e| TransportError::Backend(e.to_string()))
+    }
+
+    fn read_report(&mut self, buf: &mut [u8], timeout: Duration) -> Result<usize, TransportError> {
+        let ms = i32::try_from(timeout.as_millis()).unwrap_or(i32::MAX);
+        self.0
+            .read_timeout(buf, ms)
+            .map_err(|e| TransportError::Backend(e.to_string()))
+    }
+
+    fn get_feature_report(&mut self, buf: &mut [u8]) -> Result<usize, TransportError> {
+        self.0
+            .get_feature_report(buf)
+            .map_err(|e| TransportError::Backend(e.to_string()))
+    }
+}
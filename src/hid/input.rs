@@ -0,0 +1,160 @@
+//! Sidestick input reports: decoding axes/buttons and telling whether a hand is on the stick.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+use crate::hid::registry::{AxisField, InputLayout};
+
+/// Centered and motionless for this long counts as hands off.
+pub const HANDS_OFF_AFTER: Duration = Duration::from_secs(2);
+/// Deflection (0..1) above which the stick is considered held.
+const CENTER_EPS: f32 = 0.03;
+/// Per-report movement (sum of |dx| + |dy|) that counts as a hand moving the stick.
+const MOTION_EPS: f32 = 0.004;
+
+/// Latest decoded state of one stick. Axes are normalized to -1..1.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct StickInput {
+    pub x: f32,
+    pub y: f32,
+    pub buttons: u64,
+    pub hand_on: bool,
+}
+
+impl StickInput {
+    /// Largest axis deflection, 0 (centered) to 1 (full).
+    pub fn deflection(&self) -> f32 {
+        self.x.abs().max(self.y.abs()).min(1.0)
+    }
+
+    pub fn is_pressed(&self, button: u8) -> bool {
+        button < 64 && self.buttons & (1 << button) != 0
+    }
+}
+
+pub type InputShared = Arc<Mutex<Option<StickInput>>>;
+
+/// Buttons down in `now` that were up in `prev`.
+pub fn newly_pressed(prev: u64, now: u64) -> impl Iterator<Item = u8> {
+    let edges = now & !prev;
+    (0..64u8).filter(move |i| edges & (1 << i) != 0)
+}
+
+/// Raw axes and buttons from one report; `None` if the report is too short or another ID.
+pub fn decode(layout: &InputLayout, report: &[u8]) -> Option<(f32, f32, u64)> {
+    if report.first() != Some(&layout.report_id) {
+        return None;
+    }
+
+    let axis = |field: &AxisField| -> Option<f32> {
+        let lo = *report.get(field.offset)?;
+        let hi = *report.get(field.offset + 1)?;
+        let raw = f32::from(u16::from_le_bytes([lo, hi]));
+        let (min, max) = (f32::from(field.min), f32::from(field.max));
+        if max <= min {
+            return Some(0.0);
+        }
+        let norm = ((raw - min) / (max - min)) * 2.0 - 1.0;
+        Some(if field.invert { -norm } else { norm }.clamp(-1.0, 1.0))
+    };
+    let x = axis(&layout.x)?;
+    let y = axis(&layout.y)?;
+
+    let mut buttons = 0u64;
+    let count = usize::from(layout.buttons.count.min(64));
+    for i in 0..count {
+        let byte = *report.get(layout.buttons.offset + i / 8)?;
+        if byte & (1 << (i % 8)) != 0 {
+            buttons |= 1 << i;
+        }
+    }
+    Some((x, y, buttons))
+}
+
+/// Hand-on heuristic: a released stick springs back to center and stays still.
+#[derive(Debug, Default)]
+pub struct HandDetector {
+    last: Option<(f32, f32)>,
+    still_since: Option<Instant>,
+}
+
+impl HandDetector {
+    pub fn update(&mut self, x: f32, y: f32, buttons: u64, now: Instant) -> bool {
+        let moved = self
+            .last
+            .is_some_and(|(lx, ly)| (x - lx).abs() + (y - ly).abs() > MOTION_EPS);
+        self.last = Some((x, y));
+
+        let held = moved || x.abs().max(y.abs()) > CENTER_EPS || buttons != 0;
+        if held {
+            self.still_since = None;
+            return true;
+        }
+        let since = *self.still_since.get_or_insert(now);
+        now.saturating_duration_since(since) < HANDS_OFF_AFTER
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hid::registry::ButtonField;
+
+    fn layout() -> InputLayout {
+        InputLayout {
+            report_id: 0x01,
+            x: AxisField {
+                offset: 1,
+                min: 0,
+                max: 4095,
+                invert: false,
+            },
+            y: AxisField {
+                offset: 3,
+                min: 0,
+                max: 4095,
+                invert: true,
+            },
+            buttons: ButtonField {
+                offset: 5,
+                count: 12,
+            },
+        }
+    }
+
+    #[test]
+    fn decodes_axes_and_buttons() {
+        let report = [0x01, 0xFF, 0x0F, 0x00, 0x00, 0b0000_0101, 0b0000_1000];
+        let (x, y, buttons) = decode(&layout(), &report).unwrap();
+        assert!((x - 1.0).abs() < 1e-6);
+        assert!((y - 1.0).abs() < 1e-6, "inverted axis");
+        assert_eq!(buttons, 0b1000_0000_0101);
+    }
+
+    #[test]
+    fn rejects_other_report_ids_and_short_reports() {
+        assert!(decode(&layout(), &[0x02, 0, 0, 0, 0, 0, 0]).is_none());
+        assert!(decode(&layout(), &[0x01, 0, 0]).is_none());
+    }
+
+    #[test]
+    fn centered_still_stick_reads_hands_off_after_timeout() {
+        let mut d = HandDetector::default();
+        let t0 = Instant::now();
+        assert!(d.update(0.2, 0.0, 0, t0));
+        assert!(d.update(0.0, 0.0, 0, t0 + Duration::from_millis(100)));
+        assert!(d.update(0.0, 0.0, 0, t0 + Duration::from_millis(1500)));
+        assert!(d.update(0.0, 0.0, 0, t0 + Duration::from_millis(3400)));
+        assert!(!d.update(0.0, 0.0, 0, t0 + Duration::from_millis(3600)));
+        assert!(d.update(0.0, 0.0, 1, t0 + Duration::from_millis(3700)));
+    }
+
+    #[test]
+    fn newly_pressed_reports_rising_edges_only() {
+        let pressed: Vec<u8> = newly_pressed(0b0011, 0b1010).collect();
+        assert_eq!(pressed, vec![3]);
+        assert_eq!(newly_pressed(1 << 63, 1 << 63).count(), 0);
+    }
+}
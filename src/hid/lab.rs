@@ -0,0 +1,528 @@
+//! HID protocol lab: build experimental frames, send them to one interface and log the
+//! bytes that go out and come back, for mapping undocumented device commands.
+
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+use crate::hid::transport::{HidBackend, HidDeviceInfo, HidTransport, TransportError};
+
+/// Entries kept before the oldest are dropped.
+pub const LAB_LOG_CAP: usize = 5000;
+pub const DEFAULT_READ_TIMEOUT: Duration = Duration::from_millis(250);
+
+#[derive(Debug, thiserror::Error)]
+pub enum LabError {
+    #[error("invalid hex byte '{0}'")]
+    BadHex(String),
+    #[error("no interface matches '{0}'")]
+    NoSuchInterface(String),
+    #[error("{0}")]
+    Usage(String),
+    #[error(transparent)]
+    Transport(#[from] TransportError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// Report types that can be read back from a device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportKind {
+    Input,
+    Feature,
+}
+
+impl ReportKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            ReportKind::Input => "input",
+            ReportKind::Feature => "feature",
+        }
+    }
+}
+
+/// Output frame laid out the way the simapp vibe frame is: report ID, channel, opcode, payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabFrame {
+    pub report_id: u8,
+    pub channel: u8,
+    pub opcode: Vec<u8>,
+    pub payload: Vec<u8>,
+    /// Total report length including the report ID; 0 sends the bytes unpadded.
+    pub len: u16,
+}
+
+impl LabFrame {
+    /// The known vibe command, as a starting point for variations.
+    pub fn simapp_vibe(channel: u8, intensity: u8) -> Self {
+        Self {
+            report_id: 0x02,
+            channel,
+            opcode: vec![0xBF, 0x00, 0x00, 0x03, 0x49, 0x00],
+            payload: vec![intensity],
+            len: 14,
+        }
+    }
+
+    pub fn build(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(2 + self.opcode.len() + self.payload.len());
+        buf.push(self.report_id);
+        buf.push(self.channel);
+        buf.extend_from_slice(&self.opcode);
+        buf.extend_from_slice(&self.payload);
+        if self.len > 0 {
+            buf.resize(self.len as usize, 0);
+        }
+        buf
+    }
+}
+
+/// Accepts `02 07 BF`, `0x02,0x07` or `0207BF`.
+pub fn parse_hex(text: &str) -> Result<Vec<u8>, LabError> {
+    let mut out = Vec::new();
+    for token in text
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|t| !t.is_empty())
+    {
+        let bad = || LabError::BadHex(token.to_string());
+        let digits = token
+            .strip_prefix("0x")
+            .or_else(|| token.strip_prefix("0X"))
+            .unwrap_or(token);
+        if digits.is_empty() || !digits.is_ascii() || (digits.len() > 2 && digits.len() % 2 == 1) {
+            return Err(bad());
+        }
+        for i in (0..digits.len()).step_by(2) {
+            let pair = &digits[i..(i + 2).min(digits.len())];
+            out.push(u8::from_str_radix(pair, 16).map_err(|_| bad())?);
+        }
+    }
+    Ok(out)
+}
+
+pub fn format_hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{b:02X}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Sent,
+    Received(ReportKind),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabEntry {
+    pub at: Duration,
+    pub path: String,
+    pub direction: Direction,
+    pub bytes: Vec<u8>,
+    /// Error text when the transfer failed.
+    pub error: Option<String>,
+}
+
+impl LabEntry {
+    pub fn line(&self) -> String {
+        let dir = match self.direction {
+            Direction::Sent => "TX".to_string(),
+            Direction::Received(kind) => format!("RX {}", kind.label()),
+        };
+        let mut line = format!(
+            "{:>9.3}s {dir:<10} {} [{}]",
+            self.at.as_secs_f64(),
+            self.path,
+            format_hex(&self.bytes)
+        );
+        if let Some(e) = &self.error {
+            let _ = write!(line, " ERROR: {e}");
+        }
+        line
+    }
+}
+
+/// Session log of every lab transfer.
+#[derive(Debug)]
+pub struct LabLog {
+    started: Instant,
+    entries: VecDeque<LabEntry>,
+}
+
+pub type LabShared = Arc<Mutex<LabLog>>;
+
+impl Default for LabLog {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            entries: VecDeque::new(),
+        }
+    }
+}
+
+impl LabLog {
+    pub fn record(
+        &mut self,
+        path: &str,
+        direction: Direction,
+        bytes: &[u8],
+        error: Option<String>,
+    ) {
+        if self.entries.len() >= LAB_LOG_CAP {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(LabEntry {
+            at: self.started.elapsed(),
+            path: path.to_string(),
+            direction,
+            bytes: bytes.to_vec(),
+            error,
+        });
+    }
+
+    pub fn entries(&self) -> &VecDeque<LabEntry> {
+        &self.entries
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn export_text(&self) -> String {
+        let mut out = String::from("# Ursa Minor FFB HID lab session\n");
+        for e in &self.entries {
+            out.push_str(&e.line());
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn export_to(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, self.export_text())
+    }
+}
+
+/// Write `bytes` and log the attempt.
+pub fn send_logged<T: HidTransport>(
+    dev: &mut T,
+    path: &str,
+    bytes: &[u8],
+    log: &mut LabLog,
+) -> Result<usize, TransportError> {
+    let result = dev.write(bytes);
+    log.record(
+        path,
+        Direction::Sent,
+        bytes,
+        result.as_ref().err().map(|e| e.to_string()),
+    );
+    result
+}
+
+/// Read one report of `len` bytes (feature reports start with `report_id`) and log it.
+pub fn read_logged<T: HidTransport>(
+    dev: &mut T,
+    path: &str,
+    kind: ReportKind,
+    report_id: u8,
+    len: usize,
+    log: &mut LabLog,
+) -> Result<Vec<u8>, TransportError> {
+    let mut buf = vec![0u8; len.max(1)];
+    let result = match kind {
+        ReportKind::Input => dev.read_report(&mut buf, DEFAULT_READ_TIMEOUT),
+        ReportKind::Feature => {
+            buf[0] = report_id;
+            dev.get_feature_report(&mut buf)
+        }
+    };
+    match result {
+        Ok(n) => {
+            buf.truncate(n);
+            log.record(path, Direction::Received(kind), &buf, None);
+            Ok(buf)
+        }
+        Err(e) => {
+            log.record(path, Direction::Received(kind), &[], Some(e.to_string()));
+            Err(e)
+        }
+    }
+}
+
+/// Take a queued input report of up to `len` bytes without waiting; `Ok(None)` when none has
+/// arrived yet. Reports and errors are logged, an empty queue is not.
+pub fn poll_input_logged<T: HidTransport>(
+    dev: &mut T,
+    path: &str,
+    len: usize,
+    log: &mut LabLog,
+) -> Result<Option<Vec<u8>>, TransportError> {
+    let mut buf = vec![0u8; len.max(1)];
+    let direction = Direction::Received(ReportKind::Input);
+    match dev.read_report(&mut buf, Duration::ZERO) {
+        Ok(0) => Ok(None),
+        Ok(n) => {
+            buf.truncate(n);
+            log.record(path, direction, &buf, None);
+            Ok(Some(buf))
+        }
+        Err(e) => {
+            log.record(path, direction, &[], Some(e.to_string()));
+            Err(e)
+        }
+    }
+}
+
+/// Interfaces of a vendor, in enumeration order (CLI indices refer to this order).
+pub fn list_interfaces<B: HidBackend>(
+    backend: &mut B,
+    vendor_id: u16,
+) -> Result<Vec<HidDeviceInfo>, TransportError> {
+    Ok(backend
+        .enumerate()?
+        .into_iter()
+        .filter(|d| d.vendor_id == vendor_id)
+        .collect())
+}
+
+/// Resolve a CLI target: an index into `list_interfaces` or an exact path.
+pub fn find_interface<'a>(
+    interfaces: &'a [HidDeviceInfo],
+    target: &str,
+) -> Result<&'a HidDeviceInfo, LabError> {
+    if let Ok(i) = target.parse::<usize>() {
+        if let Some(info) = interfaces.get(i) {
+            return Ok(info);
+        }
+    }
+    interfaces
+        .iter()
+        .find(|d| d.path == target)
+        .ok_or_else(|| LabError::NoSuchInterface(target.to_string()))
+}
+
+pub const CLI_USAGE: &str = "\
+usage: ursa-hid-lab [--log FILE] <command>
+
+commands:
+  list                                   list vendor interfaces
+  send  <iface> <hex bytes>              write a raw output report
+  frame <iface> [--report-id HH] [--channel HH] [--opcode HEX] [--payload HEX] [--len N]
+                                         build a frame (defaults: simapp vibe) and write it
+  read  <iface> [--feature HH] [--len N] read an input report, or a feature report by ID
+
+<iface> is an index from `list` or a device path.";
+
+fn flag_value<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|a| a == name)
+        .and_then(|i| args.get(i + 1))
+        .map(String::as_str)
+}
+
+fn hex_byte(text: &str) -> Result<u8, LabError> {
+    match parse_hex(text)?.as_slice() {
+        [b] => Ok(*b),
+        _ => Err(LabError::BadHex(text.to_string())),
+    }
+}
+
+fn usize_arg(text: &str) -> Result<usize, LabError> {
+    text.parse()
+        .map_err(|_| LabError::Usage(format!("expected a number, got '{text}'")))
+}
+
+fn u16_arg(text: &str) -> Result<u16, LabError> {
+    u16::try_from(usize_arg(text)?)
+        .map_err(|_| LabError::Usage(format!("expected at most {}, got '{text}'", u16::MAX)))
+}
+
+/// Run one CLI command against `backend`, writing human-readable output to `out`.
+pub fn run_cli<B: HidBackend>(
+    backend: &mut B,
+    vendor_id: u16,
+    args: &[String],
+    log: &mut LabLog,
+    out: &mut String,
+) -> Result<(), LabError> {
+    let interfaces = list_interfaces(backend, vendor_id)?;
+    let command = args.first().map(String::as_str).unwrap_or_default();
+
+    match command {
+        "list" => {
+            for (i, d) in interfaces.iter().enumerate() {
+                let _ = writeln!(
+                    out,
+                    "{i}: pid=0x{:04X} if#{} up=0x{:04X} u=0x{:04X} {}",
+                    d.product_id, d.interface_number, d.usage_page, d.usage, d.path
+                );
+            }
+            if interfaces.is_empty() {
+                let _ = writeln!(out, "no interfaces for VID=0x{vendor_id:04X}");
+            }
+        }
+        "send" | "frame" | "read" => {
+            let target = args
+                .get(1)
+                .ok_or_else(|| LabError::Usage(format!("{command}: missing <iface>")))?;
+            let info = find_interface(&interfaces, target)?;
+            let mut dev = backend.open(info)?;
+
+            if command == "read" {
+                let (kind, report_id) = match flag_value(args, "--feature") {
+                    Some(id) => (ReportKind::Feature, hex_byte(id)?),
+                    None => (ReportKind::Input, 0),
+                };
+                let len = flag_value(args, "--len")
+                    .map(usize_arg)
+                    .transpose()?
+                    .unwrap_or(64);
+                let bytes = read_logged(&mut dev, &info.path, kind, report_id, len, log)?;
+                let _ = writeln!(out, "RX {} [{}]", kind.label(), format_hex(&bytes));
+                return Ok(());
+            }
+
+            let bytes = if command == "send" {
+                let hex = args
+                    .get(2..)
+                    .filter(|rest| !rest.is_empty())
+                    .ok_or_else(|| LabError::Usage("send: missing <hex bytes>".into()))?
+                    .join(" ");
+                parse_hex(&hex)?
+            } else {
+                let mut frame = LabFrame::simapp_vibe(0x07, 0x00);
+                if let Some(v) = flag_value(args, "--report-id") {
+                    frame.report_id = hex_byte(v)?;
+                }
+                if let Some(v) = flag_value(args, "--channel") {
+                    frame.channel = hex_byte(v)?;
+                }
+                if let Some(v) = flag_value(args, "--opcode") {
+                    frame.opcode = parse_hex(v)?;
+                }
+                if let Some(v) = flag_value(args, "--payload") {
+                    frame.payload = parse_hex(v)?;
+                }
+                if let Some(v) = flag_value(args, "--len") {
+                    frame.len = u16_arg(v)?;
+                }
+                frame.build()
+            };
+            let n = send_logged(&mut dev, &info.path, &bytes, log)?;
+            let _ = writeln!(out, "TX {n} bytes [{}]", format_hex(&bytes));
+        }
+        _ => return Err(LabError::Usage(CLI_USAGE.to_string())),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_hex_accepts_common_spellings() {
+        assert_eq!(parse_hex("02 07 bf").unwrap(), vec![0x02, 0x07, 0xBF]);
+        assert_eq!(parse_hex("0x02,0x07").unwrap(), vec![0x02, 0x07]);
+        assert_eq!(parse_hex("0207BF").unwrap(), vec![0x02, 0x07, 0xBF]);
+        assert!(matches!(parse_hex("zz"), Err(LabError::BadHex(_))));
+    }
+
+    #[test]
+    fn simapp_template_matches_vibe_frame() {
+        use crate::hid::protocol::{
+            build_simapp_vibe_frame, SidestickVariant, WW_PID_URSA_MINOR_AIRBUS_L,
+        };
+        let frame = LabFrame::simapp_vibe(0x07, 0x19).build();
+        let vibe = build_simapp_vibe_frame(
+            SidestickVariant::Airbus,
+            WW_PID_URSA_MINOR_AIRBUS_L,
+            0x02,
+            14,
+            0x19,
+        );
+        assert_eq!(frame, vibe);
+    }
+
+    #[test]
+    fn export_lists_entries_in_order() {
+        let mut log = LabLog::default();
+        log.record("p", Direction::Sent, &[0x02, 0xAA], None);
+        log.record(
+            "p",
+            Direction::Received(ReportKind::Feature),
+            &[],
+            Some("timeout".into()),
+        );
+        let text = log.export_text();
+        let lines: Vec<&str> = text.lines().skip(1).collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains("TX") && lines[0].ends_with("[02 AA]"));
+        assert!(lines[1].contains("RX feature") && lines[1].contains("ERROR: timeout"));
+    }
+
+    #[test]
+    fn cli_frame_and_read_against_mock() {
+        use crate::hid::clock::ManualClock;
+        use crate::hid::mock::MockHid;
+        use crate::hid::protocol::WW_VID;
+
+        let hid = MockHid::new(ManualClock::new());
+        hid.plug(MockHid::sidestick_info("stick", 0xBC27), None);
+        hid.set_feature_report("stick", vec![0x05, 0x01, 0x02]);
+        let mut backend = hid.backend();
+        let mut log = LabLog::default();
+        let args = |s: &str| s.split(' ').map(String::from).collect::<Vec<_>>();
+
+        let mut out = String::new();
+        run_cli(
+            &mut backend,
+            WW_VID,
+            &args("frame 0 --channel 0A --opcode 42 --payload 01 --len 5"),
+            &mut log,
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(
+            hid.take_frames()[0].bytes,
+            vec![0x02, 0x0A, 0x42, 0x01, 0x00]
+        );
+
+        run_cli(
+            &mut backend,
+            WW_VID,
+            &args("read stick --feature 05 --len 3"),
+            &mut log,
+            &mut out,
+        )
+        .unwrap();
+        assert!(out.ends_with("RX feature [05 01 02]\n"));
+        assert_eq!(log.entries().len(), 2);
+
+        let err = run_cli(&mut backend, WW_VID, &args("send 9 00"), &mut log, &mut out);
+        assert!(matches!(err, Err(LabError::NoSuchInterface(_))));
+        let err = run_cli(
+            &mut backend,
+            WW_VID,
+            &args("frame 0 --len 65537"),
+            &mut log,
+            &mut out,
+        );
+        assert!(matches!(err, Err(LabError::Usage(_))), "{err:?}");
+    }
+
+    #[test]
+    fn log_drops_the_oldest_entries_at_the_cap() {
+        let mut log = LabLog::default();
+        for i in 0..=LAB_LOG_CAP {
+            log.record("stick", Direction::Sent, &(i as u32).to_le_bytes(), None);
+        }
+        assert_eq!(log.entries().len(), LAB_LOG_CAP);
+        assert_eq!(log.entries()[0].bytes, 1u32.to_le_bytes());
+    }
+}
@@ -0,0 +1,291 @@
+//! In-memory HID backend for driving `HidSession` in tests without hardware.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+
+use crate::hid::clock::ManualClock;
+use crate::hid::protocol::WW_VID;
+use crate::hid::transport::{HidBackend, HidDeviceInfo, HidTransport, TransportError};
+
+/// One output report captured by the mock, stamped with `ManualClock::elapsed`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockFrame {
+    pub at: Duration,
+    pub path: String,
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+struct MockDevice {
+    info: HidDeviceInfo,
+    caps: Option<(u16, u8)>,
+}
+
+#[derive(Debug)]
+struct MockState {
+    devices: Vec<MockDevice>,
+    failing_paths: HashSet<String>,
+    write_latency: HashMap<String, Duration>,
+    frames: Vec<MockFrame>,
+    input_reports: HashMap<String, VecDeque<Vec<u8>>>,
+    feature_reports: HashMap<(String, u8), Vec<u8>>,
+    hotplug_events: bool,
+    device_changed: bool,
+    enumerations: usize,
+    opens: usize,
+}
+
+impl Default for MockState {
+    fn default() -> Self {
+        Self {
+            devices: Vec::new(),
+            failing_paths: HashSet::new(),
+            write_latency: HashMap::new(),
+            frames: Vec::new(),
+            input_reports: HashMap::new(),
+            feature_reports: HashMap::new(),
+            hotplug_events: true,
+            device_changed: false,
+            enumerations: 0,
+            opens: 0,
+        }
+    }
+}
+
+/// Shared handle to the fake bus: plug/unplug devices and inspect written frames.
+#[derive(Debug, Clone)]
+pub struct MockHid {
+    clock: ManualClock,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockHid {
+    pub fn new(clock: ManualClock) -> Self {
+        Self {
+            clock,
+            state: Arc::new(Mutex::new(MockState::default())),
+        }
+    }
+
+    /// Ursa Minor vibe interface (generic desktop / joystick usage).
+    pub fn sidestick_info(path: &str, pid: u16) -> HidDeviceInfo {
+        HidDeviceInfo {
+            path: path.to_string(),
+            vendor_id: WW_VID,
+            product_id: pid,
+            interface_number: 0,
+            usage_page: 0x0001,
+            usage: 0x0004,
+            serial: path.to_string(),
+        }
+    }
+
+    pub fn plug(&self, info: HidDeviceInfo, caps: Option<(u16, u8)>) {
+        let mut st = self.state.lock();
+        st.devices.retain(|d| d.info.path != info.path);
+        st.devices.push(MockDevice { info, caps });
+        st.device_changed = st.hotplug_events;
+    }
+
+    pub fn unplug(&self, path: &str) {
+        let mut st = self.state.lock();
+        st.devices.retain(|d| d.info.path != path);
+        st.device_changed = st.hotplug_events;
+    }
+
+    /// Whether plug/unplug raise a device-change notification (on by default).
+    pub fn set_hotplug_events(&self, enabled: bool) {
+        self.state.lock().hotplug_events = enabled;
+    }
+
+    /// Make writes to `path` fail while the device stays enumerated.
+    pub fn set_write_failure(&self, path: &str, fail: bool) {
+        let mut st = self.state.lock();
+        if fail {
+            st.failing_paths.insert(path.to_string());
+        } else {
+            st.failing_paths.remove(path);
+        }
+    }
+
+    /// Make each write to `path` advance the clock by `latency`, like a slow device.
+    pub fn set_write_latency(&self, path: &str, latency: Duration) {
+        self.state
+            .lock()
+            .write_latency
+            .insert(path.to_string(), latency);
+    }
+
+    /// Queue an input report for the next `read_report` on `path`.
+    pub fn push_input_report(&self, path: &str, bytes: Vec<u8>) {
+        self.state
+            .lock()
+            .input_reports
+            .entry(path.to_string())
+            .or_default()
+            .push_back(bytes);
+    }
+
+    /// Feature report returned for `report_id` (the first byte of `bytes`).
+    pub fn set_feature_report(&self, path: &str, bytes: Vec<u8>) {
+        let id = bytes.first().copied().unwrap_or_default();
+        self.state
+            .lock()
+            .feature_reports
+            .insert((path.to_string(), id), bytes);
+    }
+
+    pub fn frames(&self) -> Vec<MockFrame> {
+        self.state.lock().frames.clone()
+    }
+
+    pub fn take_frames(&self) -> Vec<MockFrame> {
+        std::mem::take(&mut self.state.lock().frames)
+    }
+
+    pub fn enumerations(&self) -> usize {
+        self.state.lock().enumerations
+    }
+
+    pub fn opens(&self) -> usize {
+        self.state.lock().opens
+    }
+
+    pub fn backend(&self) -> MockBackend {
+        MockBackend { hid: self.clone() }
+    }
+}
+
+pub struct MockBackend {
+    hid: MockHid,
+}
+
+impl HidBackend for MockBackend {
+    type Device = MockTransport;
+
+    fn enumerate(&mut self) -> Result<Vec<HidDeviceInfo>, TransportError> {
+        let mut st = self.hid.state.lock();
+        st.enumerations += 1;
+        Ok(st.devices.iter().map(|d| d.info.clone()).collect())
+    }
+
+    fn open(&mut self, info: &HidDeviceInfo) -> Result<MockTransport, TransportError> {
+        let mut st = self.hid.state.lock();
+        if !st.devices.iter().any(|d| d.info.path == info.path) {
+            return Err(TransportError::Disconnected);
+        }
+        st.opens += 1;
+        Ok(MockTransport {
+            hid: self.hid.clone(),
+            path: info.path.clone(),
+        })
+    }
+
+    fn query_caps(&mut self, info: &HidDeviceInfo) -> Option<(u16, u8)> {
+        let st = self.hid.state.lock();
+        st.devices
+            .iter()
+            .find(|d| d.info.path == info.path)
+            .and_then(|d| d.caps)
+    }
+
+    fn take_device_change(&mut self) -> bool {
+        std::mem::take(&mut self.hid.state.lock().device_changed)
+    }
+}
+
+pub struct MockTransport {
+    hid: MockHid,
+    path: String,
+}
+
+impl HidTransport for MockTransport {
+    fn write(&mut self, data: &[u8]) -> Result<usize, TransportError> {
+        let at = self.hid.clock.elapsed();
+        let mut st = self.hid.state.lock();
+        if !st.devices.iter().any(|d| d.info.path == self.path) {
+            return Err(TransportError::Disconnected);
+        }
+        if st.failing_paths.contains(&self.path) {
+            return Err(TransportError::Backend("mock write failure".to_string()));
+        }
+        st.frames.push(MockFrame {
+            at,
+            path: self.path.clone(),
+            bytes: data.to_vec(),
+        });
+        if let Some(latency) = st.write_latency.get(&self.path) {
+            self.hid.clock.advance(*latency);
+        }
+        Ok(data.len())
+    }
+
+    fn read_report(&mut self, buf: &mut [u8], _timeout: Duration) -> Result<usize, TransportError> {
+        let mut st = self.hid.state.lock();
+        if !st.devices.iter().any(|d| d.info.path == self.path) {
+            return Err(TransportError::Disconnected);
+        }
+        let Some(report) = st
+            .input_reports
+            .get_mut(&self.path)
+            .and_then(VecDeque::pop_front)
+        else {
+            return Ok(0);
+        };
+        let n = report.len().min(buf.len());
+        buf[..n].copy_from_slice(&report[..n]);
+        Ok(n)
+    }
+
+    fn get_feature_report(&mut self, buf: &mut [u8]) -> Result<usize, TransportError> {
+        let st = self.hid.state.lock();
+        let id = buf.first().copied().unwrap_or_default();
+        let report = st
+            .feature_reports
+            .get(&(self.path.clone(), id))
+            .ok_or_else(|| TransportError::Backend(format!("no feature report 0x{id:02X}")))?;
+        let n = report.len().min(buf.len());
+        buf[..n].copy_from_slice(&report[..n]);
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_are_stamped_with_manual_clock() {
+        let clock = ManualClock::new();
+        let hid = MockHid::new(clock.clone());
+        let info = MockHid::sidestick_info("a", 0xBC27);
+        hid.plug(info.clone(), None);
+
+        let mut backend = hid.backend();
+        let mut dev = backend.open(&info).unwrap();
+        dev.write(&[1, 2]).unwrap();
+        clock.advance(Duration::from_millis(50));
+        dev.write(&[3]).unwrap();
+
+        let frames = hid.frames();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].at, Duration::ZERO);
+        assert_eq!(frames[1].at, Duration::from_millis(50));
+        assert_eq!(frames[1].bytes, vec![3]);
+    }
+
+    #[test]
+    fn unplugged_device_rejects_writes() {
+        let hid = MockHid::new(ManualClock::new());
+        let info = MockHid::sidestick_info("a", 0xBC27);
+        hid.plug(info.clone(), None);
+        let mut dev = hid.backend().open(&info).unwrap();
+
+        hid.unplug("a");
+        assert_eq!(dev.write(&[0]), Err(TransportError::Disconnected));
+        assert!(hid.frames().is_empty());
+    }
+}
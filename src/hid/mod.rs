@@ -1,5 +1,16 @@
+pub mod clock;
+pub mod duty;
+pub mod input;
+pub mod lab;
+pub mod mock;
+pub mod pattern;
 pub mod protocol;
+pub mod registry;
+pub mod session;
+pub mod transport;
 
+#[cfg(all(windows, feature = "app"))]
+pub mod hidapi_backend;
 #[cfg(all(windows, feature = "app"))]
 mod win32;
 #[cfg(all(windows, feature = "app"))]
@@ -0,0 +1,304 @@
+//! Haptic patterns: timed intensity keyframes the HID worker plays at its own rate, layered on
+//! top of the continuous intensity from the sim thread.
+//!
+//! A pattern is a YAML or JSON document (`Pattern::parse` reads both):
+//!
+//! ```yaml
+//! keyframes:
+//!   - { at_ms: 0, level: 1.0 }
+//!   - { at_ms: 30, level: 0.3, curve: smooth }
+//!   - { at_ms: 90, level: 0 }
+//! repeat: 2          # play the loop section twice more
+//! loop_from_ms: 30   # start of the loop section; default 0
+//! ```
+//!
+//! Levels are fractions of the peak the pattern is played at. Presets attach patterns to the
+//! flaps and gear bumps (`patterns:`); the sim thread then sends `HidCmd::PlayPattern` instead
+//! of computing the bump frame by frame.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::bindings::Effect;
+
+/// Most keyframes in one pattern.
+pub const MAX_KEYFRAMES: usize = 64;
+/// Longest a pattern may play, repeats included.
+pub const MAX_PATTERN_DURATION: Duration = Duration::from_secs(10);
+/// Most patterns sounding at once; a new one replaces the oldest.
+pub const MAX_PLAYING: usize = 8;
+/// Built-in effects a pattern can replace: the one-shot bumps.
+pub const PATTERN_EFFECTS: [Effect; 2] = [Effect::Flaps, Effect::Gear];
+
+/// Patterns replacing built-in transients, by effect.
+pub type Patterns = BTreeMap<Effect, Pattern>;
+
+/// How the level gets from the previous keyframe to this one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Curve {
+    /// Jump at the keyframe's time.
+    Step,
+    #[default]
+    Linear,
+    /// Ease in and out.
+    Smooth,
+}
+
+impl Curve {
+    fn is_linear(&self) -> bool {
+        *self == Curve::Linear
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Keyframe {
+    pub at_ms: u32,
+    /// `0..=1` of the peak.
+    pub level: f64,
+    #[serde(default, skip_serializing_if = "Curve::is_linear")]
+    pub curve: Curve,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pattern {
+    pub keyframes: Vec<Keyframe>,
+    /// Extra plays of the section from `loop_from_ms` to the last keyframe.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub repeat: u32,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub loop_from_ms: u32,
+}
+
+fn is_zero(n: &u32) -> bool {
+    *n == 0
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum PatternIssue {
+    #[error("{0}")]
+    Syntax(String),
+    #[error("pattern needs at least one keyframe and at most {MAX_KEYFRAMES}")]
+    KeyframeCount,
+    #[error("keyframe at {0} ms: times must increase")]
+    Order(u32),
+    #[error("keyframe at {0} ms: level must be between 0 and 1")]
+    Level(u32),
+    #[error("loop_from_ms must come before the last keyframe")]
+    LoopStart,
+    #[error("pattern plays longer than {} s", MAX_PATTERN_DURATION.as_secs())]
+    TooLong,
+    #[error("only the flaps and gear bumps can play a pattern")]
+    Unsupported,
+}
+
+impl Pattern {
+    /// Read a pattern file (YAML or JSON) and check it.
+    pub fn parse(text: &str) -> Result<Self, PatternIssue> {
+        let pattern: Self =
+            serde_yaml::from_str(text).map_err(|e| PatternIssue::Syntax(e.to_string()))?;
+        pattern.check()?;
+        Ok(pattern)
+    }
+
+    pub fn check(&self) -> Result<(), PatternIssue> {
+        if self.keyframes.is_empty() || self.keyframes.len() > MAX_KEYFRAMES {
+            return Err(PatternIssue::KeyframeCount);
+        }
+        for (i, k) in self.keyframes.iter().enumerate() {
+            if i > 0 && k.at_ms <= self.keyframes[i - 1].at_ms {
+                return Err(PatternIssue::Order(k.at_ms));
+            }
+            if !(0.0..=1.0).contains(&k.level) {
+                return Err(PatternIssue::Level(k.at_ms));
+            }
+        }
+        if self.repeat > 0 && self.loop_from_ms >= self.end_ms() {
+            return Err(PatternIssue::LoopStart);
+        }
+        if self.duration() > MAX_PATTERN_DURATION {
+            return Err(PatternIssue::TooLong);
+        }
+        Ok(())
+    }
+
+    fn end_ms(&self) -> u32 {
+        self.keyframes.last().map_or(0, |k| k.at_ms)
+    }
+
+    /// Total play time, repeats included.
+    pub fn duration(&self) -> Duration {
+        let end = u64::from(self.end_ms());
+        let section = end.saturating_sub(u64::from(self.loop_from_ms));
+        Duration::from_millis(end + u64::from(self.repeat) * section)
+    }
+
+    /// Level at `elapsed` since the start, or `None` once the pattern has finished.
+    pub fn level_at(&self, elapsed: Duration) -> Option<f64> {
+        if elapsed > self.duration() {
+            return None;
+        }
+        let end = f64::from(self.end_ms());
+        let mut t = elapsed.as_secs_f64() * 1000.0;
+        if t > end {
+            let from = f64::from(self.loop_from_ms);
+            let into = (t - end) % (end - from);
+            t = if into > 0.0 { from + into } else { end };
+        }
+        let next = self.keyframes.iter().position(|k| f64::from(k.at_ms) >= t);
+        let Some(i) = next else {
+            return self.keyframes.last().map(|k| k.level);
+        };
+        let b = self.keyframes[i];
+        if b.at_ms == 0 {
+            return Some(b.level);
+        }
+        // Before the first keyframe the level comes up from zero.
+        let a = match i {
+            0 => Keyframe {
+                at_ms: 0,
+                level: 0.0,
+                curve: Curve::Linear,
+            },
+            _ => self.keyframes[i - 1],
+        };
+        let p = (t - f64::from(a.at_ms)) / f64::from(b.at_ms - a.at_ms);
+        let s = match b.curve {
+            Curve::Step => {
+                if p >= 1.0 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Curve::Linear => p,
+            Curve::Smooth => p * p * (3.0 - 2.0 * p),
+        };
+        Some(a.level + (b.level - a.level) * s)
+    }
+}
+
+/// One pattern to start on the HID side (`HidCmd::PlayPattern`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatternPlay {
+    pub pattern: Arc<Pattern>,
+    /// Intensity a level of 1 maps to.
+    pub peak: u8,
+    /// The preset's output limit; patterns never push the total above it (or above the
+    /// continuous intensity, if that is already higher).
+    pub max_output: u8,
+}
+
+/// Patterns currently sounding, sequenced by the HID session.
+#[derive(Debug, Default)]
+pub struct PatternPlayer {
+    playing: Vec<(PatternPlay, Instant)>,
+}
+
+impl PatternPlayer {
+    pub fn start(&mut self, play: PatternPlay, now: Instant) {
+        if self.playing.len() >= MAX_PLAYING {
+            self.playing.remove(0);
+        }
+        self.playing.push((play, now));
+    }
+
+    pub fn stop_all(&mut self) {
+        self.playing.clear();
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.playing.is_empty()
+    }
+
+    /// `base` with every playing pattern added; finished patterns are dropped.
+    pub fn mix(&mut self, base: u8, now: Instant) -> u8 {
+        let mut total = f64::from(base);
+        let mut limit = base;
+        self.playing.retain(|(play, started)| {
+            let Some(level) = play
+                .pattern
+                .level_at(now.saturating_duration_since(*started))
+            else {
+                return false;
+            };
+            total += level * f64::from(play.peak);
+            limit = limit.max(play.max_output);
+            true
+        });
+        total.round().min(f64::from(limit)) as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLUNK: &str = r#"{"keyframes": [
+        {"at_ms": 0, "level": 1.0},
+        {"at_ms": 40, "level": 0.5, "curve": "step"},
+        {"at_ms": 100, "level": 0.0}
+    ], "repeat": 1, "loop_from_ms": 40}"#;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    #[test]
+    fn keyframes_interpolate_and_loop() {
+        let p = Pattern::parse(CLUNK).unwrap();
+        assert_eq!(p.duration(), ms(160));
+        assert_eq!(p.level_at(ms(0)), Some(1.0));
+        assert_eq!(
+            p.level_at(ms(39)),
+            Some(1.0),
+            "step holds until the keyframe"
+        );
+        assert_eq!(p.level_at(ms(40)), Some(0.5));
+        assert_eq!(p.level_at(ms(70)), Some(0.25));
+        assert_eq!(p.level_at(ms(130)), Some(0.25), "second pass of the loop");
+        assert_eq!(p.level_at(ms(161)), None);
+    }
+
+    #[test]
+    fn bad_patterns_are_rejected() {
+        let parse = |yaml: &str| Pattern::parse(yaml).unwrap_err();
+        assert_eq!(parse("keyframes: []"), PatternIssue::KeyframeCount);
+        assert_eq!(
+            parse("keyframes: [{at_ms: 10, level: 1}, {at_ms: 10, level: 0}]"),
+            PatternIssue::Order(10)
+        );
+        assert_eq!(
+            parse("keyframes: [{at_ms: 0, level: 2}]"),
+            PatternIssue::Level(0)
+        );
+        assert_eq!(
+            parse("keyframes: [{at_ms: 0, level: 1}, {at_ms: 1000, level: 0}]\nrepeat: 10"),
+            PatternIssue::TooLong
+        );
+        assert!(matches!(parse("keyframes: 3"), PatternIssue::Syntax(_)));
+    }
+
+    #[test]
+    fn player_layers_patterns_over_the_base_up_to_the_limit() {
+        let pattern = Arc::new(Pattern::parse(CLUNK).unwrap());
+        let t0 = Instant::now();
+        let mut player = PatternPlayer::default();
+        player.start(
+            PatternPlay {
+                pattern,
+                peak: 100,
+                max_output: 120,
+            },
+            t0,
+        );
+        assert_eq!(player.mix(10, t0), 110);
+        assert_eq!(player.mix(50, t0), 120, "capped at max_output");
+        assert_eq!(player.mix(200, t0), 200, "never below the base");
+        assert_eq!(player.mix(10, t0 + ms(200)), 10);
+        assert!(!player.is_active());
+    }
+}
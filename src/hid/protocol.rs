@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::hid::registry::{DeviceRegistry, Hand};
+
 pub const WW_VID: u16 = 0x4098;
 
 pub const WW_PID_URSA_MINOR_AIRBUS_L: u16 = 0xBC27;
@@ -59,44 +61,30 @@ impl SidestickVariant {
     }
 }
 
+// PID-level helpers for the vendor's own sticks, answered by the bundled `DeviceRegistry`.
+
 pub fn is_ursa_minor_left(pid: u16) -> bool {
-    matches!(
-        pid,
-        WW_PID_URSA_MINOR_AIRBUS_L | WW_PID_URSA_MINOR_FIGHTER_L | WW_PID_URSA_MINOR_SPACE_L
-    )
+    DeviceRegistry::bundled()
+        .find(WW_VID, pid)
+        .is_some_and(|d| d.hand == Hand::Left)
 }
 
 pub fn is_ursa_minor_right(pid: u16) -> bool {
-    matches!(
-        pid,
-        WW_PID_URSA_MINOR_AIRBUS_R | WW_PID_URSA_MINOR_FIGHTER_R | WW_PID_URSA_MINOR_SPACE_R
-    )
+    DeviceRegistry::bundled()
+        .find(WW_VID, pid)
+        .is_some_and(|d| d.hand == Hand::Right)
 }
 
 pub fn handed_label(pid: u16) -> &'static str {
-    if is_ursa_minor_right(pid) {
-        "Right"
-    } else if is_ursa_minor_left(pid) {
-        "Left"
-    } else {
-        "Unknown"
-    }
+    DeviceRegistry::bundled().hand(WW_VID, pid).label()
 }
 
 pub fn channel_byte_for(variant: SidestickVariant, pid: u16) -> u8 {
-    variant.channel_for_hand(is_ursa_minor_right(pid))
+    DeviceRegistry::bundled().channel_byte(variant, WW_VID, pid)
 }
 
 pub fn ursa_model_label(variant: SidestickVariant, pid: u16) -> String {
-    let hand = if is_ursa_minor_right(pid) {
-        "R"
-    } else if is_ursa_minor_left(pid) {
-        "L"
-    } else {
-        return format!("UNKNOWN (PID=0x{pid:04X})");
-    };
-
-    format!("URSA MINOR {} {}", variant.label().to_uppercase(), hand)
+    DeviceRegistry::bundled().model_label(variant, WW_VID, pid)
 }
 
 /// Minimum HID output report length for the simapp vibe intensity byte (body offset 7 → frame[8]).
@@ -113,23 +101,7 @@ pub fn build_simapp_vibe_frame(
     out_len: u16,
     intensity: u8,
 ) -> Vec<u8> {
-    let channel = channel_byte_for(variant, pid);
-
-    let body: [u8; 13] = [
-        channel, 0xBF, 0x00, 0x00, 0x03, 0x49, 0x00, intensity, 0, 0, 0, 0, 0,
-    ];
-
-    let len = out_len as usize;
-    let mut buf = vec![0u8; len];
-
-    if len == 0 {
-        return buf;
-    }
-
-    buf[0] = report_id;
-    let copy_len = body.len().min(len.saturating_sub(1));
-    buf[1..1 + copy_len].copy_from_slice(&body[..copy_len]);
-    buf
+    DeviceRegistry::bundled().build_vibe_frame(variant, WW_VID, pid, report_id, out_len, intensity)
 }
 
 #[cfg(test)]
@@ -0,0 +1,455 @@
+//! Data-driven list of supported Winwing devices: IDs, labels, vibe interface and frame layout.
+//! The bundled `devices.yml` can be extended or overridden by `devices.yml` in the presets dir.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::hid::protocol::SidestickVariant;
+use crate::hid::transport::HidDeviceInfo;
+use crate::LogBuffer;
+
+const BUNDLED_YAML: &str = include_str!("devices.yml");
+/// Output rate cap for devices whose entry sets no `max_send_hz`.
+pub const DEFAULT_MAX_SEND_HZ: u32 = 100;
+
+fn default_max_send_hz() -> u32 {
+    DEFAULT_MAX_SEND_HZ
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RegistryError {
+    #[error("invalid device registry YAML: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+    #[error("device 0x{vid:04X}:0x{pid:04X} uses unknown frame template '{frame}'")]
+    UnknownFrame { vid: u16, pid: u16, frame: String },
+    #[error("frame template '{0}' has no intensity byte")]
+    NoIntensitySlot(String),
+    #[error("device 0x{vid:04X}:0x{pid:04X} has no channel for variant {variant}")]
+    MissingChannel {
+        vid: u16,
+        pid: u16,
+        variant: &'static str,
+    },
+    #[error("device registry has no fallback entry")]
+    NoFallback,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Hand {
+    Left,
+    Right,
+    #[default]
+    Unknown,
+}
+
+impl Hand {
+    pub fn label(self) -> &'static str {
+        match self {
+            Hand::Left => "Left",
+            Hand::Right => "Right",
+            Hand::Unknown => "Unknown",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FrameSlot {
+    Channel,
+    Intensity,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FrameByte {
+    Literal(u8),
+    Slot(FrameSlot),
+}
+
+/// Output report body following the report ID byte.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FrameTemplate {
+    pub min_len: u16,
+    pub body: Vec<FrameByte>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ChannelSpec {
+    Fixed(u8),
+    PerVariant(HashMap<SidestickVariant, u8>),
+}
+
+impl ChannelSpec {
+    fn get(&self, variant: SidestickVariant) -> Option<u8> {
+        match self {
+            ChannelSpec::Fixed(c) => Some(*c),
+            ChannelSpec::PerVariant(map) => map.get(&variant).copied(),
+        }
+    }
+}
+
+/// 16-bit little-endian axis at `offset` (report ID is byte 0).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AxisField {
+    pub offset: usize,
+    pub min: u16,
+    pub max: u16,
+    #[serde(default)]
+    pub invert: bool,
+}
+
+/// `count` button bits starting at bit 0 of `offset`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ButtonField {
+    pub offset: usize,
+    pub count: u8,
+}
+
+/// Where stick position and buttons live in the input report.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputLayout {
+    pub report_id: u8,
+    pub x: AxisField,
+    pub y: AxisField,
+    pub buttons: ButtonField,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceSpec {
+    #[serde(default)]
+    pub vid: u16,
+    #[serde(default)]
+    pub pid: u16,
+    pub model: String,
+    #[serde(default)]
+    pub hand: Hand,
+    pub channel: ChannelSpec,
+    pub vibe_usage_page: u16,
+    pub vibe_usage: u16,
+    pub frame: String,
+    /// Input report layout; devices without one are never read.
+    #[serde(default)]
+    pub input: Option<InputLayout>,
+    /// Highest rate the device takes vibe reports at.
+    #[serde(default = "default_max_send_hz")]
+    pub max_send_hz: u32,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RegistryFile {
+    #[serde(default)]
+    frame_templates: HashMap<String, FrameTemplate>,
+    #[serde(default)]
+    fallback: Option<DeviceSpec>,
+    #[serde(default)]
+    devices: Vec<DeviceSpec>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DeviceRegistry {
+    frame_templates: HashMap<String, FrameTemplate>,
+    fallback: DeviceSpec,
+    devices: Vec<DeviceSpec>,
+}
+
+impl DeviceRegistry {
+    /// The registry compiled into the binary.
+    pub fn bundled() -> Arc<DeviceRegistry> {
+        static BUNDLED: OnceLock<Arc<DeviceRegistry>> = OnceLock::new();
+        BUNDLED
+            .get_or_init(|| {
+                Arc::new(Self::from_yaml(BUNDLED_YAML).expect("bundled devices.yml is valid"))
+            })
+            .clone()
+    }
+
+    pub fn from_yaml(text: &str) -> Result<Self, RegistryError> {
+        let file: RegistryFile = serde_yaml::from_str(text)?;
+        let reg = Self {
+            frame_templates: file.frame_templates,
+            fallback: file.fallback.ok_or(RegistryError::NoFallback)?,
+            devices: file.devices,
+        };
+        reg.validate()?;
+        Ok(reg)
+    }
+
+    /// Merge an override file: matching vid/pid entries are replaced, new ones appended.
+    pub fn with_override_yaml(&self, text: &str) -> Result<Self, RegistryError> {
+        let file: RegistryFile = serde_yaml::from_str(text)?;
+        let mut reg = self.clone();
+        reg.frame_templates.extend(file.frame_templates);
+        if let Some(fallback) = file.fallback {
+            reg.fallback = fallback;
+        }
+        for spec in file.devices {
+            match reg
+                .devices
+                .iter_mut()
+                .find(|d| d.vid == spec.vid && d.pid == spec.pid)
+            {
+                Some(existing) => *existing = spec,
+                None => reg.devices.push(spec),
+            }
+        }
+        reg.validate()?;
+        Ok(reg)
+    }
+
+    /// Bundled registry plus `path` when it exists; a broken override is logged and ignored.
+    pub fn load_with_override(path: &Path, logs: &LogBuffer) -> Arc<DeviceRegistry> {
+        let bundled = Self::bundled();
+        if !path.exists() {
+            return bundled;
+        }
+
+        let merged = fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| bundled.with_override_yaml(&text).map_err(|e| e.to_string()));
+        match merged {
+            Ok(reg) => {
+                logs.push(format!(
+                    "HID: device registry override loaded from {} ({} devices)",
+                    path.display(),
+                    reg.devices.len()
+                ));
+                Arc::new(reg)
+            }
+            Err(e) => {
+                logs.push(format!(
+                    "HID: device registry override {} ignored: {e}",
+                    path.display()
+                ));
+                bundled
+            }
+        }
+    }
+
+    fn validate(&self) -> Result<(), RegistryError> {
+        for (name, template) in &self.frame_templates {
+            if !template
+                .body
+                .contains(&FrameByte::Slot(FrameSlot::Intensity))
+            {
+                return Err(RegistryError::NoIntensitySlot(name.clone()));
+            }
+        }
+        for spec in self.devices.iter().chain(std::iter::once(&self.fallback)) {
+            if !self.frame_templates.contains_key(&spec.frame) {
+                return Err(RegistryError::UnknownFrame {
+                    vid: spec.vid,
+                    pid: spec.pid,
+                    frame: spec.frame.clone(),
+                });
+            }
+            if let Some(variant) = SidestickVariant::ALL
+                .into_iter()
+                .find(|v| spec.channel.get(*v).is_none())
+            {
+                return Err(RegistryError::MissingChannel {
+                    vid: spec.vid,
+                    pid: spec.pid,
+                    variant: variant.label(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn devices(&self) -> &[DeviceSpec] {
+        &self.devices
+    }
+
+    /// Whether any listed device uses this vendor ID (only those are opened).
+    pub fn is_known_vendor(&self, vid: u16) -> bool {
+        self.devices.iter().any(|d| d.vid == vid)
+    }
+
+    pub fn find(&self, vid: u16, pid: u16) -> Option<&DeviceSpec> {
+        self.devices.iter().find(|d| d.vid == vid && d.pid == pid)
+    }
+
+    /// Entry for the device, or the fallback entry for unlisted PIDs.
+    pub fn spec(&self, vid: u16, pid: u16) -> &DeviceSpec {
+        self.find(vid, pid).unwrap_or(&self.fallback)
+    }
+
+    pub fn hand(&self, vid: u16, pid: u16) -> Hand {
+        self.spec(vid, pid).hand
+    }
+
+    pub fn channel_byte(&self, variant: SidestickVariant, vid: u16, pid: u16) -> u8 {
+        self.spec(vid, pid).channel.get(variant).unwrap_or_default()
+    }
+
+    pub fn model_label(&self, variant: SidestickVariant, vid: u16, pid: u16) -> String {
+        self.spec(vid, pid)
+            .model
+            .replace("{variant}", &variant.label().to_uppercase())
+            .replace("{pid}", &format!("0x{pid:04X}"))
+    }
+
+    pub fn input_layout(&self, vid: u16, pid: u16) -> Option<&InputLayout> {
+        self.find(vid, pid).and_then(|d| d.input.as_ref())
+    }
+
+    pub fn max_send_hz(&self, vid: u16, pid: u16) -> u32 {
+        self.spec(vid, pid).max_send_hz.max(1)
+    }
+
+    pub fn frame_template(&self, vid: u16, pid: u16) -> Option<&FrameTemplate> {
+        self.frame_templates.get(&self.spec(vid, pid).frame)
+    }
+
+    /// The interface of a listed vendor that accepts vibe output reports.
+    pub fn is_vibe_interface(&self, info: &HidDeviceInfo) -> bool {
+        if !self.is_known_vendor(info.vendor_id) {
+            return false;
+        }
+        let spec = self.spec(info.vendor_id, info.product_id);
+        info.usage_page == spec.vibe_usage_page && info.usage == spec.vibe_usage
+    }
+
+    /// Report ID followed by the template body, truncated or zero-padded to `out_len`.
+    pub fn build_vibe_frame(
+        &self,
+        variant: SidestickVariant,
+        vid: u16,
+        pid: u16,
+        report_id: u8,
+        out_len: u16,
+        intensity: u8,
+    ) -> Vec<u8> {
+        let len = out_len as usize;
+        let mut buf = vec![0u8; len];
+        if len == 0 {
+            return buf;
+        }
+
+        buf[0] = report_id;
+        let channel = self.channel_byte(variant, vid, pid);
+        let body = self
+            .frame_template(vid, pid)
+            .map(|t| t.body.as_slice())
+            .unwrap_or_default();
+        for (dst, byte) in buf[1..].iter_mut().zip(body) {
+            *dst = match byte {
+                FrameByte::Literal(b) => *b,
+                FrameByte::Slot(FrameSlot::Channel) => channel,
+                FrameByte::Slot(FrameSlot::Intensity) => intensity,
+            };
+        }
+        buf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hid::protocol::{WW_PID_URSA_MINOR_AIRBUS_R, WW_VID};
+
+    #[test]
+    fn bundled_registry_lists_all_ursa_minor_pids() {
+        let reg = DeviceRegistry::bundled();
+        assert_eq!(reg.devices().len(), 6);
+        assert!(reg.is_known_vendor(WW_VID));
+        assert_eq!(reg.hand(WW_VID, WW_PID_URSA_MINOR_AIRBUS_R), Hand::Right);
+        assert_eq!(reg.hand(WW_VID, 0x1234), Hand::Unknown);
+        assert!(
+            reg.devices().iter().all(|d| d.input.is_none()),
+            "no input layout ships until one is verified on hardware"
+        );
+        assert_eq!(
+            reg.model_label(SidestickVariant::Airbus, WW_VID, 0x1234),
+            "UNKNOWN (PID=0x1234)"
+        );
+    }
+
+    #[test]
+    fn override_adds_device_with_own_template() {
+        let reg = DeviceRegistry::bundled()
+            .with_override_yaml(
+                r#"
+frame_templates:
+  short:
+    min_len: 4
+    body: [0xAA, intensity, channel]
+devices:
+  - { vid: 0x4098, pid: 0xBE01, model: "ORION 2 BASE", channel: 0x20,
+      vibe_usage_page: 0xFF00, vibe_usage: 0x0001, frame: short }
+"#,
+            )
+            .unwrap();
+
+        assert_eq!(reg.devices().len(), 7);
+        let frame = reg.build_vibe_frame(SidestickVariant::Fighter, WW_VID, 0xBE01, 0x05, 5, 0x40);
+        assert_eq!(frame, vec![0x05, 0xAA, 0x40, 0x20, 0x00]);
+
+        let info = HidDeviceInfo {
+            path: "p".into(),
+            vendor_id: WW_VID,
+            product_id: 0xBE01,
+            interface_number: 1,
+            usage_page: 0xFF00,
+            usage: 0x0001,
+            serial: String::new(),
+        };
+        assert!(reg.is_vibe_interface(&info));
+    }
+
+    #[test]
+    fn override_replaces_matching_entry() {
+        let reg = DeviceRegistry::bundled()
+            .with_override_yaml(
+                r#"
+devices:
+  - { vid: 0x4098, pid: 0xBC28, model: "MY STICK", hand: right, channel: 0x30,
+      vibe_usage_page: 0x0001, vibe_usage: 0x0004, frame: ursa_simapp }
+"#,
+            )
+            .unwrap();
+        assert_eq!(reg.devices().len(), 6);
+        assert_eq!(
+            reg.channel_byte(SidestickVariant::Space, WW_VID, WW_PID_URSA_MINOR_AIRBUS_R),
+            0x30
+        );
+    }
+
+    #[test]
+    fn override_with_unknown_template_is_rejected() {
+        let err = DeviceRegistry::bundled()
+            .with_override_yaml(
+                r#"
+devices:
+  - { vid: 0x4098, pid: 0xBE02, model: "X", channel: 1,
+      vibe_usage_page: 1, vibe_usage: 4, frame: nope }
+"#,
+            )
+            .unwrap_err();
+        assert!(matches!(err, RegistryError::UnknownFrame { .. }));
+    }
+
+    #[test]
+    fn load_with_override_falls_back_to_bundled_on_error() {
+        let dir = std::env::temp_dir().join(format!("ursa-registry-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("devices.yml");
+        fs::write(&path, "devices: [ {").unwrap();
+
+        let logs = LogBuffer::default();
+        let reg = DeviceRegistry::load_with_override(&path, &logs);
+        assert_eq!(reg.devices().len(), 6);
+        assert!(logs
+            .snapshot()
+            .iter()
+            .any(|l| l.contains("override") && l.contains("ignored")));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
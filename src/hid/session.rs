@@ -0,0 +1,1148 @@
+//! HID worker state machine: device lifecycle, `HidCmd` handling and the send cadence.
+//! Hardware-free so it can be driven by `hid::mock` with a `ManualClock`.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
+
+use crate::hid::clock::Clock;
+use crate::hid::duty::DutyLimiter;
+use crate::hid::input::{self, HandDetector, InputShared, StickInput};
+use crate::hid::lab::{self, Direction, LabShared, ReportKind};
+use crate::hid::pattern::PatternPlayer;
+use crate::hid::protocol::{SidestickVariant, WW_VID};
+use crate::hid::registry::DeviceRegistry;
+use crate::hid::transport::{HidBackend, HidDeviceInfo, HidTransport, TransportError};
+use crate::{ControllerShared, DeviceHealth, HidCmd, HidEvent, LogBuffer};
+
+pub const DEFAULT_SEND_HZ: u32 = 50;
+pub const MIN_SEND_HZ: u32 = 10;
+pub const MAX_SEND_HZ: u32 = 250;
+/// Send cadence while a haptic pattern is playing (unless the configured rate is higher).
+pub const PATTERN_SEND_INTERVAL: Duration = Duration::from_millis(10);
+/// Output changes at least this large go out without waiting for the cadence.
+pub const IMMEDIATE_CHANGE: u8 = 48;
+/// Unchanged output is sent again this often, in case a frame was lost.
+pub const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(1);
+/// Without a fresh `SendIntensity` for this long, the output fades to zero.
+pub const DEFAULT_WATCHDOG_TIMEOUT: Duration = Duration::from_millis(500);
+pub const MIN_WATCHDOG_TIMEOUT: Duration = Duration::from_millis(100);
+pub const MAX_WATCHDOG_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long the watchdog takes to ramp the output down.
+pub const WATCHDOG_FADE: Duration = Duration::from_millis(250);
+/// Fallback rescan while connected, for backends without device-change notifications.
+pub const IDLE_RESCAN_INTERVAL: Duration = Duration::from_secs(10);
+pub const RECONNECT_BACKOFF_MIN: Duration = Duration::from_millis(250);
+pub const RECONNECT_BACKOFF_MAX: Duration = Duration::from_secs(8);
+/// Consecutive failed writes before a handle is treated as stale and dropped.
+pub const MAX_CONSECUTIVE_WRITE_FAILURES: u32 = 3;
+const CMD_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// How often a pending lab input read checks for its report.
+const LAB_READ_POLL: Duration = Duration::from_millis(5);
+const MISSING_LOG_INTERVAL: Duration = Duration::from_secs(5);
+const STATUS_LOG_INTERVAL: Duration = Duration::from_millis(900);
+const METRICS_LOG_INTERVAL: Duration = Duration::from_secs(60);
+/// Weight of the newest sample in the smoothed latency and send interval.
+const METRICS_SMOOTHING: f64 = 0.1;
+
+/// Acknowledgement pulse for stick-button actions.
+pub const ACK_PULSE_INTENSITY: u8 = 140;
+pub const ACK_PULSE_DURATION: Duration = Duration::from_millis(120);
+
+/// How often the stick's input reports are read, with or without commands coming in.
+const INPUT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+const INPUT_REPORT_BUF_LEN: usize = 64;
+
+/// Report length / ID used when the OS caps query fails.
+const FALLBACK_CAPS: (u16, u8) = (14, 0x02);
+
+struct HidEntry<D> {
+    dev: D,
+    path: String,
+    vid: u16,
+    pid: u16,
+    serial: String,
+    vibe: bool,
+    out_len: u16,
+    report_id: u8,
+    health: DeviceHealth,
+    consecutive_failures: u32,
+    stale: bool,
+    reads_input: bool,
+    hand: HandDetector,
+    last_input: Option<StickInput>,
+    last_vibe_write: Option<Instant>,
+}
+
+fn smooth(avg: Duration, sample: Duration) -> Duration {
+    if avg.is_zero() {
+        sample
+    } else {
+        avg.mul_f64(1.0 - METRICS_SMOOTHING) + sample.mul_f64(METRICS_SMOOTHING)
+    }
+}
+
+/// Time between reports at `hz`.
+fn hz_interval(hz: u32) -> Duration {
+    Duration::from_secs(1) / hz.max(1)
+}
+
+impl<D> HidEntry<D> {
+    /// Fold one vibe write started at `at` and taking `took` into the rate and latency figures.
+    fn record_vibe_timing(&mut self, at: Instant, took: Duration) {
+        let h = &mut self.health;
+        h.write_latency = smooth(h.write_latency, took);
+        h.write_latency_max = h.write_latency_max.max(took);
+        if let Some(prev) = self.last_vibe_write {
+            h.send_interval = smooth(h.send_interval, at.saturating_duration_since(prev));
+        }
+        self.last_vibe_write = Some(at);
+    }
+
+    fn record_write(&mut self, result: &Result<usize, TransportError>, expected: usize) -> bool {
+        let err = match result {
+            Ok(n) if *n == expected => None,
+            Ok(n) => Some(format!("short write ({n}/{expected} bytes)")),
+            Err(e) => Some(e.to_string()),
+        };
+        match err {
+            None => {
+                self.health.writes_ok += 1;
+                self.consecutive_failures = 0;
+                true
+            }
+            Some(e) => {
+                self.health.writes_failed += 1;
+                self.health.last_error = Some(e);
+                self.consecutive_failures += 1;
+                if matches!(result, Err(TransportError::Disconnected))
+                    || self.consecutive_failures >= MAX_CONSECUTIVE_WRITE_FAILURES
+                {
+                    self.stale = true;
+                }
+                false
+            }
+        }
+    }
+}
+
+/// A lab input read waiting for its report.
+struct LabRead {
+    path: String,
+    len: usize,
+    until: Instant,
+}
+
+fn is_vibe_interface<D>(d: &HidEntry<D>) -> bool {
+    d.vibe
+}
+
+/// Whether two interfaces belong to the same physical device.
+fn same_device<D>(a: &HidEntry<D>, b: &HidEntry<D>) -> bool {
+    (a.vid, a.pid, &a.serial) == (b.vid, b.pid, &b.serial)
+}
+
+/// True when `then` is unset or at least `interval` old.
+fn interval_elapsed(now: Instant, then: Option<Instant>, interval: Duration) -> bool {
+    then.is_none_or(|t| now.saturating_duration_since(t) >= interval)
+}
+
+pub struct HidSession<B: HidBackend, C: Clock> {
+    backend: B,
+    clock: C,
+    controller: ControllerShared,
+    events: Option<Sender<HidEvent>>,
+    registry: Arc<DeviceRegistry>,
+    lab: Option<LabShared>,
+    lab_reads: Vec<LabRead>,
+    input: Option<InputShared>,
+    logs: LogBuffer,
+    verbose: bool,
+
+    devices: Vec<HidEntry<B::Device>>,
+    next_scan: Option<Instant>,
+    backoff: Duration,
+    last_status_log: Option<Instant>,
+    last_missing_log: Option<Instant>,
+    prev_scan_sig: String,
+    prev_vibe_ready: bool,
+
+    desired_intensity: u8,
+    /// When the last `SendIntensity` arrived, for the output watchdog.
+    intensity_at: Option<Instant>,
+    watchdog_timeout: Duration,
+    stalled: bool,
+    duty: DutyLimiter,
+    /// Percent let through by the duty limiter, while it is limiting.
+    duty_limit: Option<u8>,
+    last_sent_intensity: Option<u8>,
+    /// Start of the current cadence slot.
+    last_send: Option<Instant>,
+    /// When a vibe frame last went out, for the keep-alive.
+    last_write: Option<Instant>,
+    send_hz: u32,
+    last_metrics_log: Option<Instant>,
+    hold: bool,
+    pulse_until: Option<Instant>,
+    patterns: PatternPlayer,
+    variant: SidestickVariant,
+    /// A changed output is waiting for the next cadence slot.
+    held_back: bool,
+    /// Set once the final zero frame went out; nothing is sent after that.
+    stopped: bool,
+}
+
+impl<B: HidBackend, C: Clock> HidSession<B, C> {
+    pub fn new(backend: B, clock: C, controller: ControllerShared, logs: LogBuffer) -> Self {
+        Self {
+            backend,
+            clock,
+            controller,
+            events: None,
+            registry: DeviceRegistry::bundled(),
+            lab: None,
+            lab_reads: Vec::new(),
+            input: None,
+            logs,
+            verbose: std::env::var_os("URSA_VERBOSE_HID").is_some(),
+            devices: Vec::new(),
+            next_scan: None,
+            backoff: RECONNECT_BACKOFF_MIN,
+            last_status_log: None,
+            last_missing_log: None,
+            prev_scan_sig: String::new(),
+            prev_vibe_ready: false,
+            desired_intensity: 0,
+            intensity_at: None,
+            watchdog_timeout: DEFAULT_WATCHDOG_TIMEOUT,
+            stalled: false,
+            duty: DutyLimiter::default(),
+            duty_limit: None,
+            last_sent_intensity: None,
+            last_send: None,
+            last_write: None,
+            send_hz: DEFAULT_SEND_HZ,
+            last_metrics_log: None,
+            hold: false,
+            pulse_until: None,
+            patterns: PatternPlayer::default(),
+            variant: SidestickVariant::Airbus,
+            held_back: false,
+            stopped: false,
+        }
+    }
+
+    /// Receive `HidEvent::Connected` / `Disconnected` as physical devices come and go, one
+    /// event per device however many interfaces it has.
+    pub fn set_event_sink(&mut self, tx: Sender<HidEvent>) {
+        self.events = Some(tx);
+    }
+
+    /// Replace the bundled device registry (e.g. with user overrides merged in).
+    pub fn set_registry(&mut self, registry: Arc<DeviceRegistry>) {
+        self.registry = registry;
+    }
+
+    /// Record raw sends and report reads in the protocol lab session log.
+    pub fn set_lab_log(&mut self, lab: LabShared) {
+        self.lab = Some(lab);
+    }
+
+    /// Publish decoded stick input (axes, buttons, hand-on) here.
+    pub fn set_input_sink(&mut self, input: InputShared) {
+        self.input = Some(input);
+    }
+
+    pub fn device_count(&self) -> usize {
+        self.devices.len()
+    }
+
+    pub fn variant(&self) -> SidestickVariant {
+        self.variant
+    }
+
+    pub fn is_held(&self) -> bool {
+        self.hold
+    }
+
+    pub fn is_playing_pattern(&self) -> bool {
+        self.patterns.is_active()
+    }
+
+    /// Configured rate capped by the slowest open vibe interface.
+    pub fn send_hz(&self) -> u32 {
+        self.send_hz.min(self.device_max_hz())
+    }
+
+    fn device_max_hz(&self) -> u32 {
+        self.devices
+            .iter()
+            .filter(|d| is_vibe_interface(d))
+            .map(|d| self.registry.max_send_hz(d.vid, d.pid))
+            .min()
+            .unwrap_or(MAX_SEND_HZ)
+    }
+
+    fn send_interval(&self) -> Duration {
+        let interval = hz_interval(self.send_hz());
+        if self.patterns.is_active() {
+            interval
+                .min(PATTERN_SEND_INTERVAL)
+                .max(hz_interval(self.device_max_hz()))
+        } else {
+            interval
+        }
+    }
+
+    /// When the next enumeration is scheduled (reconnect backoff or idle rescan).
+    pub fn next_scan(&self) -> Option<Instant> {
+        self.next_scan
+    }
+
+    /// Blocking worker loop; returns when every `HidCmd` sender is dropped.
+    pub fn run(&mut self, rx: &Receiver<HidCmd>) {
+        self.maybe_rescan();
+        loop {
+            match self.clock.recv_cmd(rx, self.next_wakeup()) {
+                Ok(cmd) => self.handle(cmd),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.logs.push("HID: channel disconnected → worker exit");
+                    break;
+                }
+            }
+            if self.stopped {
+                self.logs.push("HID: shutdown → worker exit");
+                break;
+            }
+            self.tick();
+        }
+    }
+
+    pub fn handle(&mut self, cmd: HidCmd) {
+        match cmd {
+            HidCmd::SendIntensity(level) => {
+                self.desired_intensity = level;
+                self.intensity_at = Some(self.clock.now());
+                if self.stalled {
+                    self.stalled = false;
+                    self.controller.set_output_stalled(false);
+                    self.logs.push("HID: intensity stream resumed");
+                }
+                let last = i16::from(self.last_sent_intensity.unwrap_or(255));
+                if self.verbose && (i16::from(level) - last).abs() >= 15 {
+                    self.logs.push(format!("HID: cmd SendIntensity({level})"));
+                }
+            }
+            HidCmd::SendRaw(bytes) => {
+                self.logs
+                    .push(format!("HID: cmd SendRaw(len={})", bytes.len()));
+                for d in &mut self.devices {
+                    let result = d.dev.write(&bytes);
+                    if let Err(e) = &result {
+                        self.logs.push(format!(
+                            "HID: raw write FAILED (PID=0x{:04X} {}, path='{}'): {}",
+                            d.pid,
+                            self.registry.model_label(self.variant, d.vid, d.pid),
+                            d.path,
+                            e
+                        ));
+                    }
+                    if let Some(lab) = &self.lab {
+                        lab.lock().record(
+                            &d.path,
+                            Direction::Sent,
+                            &bytes,
+                            result.as_ref().err().map(|e| e.to_string()),
+                        );
+                    }
+                    d.record_write(&result, bytes.len());
+                }
+                self.drop_stale_devices();
+                self.publish();
+            }
+            HidCmd::SendRawTo { path, bytes } => {
+                let lab = self.lab.get_or_insert_with(LabShared::default).clone();
+                let mut log = lab.lock();
+                match self.devices.iter_mut().find(|d| d.path == path) {
+                    Some(d) => {
+                        let result = lab::send_logged(&mut d.dev, &path, &bytes, &mut log);
+                        d.record_write(&result, bytes.len());
+                    }
+                    None => log.record(
+                        &path,
+                        Direction::Sent,
+                        &bytes,
+                        Some("interface not open".to_string()),
+                    ),
+                }
+                drop(log);
+                self.drop_stale_devices();
+                self.publish();
+            }
+            // Polled from the run loop until it arrives, so sending goes on while it waits.
+            HidCmd::ReadReport {
+                path,
+                kind: ReportKind::Input,
+                len,
+                ..
+            } if self.devices.iter().any(|d| d.path == path) => {
+                let until = self.clock.now() + lab::DEFAULT_READ_TIMEOUT;
+                self.lab_reads.push(LabRead { path, len, until });
+                self.poll_lab_reads();
+            }
+            HidCmd::ReadReport {
+                path,
+                kind,
+                report_id,
+                len,
+            } => {
+                let lab = self.lab.get_or_insert_with(LabShared::default).clone();
+                let mut log = lab.lock();
+                match self.devices.iter_mut().find(|d| d.path == path) {
+                    Some(d) => {
+                        let _ = lab::read_logged(&mut d.dev, &path, kind, report_id, len, &mut log);
+                    }
+                    None => log.record(
+                        &path,
+                        Direction::Received(kind),
+                        &[],
+                        Some("interface not open".to_string()),
+                    ),
+                }
+            }
+            HidCmd::StopAll => {
+                self.logs.push("HID: cmd StopAll");
+                self.desired_intensity = 0;
+                self.patterns.stop_all();
+                self.last_send = None;
+            }
+            HidCmd::SetHold(hold) => {
+                self.hold = hold;
+                self.logs.push(format!("HID: cmd SetHold({hold})"));
+                if hold {
+                    self.patterns.stop_all();
+                    let _ = self.send_vibe(0);
+                    self.last_sent_intensity = Some(0);
+                }
+            }
+            HidCmd::SetSendRate(hz) => {
+                self.send_hz = hz.clamp(MIN_SEND_HZ, MAX_SEND_HZ);
+                let capped = self.send_hz();
+                self.logs.push(if capped < self.send_hz {
+                    format!(
+                        "HID: send rate → {} Hz (capped at {capped} Hz by the device)",
+                        self.send_hz
+                    )
+                } else {
+                    format!("HID: send rate → {} Hz", self.send_hz)
+                });
+            }
+            HidCmd::SetWatchdogTimeout(timeout) => {
+                self.watchdog_timeout = timeout.clamp(MIN_WATCHDOG_TIMEOUT, MAX_WATCHDOG_TIMEOUT);
+                self.logs.push(format!(
+                    "HID: output watchdog → {} ms",
+                    self.watchdog_timeout.as_millis()
+                ));
+            }
+            HidCmd::SetDutyLimits(limits) => {
+                self.duty.set_limits(limits);
+                let l = self.duty.limits();
+                self.logs.push(if l.enabled {
+                    format!(
+                        "HID: motor duty limits → {}–{}% over {} s, floor {}%",
+                        l.onset_pct, l.full_pct, l.window_s, l.floor_pct
+                    )
+                } else {
+                    "HID: motor duty limits → off".to_string()
+                });
+            }
+            HidCmd::Shutdown(reply) => {
+                let stopped = self.stop_outputs();
+                let _ = reply.send(stopped);
+            }
+            HidCmd::AckPulse => {
+                self.pulse_until = Some(self.clock.now() + ACK_PULSE_DURATION);
+                self.last_send = None;
+            }
+            HidCmd::PlayPattern(play) => {
+                if self.verbose {
+                    self.logs.push(format!(
+                        "HID: cmd PlayPattern(peak={}, {} ms)",
+                        play.peak,
+                        play.pattern.duration().as_millis()
+                    ));
+                }
+                if !self.hold {
+                    self.patterns.start(play, self.clock.now());
+                    self.last_send = None;
+                }
+            }
+            HidCmd::ReopenDevices => {
+                self.logs.push("HID: cmd ReopenDevices");
+                self.next_scan = None;
+                self.backoff = RECONNECT_BACKOFF_MIN;
+                self.maybe_rescan();
+            }
+            HidCmd::SetSidestickVariant(v) => {
+                self.variant = v;
+                let (left, right) = v.channel_pair();
+                self.logs.push(format!(
+                    "HID: sidestick variant → {} (channel L=0x{left:02X}, R=0x{right:02X})",
+                    v.label()
+                ));
+                self.last_sent_intensity = None;
+                self.publish();
+            }
+        }
+    }
+
+    /// Rescan when due, then send the current intensity (plus any playing patterns) when it
+    /// changed and the cadence allows, at once when it jumped, or as a keep-alive.
+    pub fn tick(&mut self) {
+        if self.stopped {
+            return;
+        }
+        self.maybe_rescan();
+        self.poll_lab_reads();
+        self.poll_inputs();
+        self.maybe_log_metrics();
+
+        let now = self.clock.now();
+        if self.pulse_until.is_some_and(|until| now >= until) {
+            self.pulse_until = None;
+        }
+        let gain = self.duty_gain(now);
+        let out = if self.pulse_until.is_some() {
+            ACK_PULSE_INTENSITY
+        } else if self.hold {
+            0
+        } else {
+            let base = self.watchdog_level(now);
+            (f64::from(self.patterns.mix(base, now)) * gain).round() as u8
+        };
+
+        let changed = self.last_sent_intensity != Some(out);
+        let jump = changed
+            && self
+                .last_sent_intensity
+                .is_some_and(|last| last.abs_diff(out) >= IMMEDIATE_CHANGE)
+            && interval_elapsed(now, self.last_send, hz_interval(self.device_max_hz()));
+        if !jump && !interval_elapsed(now, self.last_send, self.send_interval()) {
+            self.held_back = changed;
+            return;
+        }
+        self.held_back = false;
+
+        let keepalive = !changed
+            && self.prev_vibe_ready
+            && interval_elapsed(now, self.last_write, KEEPALIVE_INTERVAL);
+        if changed || keepalive {
+            let (ok, fail) = self.send_vibe(out);
+            if ok > 0 {
+                self.last_write = Some(now);
+            }
+
+            // Patterns send at up to 100 Hz; log those frames on the status cadence only.
+            // Keep-alives are only logged when they fail.
+            let noteworthy = (out > 0 && !self.patterns.is_active())
+                || ok == 0
+                || interval_elapsed(now, self.last_status_log, STATUS_LOG_INTERVAL);
+            if fail > 0 || (changed && noteworthy) {
+                self.logs.push(format!(
+                    "HID: send intensity {out} → ok={ok} fail={fail} (devs={}, hold={})",
+                    self.devices.len(),
+                    self.hold,
+                ));
+                self.last_status_log = Some(now);
+            }
+
+            // Keep retrying while nothing accepted the frame.
+            self.last_sent_intensity = if ok > 0 || fail == 0 { Some(out) } else { None };
+        }
+        self.last_send = Some(self.clock.now());
+    }
+
+    /// How long `run` may wait for a command before a tick is due: for a held-back change, a
+    /// playing pattern or pulse, the watchdog, the keep-alive or a rescan. Output only goes out
+    /// on a cadence slot, so nothing is due before the next one.
+    fn next_wakeup(&self) -> Duration {
+        let now = self.clock.now();
+        let slot = self.last_send.map_or(now, |t| t + self.send_interval());
+        // Output changes can go out no earlier than the next send slot.
+        let mut send_due = None::<Instant>;
+        let mut send_at = |at: Instant| send_due = Some(send_due.map_or(at, |d| d.min(at)));
+        let fading = self.stalled
+            && self
+                .intensity_at
+                .is_some_and(|t| now < t + self.watchdog_timeout + WATCHDOG_FADE);
+        if self.held_back || self.patterns.is_active() || fading {
+            send_at(slot);
+        }
+        if let Some(until) = self.pulse_until {
+            send_at(until);
+        }
+        if let Some(at) = self
+            .intensity_at
+            .filter(|_| !self.stalled && self.desired_intensity > 0)
+        {
+            send_at(at + self.watchdog_timeout);
+        }
+        if let Some(write) = self.last_write.filter(|_| self.prev_vibe_ready) {
+            send_at(write + KEEPALIVE_INTERVAL);
+        }
+
+        let mut due = now + CMD_POLL_INTERVAL;
+        if let Some(send) = send_due {
+            due = due.min(send.max(slot));
+        }
+        if let Some(scan) = self.next_scan {
+            due = due.min(scan);
+        }
+        if !self.lab_reads.is_empty() {
+            due = due.min(now + LAB_READ_POLL);
+        }
+        if self.input.is_some() && self.devices.iter().any(|d| d.reads_input) {
+            due = due.min(now + INPUT_POLL_INTERVAL);
+        }
+        due.saturating_duration_since(now)
+    }
+
+    /// `desired_intensity`, ramped to zero once it has gone unrefreshed for the watchdog timeout.
+    fn watchdog_level(&mut self, now: Instant) -> u8 {
+        let Some(at) = self.intensity_at else {
+            return self.desired_intensity;
+        };
+        let late = now
+            .saturating_duration_since(at)
+            .saturating_sub(self.watchdog_timeout);
+        if late.is_zero() || self.desired_intensity == 0 {
+            return self.desired_intensity;
+        }
+        if !self.stalled {
+            self.stalled = true;
+            self.controller.set_output_stalled(true);
+            self.logs.push(format!(
+                "HID: no intensity for {} ms → fading output {} to zero",
+                self.watchdog_timeout.as_millis(),
+                self.desired_intensity
+            ));
+        }
+        let left = 1.0 - late.as_secs_f64() / WATCHDOG_FADE.as_secs_f64();
+        (f64::from(self.desired_intensity) * left.max(0.0)).round() as u8
+    }
+
+    /// Share of the requested output the duty limiter lets through now; publishes and logs
+    /// when limiting starts, changes noticeably or ends.
+    fn duty_gain(&mut self, now: Instant) -> f64 {
+        self.duty.advance(now);
+        let gain = self.duty.gain();
+        let limit = (gain < 1.0).then(|| (gain * 100.0).round() as u8);
+        let noticeable = match (self.duty_limit, limit) {
+            (Some(was), Some(pct)) => was.abs_diff(pct) >= 10,
+            (was, pct) => was != pct,
+        };
+        if noticeable {
+            let window_s = self.duty.limits().window_s;
+            self.logs.push(match limit {
+                Some(pct) => format!(
+                    "HID: motor duty limiter active (avg {:.0}% over {window_s} s → output at {pct}%)",
+                    self.duty.average_pct()
+                ),
+                None => format!(
+                    "HID: motor duty limiter released (avg {:.0}% over {window_s} s)",
+                    self.duty.average_pct()
+                ),
+            });
+            self.duty_limit = limit;
+        }
+        self.controller.set_motor_limit_pct(limit);
+        gain
+    }
+
+    fn send_vibe(&mut self, intensity: u8) -> (usize, usize) {
+        let mut ok = 0usize;
+        let mut fail = 0usize;
+
+        for d in &mut self.devices {
+            if !is_vibe_interface(d) {
+                continue;
+            }
+
+            let frame = self.registry.build_vibe_frame(
+                self.variant,
+                d.vid,
+                d.pid,
+                d.report_id,
+                d.out_len,
+                intensity,
+            );
+            let started = self.clock.now();
+            let result = d.dev.write(&frame);
+            d.record_vibe_timing(started, self.clock.now().saturating_duration_since(started));
+            if let Err(e) = &result {
+                self.logs.push(format!(
+                    "HID: vibe write FAILED (PID=0x{:04X} {}, path='{}'): {}",
+                    d.pid,
+                    self.registry.model_label(self.variant, d.vid, d.pid),
+                    d.path,
+                    e
+                ));
+            }
+            if d.record_write(&result, frame.len()) {
+                ok += 1;
+            } else {
+                fail += 1;
+            }
+        }
+
+        // The motor keeps running at the last accepted level.
+        let now = self.clock.now();
+        self.duty.set_level(now, if ok > 0 { intensity } else { 0 });
+
+        self.drop_stale_devices();
+        self.publish();
+        (ok, fail)
+    }
+
+    /// Write a zero frame to every open vibe interface, retrying each once, and send nothing
+    /// after that. Returns how many interfaces accepted the frame.
+    pub fn stop_outputs(&mut self) -> usize {
+        self.stopped = true;
+        self.patterns.stop_all();
+        self.last_sent_intensity = Some(0);
+        let mut ok = 0;
+        let mut total = 0;
+        for d in self.devices.iter_mut().filter(|d| is_vibe_interface(d)) {
+            total += 1;
+            let frame = self.registry.build_vibe_frame(
+                self.variant,
+                d.vid,
+                d.pid,
+                d.report_id,
+                d.out_len,
+                0,
+            );
+            if d.dev.write(&frame).or_else(|_| d.dev.write(&frame)).is_ok() {
+                ok += 1;
+            }
+        }
+        self.logs.push(format!(
+            "HID: motors stopped (zero frame accepted by {ok} of {total} vibe interface(s))"
+        ));
+        ok
+    }
+
+    /// Rate, latency and failure figures per vibe interface, once a minute.
+    fn maybe_log_metrics(&mut self) {
+        let now = self.clock.now();
+        if self.last_metrics_log.is_none() {
+            self.last_metrics_log = Some(now);
+            return;
+        }
+        if !interval_elapsed(now, self.last_metrics_log, METRICS_LOG_INTERVAL) {
+            return;
+        }
+        self.last_metrics_log = Some(now);
+        for d in self.devices.iter().filter(|d| is_vibe_interface(d)) {
+            self.logs.push(format!(
+                "HID: PID=0x{:04X} {} (target {} Hz, device max {} Hz)",
+                d.pid,
+                d.health.metrics_summary(),
+                self.send_hz(),
+                self.registry.max_send_hz(d.vid, d.pid)
+            ));
+        }
+        if self.duty.limits().enabled && self.prev_vibe_ready {
+            self.logs.push(format!(
+                "HID: motor duty avg {:.0}% over {} s",
+                self.duty.average_pct(),
+                self.duty.limits().window_s
+            ));
+        }
+    }
+
+    /// Check the lab's queued input reads, logging each report or timeout.
+    fn poll_lab_reads(&mut self) {
+        if self.lab_reads.is_empty() {
+            return;
+        }
+        let now = self.clock.now();
+        let lab = self.lab.get_or_insert_with(LabShared::default).clone();
+        let mut log = lab.lock();
+        let devices = &mut self.devices;
+        self.lab_reads.retain(|read| {
+            let direction = Direction::Received(ReportKind::Input);
+            let Some(d) = devices.iter_mut().find(|d| d.path == read.path) else {
+                log.record(
+                    &read.path,
+                    direction,
+                    &[],
+                    Some("interface not open".to_string()),
+                );
+                return false;
+            };
+            match lab::poll_input_logged(&mut d.dev, &read.path, read.len, &mut log) {
+                Ok(None) if now < read.until => true,
+                Ok(None) => {
+                    log.record(
+                        &read.path,
+                        direction,
+                        &[],
+                        Some(format!(
+                            "no input report within {} ms",
+                            lab::DEFAULT_READ_TIMEOUT.as_millis()
+                        )),
+                    );
+                    false
+                }
+                Ok(Some(_)) | Err(_) => false,
+            }
+        });
+    }
+
+    /// Drain pending input reports and publish the newest decoded state.
+    fn poll_inputs(&mut self) {
+        let Some(shared) = &self.input else {
+            return;
+        };
+
+        let now = self.clock.now();
+        let mut latest: Option<StickInput> = None;
+        let mut pressed: Vec<HidEvent> = Vec::new();
+        let mut buf = [0u8; INPUT_REPORT_BUF_LEN];
+        for d in self.devices.iter_mut().filter(|d| d.reads_input) {
+            let Some(layout) = self.registry.input_layout(d.vid, d.pid) else {
+                d.reads_input = false;
+                continue;
+            };
+            // Drain everything queued so the state published is the newest.
+            loop {
+                match d.dev.read_report(&mut buf, Duration::ZERO) {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        if let Some((x, y, buttons)) = input::decode(layout, &buf[..n]) {
+                            let prev = d.last_input.map_or(0, |i| i.buttons);
+                            pressed.extend(input::newly_pressed(prev, buttons).map(|button| {
+                                HidEvent::ButtonPressed {
+                                    path: d.path.clone(),
+                                    button,
+                                }
+                            }));
+                            d.last_input = Some(StickInput {
+                                x,
+                                y,
+                                buttons,
+                                hand_on: d.hand.update(x, y, buttons, now),
+                            });
+                        }
+                    }
+                    Err(TransportError::Unsupported) => {
+                        d.reads_input = false;
+                        break;
+                    }
+                    Err(_) => break,
+                }
+            }
+            // Re-evaluate even without new reports: a still stick sends nothing.
+            if let Some(last) = &mut d.last_input {
+                last.hand_on = d.hand.update(last.x, last.y, last.buttons, now);
+            }
+            latest = latest.or(d.last_input);
+        }
+        *shared.lock() = latest;
+        for event in pressed {
+            self.emit(event);
+        }
+    }
+
+    /// Close handles that failed repeatedly and schedule a quick reconnect attempt.
+    fn drop_stale_devices(&mut self) {
+        if !self.devices.iter().any(|d| d.stale) {
+            return;
+        }
+
+        let (stale, keep): (Vec<_>, Vec<_>) = std::mem::take(&mut self.devices)
+            .into_iter()
+            .partition(|d| d.stale);
+        self.devices = keep;
+
+        for (i, d) in stale.iter().enumerate() {
+            let reason = d
+                .health
+                .last_error
+                .clone()
+                .unwrap_or_else(|| "write failed".to_string());
+            self.logs.push(format!(
+                "HID: dropping stale handle path='{}' after {} failed writes ({reason})",
+                d.path, d.consecutive_failures
+            ));
+            self.emit_if_gone(d, &stale[i + 1..], reason);
+        }
+
+        self.backoff = RECONNECT_BACKOFF_MIN;
+        self.next_scan = Some(self.clock.now() + RECONNECT_BACKOFF_MIN);
+        self.update_vibe_ready();
+    }
+
+    /// Enumerate on a device-change notification or when the schedule says so.
+    /// Without a vibe interface the schedule backs off exponentially.
+    fn maybe_rescan(&mut self) {
+        let now = self.clock.now();
+        let device_change = self.backend.take_device_change();
+        if device_change {
+            self.backoff = RECONNECT_BACKOFF_MIN;
+        }
+        let due = self.next_scan.is_none_or(|t| now >= t);
+        if !device_change && !due {
+            return;
+        }
+
+        self.rescan(now);
+
+        let wait = if self.prev_vibe_ready {
+            self.backoff = RECONNECT_BACKOFF_MIN;
+            IDLE_RESCAN_INTERVAL
+        } else {
+            let wait = self.backoff;
+            self.backoff = (self.backoff * 2).min(RECONNECT_BACKOFF_MAX);
+            wait
+        };
+        self.next_scan = Some(now + wait);
+    }
+
+    fn rescan(&mut self, now: Instant) {
+        let listed = match self.backend.enumerate() {
+            Ok(list) => list,
+            Err(e) => {
+                self.logs.push(format!("HID: refresh_devices FAILED: {e}"));
+                Vec::new()
+            }
+        };
+        let winwing: Vec<HidDeviceInfo> = listed
+            .into_iter()
+            .filter(|d| self.registry.is_known_vendor(d.vendor_id))
+            .collect();
+
+        let mut seen_paths: HashSet<String> = HashSet::new();
+        let mut found_summary: Vec<String> = Vec::new();
+        for info in &winwing {
+            seen_paths.insert(info.path.clone());
+            found_summary.push(format!(
+                "pid=0x{:04X} ({}) if#{} up=0x{:04X} u=0x{:04X} path='{}'",
+                info.product_id,
+                self.registry
+                    .model_label(self.variant, info.vendor_id, info.product_id),
+                info.interface_number,
+                info.usage_page,
+                info.usage,
+                info.path,
+            ));
+        }
+
+        found_summary.sort();
+        let scan_sig = found_summary.join(" | ");
+        if scan_sig != self.prev_scan_sig {
+            self.logs.push(if found_summary.is_empty() {
+                "HID: scan found 0 Winwing devices".to_string()
+            } else {
+                format!(
+                    "HID: scan found {} Winwing devices: {}",
+                    found_summary.len(),
+                    scan_sig
+                )
+            });
+            self.prev_scan_sig = scan_sig;
+        }
+
+        let (kept, removed): (Vec<_>, Vec<_>) = std::mem::take(&mut self.devices)
+            .into_iter()
+            .partition(|d| seen_paths.contains(&d.path));
+        self.devices = kept;
+        for (i, d) in removed.iter().enumerate() {
+            self.logs
+                .push(format!("HID: device REMOVED path='{}'", d.path));
+            self.emit_if_gone(d, &removed[i + 1..], "removed".to_string());
+        }
+
+        for info in &winwing {
+            if self.devices.iter().any(|d| d.path == info.path) {
+                continue;
+            }
+            self.open_entry(info);
+        }
+
+        if seen_paths.is_empty()
+            && interval_elapsed(now, self.last_missing_log, MISSING_LOG_INTERVAL)
+        {
+            self.logs.push(format!(
+                "HID: no Winwing devices found (VID=0x{WW_VID:04X})"
+            ));
+            self.last_missing_log = Some(now);
+        }
+
+        self.update_vibe_ready();
+    }
+
+    fn update_vibe_ready(&mut self) {
+        let vibe_ready = self.devices.iter().any(is_vibe_interface);
+        if vibe_ready && !self.prev_vibe_ready {
+            self.log_vibe_ready_summary();
+        }
+        self.prev_vibe_ready = vibe_ready;
+        self.publish();
+    }
+
+    fn publish(&self) {
+        let health = self
+            .devices
+            .iter()
+            .map(|d| DeviceHealth {
+                model: self.registry.model_label(self.variant, d.vid, d.pid),
+                ..d.health.clone()
+            })
+            .collect();
+        self.controller.publish(self.prev_vibe_ready, health);
+    }
+
+    /// `Disconnected` for the device `closed` belonged to, unless one of its interfaces is
+    /// still open or still in `closing` (the handles that are yet to be reported).
+    fn emit_if_gone(
+        &self,
+        closed: &HidEntry<B::Device>,
+        closing: &[HidEntry<B::Device>],
+        reason: String,
+    ) {
+        if self
+            .devices
+            .iter()
+            .chain(closing)
+            .any(|d| same_device(d, closed))
+        {
+            return;
+        }
+        self.emit(HidEvent::Disconnected {
+            path: closed.path.clone(),
+            model: self
+                .registry
+                .model_label(self.variant, closed.vid, closed.pid),
+            reason,
+        });
+    }
+
+    fn emit(&self, event: HidEvent) {
+        if let Some(tx) = &self.events {
+            let _ = tx.send(event);
+        }
+    }
+
+    fn open_entry(&mut self, info: &HidDeviceInfo) {
+        let path = &info.path;
+        let pid = info.product_id;
+        let (out_len, report_id) = self.backend.query_caps(info).unwrap_or(FALLBACK_CAPS);
+
+        let dev = match self.backend.open(info) {
+            Ok(d) => d,
+            Err(e) => {
+                self.logs
+                    .push(format!("HID: open failed on '{path}' : {e}"));
+                return;
+            }
+        };
+
+        let vid = info.vendor_id;
+        let channel = self.registry.channel_byte(self.variant, vid, pid);
+        self.logs.push(format!(
+            "HID: sidestick connected (VID=0x{vid:04X}, PID=0x{pid:04X}, hand={}, variant={}, channel=0x{channel:02X}, out_len={out_len}, report_id=0x{report_id:02X}) path='{path}'",
+            self.registry.hand(vid, pid).label(),
+            self.variant.label(),
+        ));
+
+        if let Some(t) = self.registry.frame_template(vid, pid) {
+            if out_len < t.min_len {
+                self.logs.push(format!(
+                    "HID: output report too short for vibe frame ({out_len} < {}) path='{path}'",
+                    t.min_len
+                ));
+            }
+        }
+
+        let model = self.registry.model_label(self.variant, vid, pid);
+        let first_interface = !self
+            .devices
+            .iter()
+            .any(|d| (d.vid, d.pid, &d.serial) == (vid, pid, &info.serial));
+        if first_interface {
+            self.emit(HidEvent::Connected {
+                path: path.clone(),
+                model: model.clone(),
+            });
+        }
+
+        self.devices.push(HidEntry {
+            dev,
+            path: path.clone(),
+            vid,
+            pid,
+            serial: info.serial.clone(),
+            vibe: self.registry.is_vibe_interface(info),
+            out_len,
+            report_id,
+            health: DeviceHealth {
+                path: path.clone(),
+                pid,
+                model,
+                connected_at: self.clock.now(),
+                writes_ok: 0,
+                writes_failed: 0,
+                last_error: None,
+                write_latency: Duration::ZERO,
+                write_latency_max: Duration::ZERO,
+                send_interval: Duration::ZERO,
+            },
+            consecutive_failures: 0,
+            stale: false,
+            reads_input: self.registry.input_layout(vid, pid).is_some(),
+            hand: HandDetector::default(),
+            last_input: None,
+            last_vibe_write: None,
+        });
+        // A fresh handle has not seen the current intensity yet.
+        self.last_sent_intensity = None;
+    }
+
+    fn log_vibe_ready_summary(&self) {
+        let summary: Vec<String> = self
+            .devices
+            .iter()
+            .filter(|d| is_vibe_interface(d))
+            .map(|d| {
+                format!(
+                    "PID=0x{:04X} hand={} channel=0x{:02X}",
+                    d.pid,
+                    self.registry.hand(d.vid, d.pid).label(),
+                    self.registry.channel_byte(self.variant, d.vid, d.pid)
+                )
+            })
+            .collect();
+        if summary.is_empty() {
+            return;
+        }
+
+        self.logs.push(format!(
+            "HID: sidestick vibe ready ({})",
+            summary.join(", ")
+        ));
+    }
+}
+
+/// A worker that unwinds (a panic) or returns early still leaves the motors at rest.
+impl<B: HidBackend, C: Clock> Drop for HidSession<B, C> {
+    fn drop(&mut self) {
+        if !self.stopped && self.devices.iter().any(is_vibe_interface) {
+            self.stop_outputs();
+        }
+    }
+}
@@ -1,9 +1,20 @@
-use std::sync::{atomic::AtomicBool, Arc};
+use std::sync::Arc;
 
-use crossbeam_channel::Receiver;
+use crossbeam_channel::{Receiver, Sender};
 
-use crate::{HidCmd, LogBuffer};
+use crate::hid::input::InputShared;
+use crate::hid::lab::LabShared;
+use crate::hid::registry::DeviceRegistry;
+use crate::{ControllerShared, HidCmd, HidEvent, LogBuffer};
 
-pub fn hid_worker(_controller_connected: Arc<AtomicBool>, _rx: Receiver<HidCmd>, _logs: LogBuffer) {
+pub fn hid_worker(
+    _controller: ControllerShared,
+    _rx: Receiver<HidCmd>,
+    _events: Sender<HidEvent>,
+    _registry: Arc<DeviceRegistry>,
+    _lab: LabShared,
+    _input: InputShared,
+    _logs: LogBuffer,
+) {
     // Non-Windows stub: HID hardware is unavailable.
 }
@@ -0,0 +1,66 @@
+//! Device access seam for the HID worker: enumeration, open, write and report reads.
+//! `hidapi` backs it in the app; `hid::mock` backs it in tests.
+
+use std::time::Duration;
+
+/// Enumeration entry for one HID interface (a physical stick exposes several).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HidDeviceInfo {
+    pub path: String,
+    pub vendor_id: u16,
+    pub product_id: u16,
+    pub interface_number: i32,
+    pub usage_page: u16,
+    pub usage: u16,
+    /// Shared by the interfaces of one physical device; empty if the device reports none.
+    pub serial: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum TransportError {
+    #[error("{0}")]
+    Backend(String),
+    #[error("device not connected")]
+    Disconnected,
+    #[error("not supported by this backend")]
+    Unsupported,
+}
+
+/// An opened HID interface.
+pub trait HidTransport {
+    /// Write one output report; returns the number of bytes accepted.
+    fn write(&mut self, data: &[u8]) -> Result<usize, TransportError>;
+
+    /// Read one input report into `buf`; `Ok(0)` when nothing arrived within `timeout`.
+    fn read_report(
+        &mut self,
+        _buf: &mut [u8],
+        _timeout: Duration,
+    ) -> Result<usize, TransportError> {
+        Err(TransportError::Unsupported)
+    }
+
+    /// Fetch the feature report whose ID is in `buf[0]`; returns the bytes filled in.
+    fn get_feature_report(&mut self, _buf: &mut [u8]) -> Result<usize, TransportError> {
+        Err(TransportError::Unsupported)
+    }
+}
+
+/// Enumerates and opens HID interfaces.
+pub trait HidBackend {
+    type Device: HidTransport;
+
+    /// Rescan and return every HID interface currently present (all vendors).
+    fn enumerate(&mut self) -> Result<Vec<HidDeviceInfo>, TransportError>;
+
+    fn open(&mut self, info: &HidDeviceInfo) -> Result<Self::Device, TransportError>;
+
+    /// Output report length and report ID; `None` when the OS query fails.
+    fn query_caps(&mut self, info: &HidDeviceInfo) -> Option<(u16, u8)>;
+
+    /// True once after the OS reported a device arrival/removal since the last call.
+    /// Backends without notifications return false and rely on the periodic rescan.
+    fn take_device_change(&mut self) -> bool {
+        false
+    }
+}
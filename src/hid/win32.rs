@@ -1,17 +1,30 @@
-use std::ffi::OsStr;
+use std::ffi::{c_void, OsStr};
+use std::mem::{size_of, zeroed};
 use std::os::windows::ffi::OsStrExt;
+use std::ptr::{addr_of, addr_of_mut};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, OnceLock,
+};
 
 use windows::core::PCWSTR;
 use windows::Win32::Devices::HumanInterfaceDevice::{
-    HidD_FreePreparsedData, HidD_GetPreparsedData, HidP_GetCaps, HidP_GetValueCaps, HidP_Output,
-    HIDP_CAPS, HIDP_STATUS_SUCCESS, HIDP_VALUE_CAPS,
+    HidD_FreePreparsedData, HidD_GetHidGuid, HidD_GetPreparsedData, HidP_GetCaps,
+    HidP_GetValueCaps, HidP_Output, HIDP_CAPS, HIDP_STATUS_SUCCESS, HIDP_VALUE_CAPS,
 };
-use windows::Win32::Foundation::{HANDLE, NTSTATUS};
+use windows::Win32::Foundation::{HANDLE, HINSTANCE, HWND, LPARAM, LRESULT, NTSTATUS, WPARAM};
 use windows::Win32::Storage::FileSystem::{
     CreateFileW, FILE_ATTRIBUTE_NORMAL, FILE_CREATION_DISPOSITION, FILE_FLAGS_AND_ATTRIBUTES,
     FILE_GENERIC_READ, FILE_GENERIC_WRITE, FILE_SHARE_MODE, FILE_SHARE_READ, FILE_SHARE_WRITE,
     OPEN_EXISTING,
 };
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::UI::WindowsAndMessaging::{
+    CreateWindowExW, DefWindowProcW, DispatchMessageW, GetMessageW, RegisterClassW,
+    RegisterDeviceNotificationW, TranslateMessage, UnregisterDeviceNotification, DBT_DEVICEARRIVAL,
+    DBT_DEVICEREMOVECOMPLETE, DBT_DEVTYP_DEVICEINTERFACE, DEVICE_NOTIFY_WINDOW_HANDLE,
+    DEV_BROADCAST_DEVICEINTERFACE_W, HWND_MESSAGE, MSG, WINDOW_STYLE, WM_DEVICECHANGE, WNDCLASSW,
+};
 
 use crate::LogBuffer;
 
@@ -118,3 +131,103 @@ pub fn hid_query_caps_from_path(path_utf8: &str, logs: &LogBuffer) -> Option<(u1
 
     Some((out_len, report_id))
 }
+
+static DEVICE_CHANGED: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+
+unsafe extern "system" fn device_change_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    if msg == WM_DEVICECHANGE {
+        let event = wparam.0 as u32;
+        if event == DBT_DEVICEARRIVAL || event == DBT_DEVICEREMOVECOMPLETE {
+            if let Some(flag) = DEVICE_CHANGED.get() {
+                flag.store(true, Ordering::Relaxed);
+            }
+        }
+        return LRESULT(1);
+    }
+    DefWindowProcW(hwnd, msg, wparam, lparam)
+}
+
+/// Start a message-only window that listens for HID interface arrival/removal.
+/// Returns the flag it raises; `None` if registration failed (callers fall back to polling).
+pub fn spawn_device_change_listener(logs: &LogBuffer) -> Option<Arc<AtomicBool>> {
+    if let Some(flag) = DEVICE_CHANGED.get() {
+        return Some(flag.clone());
+    }
+    let flag = DEVICE_CHANGED
+        .get_or_init(|| Arc::new(AtomicBool::new(false)))
+        .clone();
+
+    let (tx_ready, rx_ready) = crossbeam_channel::bounded::<bool>(1);
+    std::thread::spawn(move || unsafe {
+        let Ok(module) = GetModuleHandleW(None) else {
+            let _ = tx_ready.send(false);
+            return;
+        };
+        let hinst = HINSTANCE(module.0);
+        let class_name: Vec<u16> = OsStr::new("UrsaMinorFFB.HidNotify")
+            .encode_wide()
+            .chain(Some(0))
+            .collect();
+        let wc = WNDCLASSW {
+            lpfnWndProc: Some(device_change_proc),
+            hInstance: hinst,
+            lpszClassName: PCWSTR(class_name.as_ptr()),
+            ..zeroed()
+        };
+        let _ = RegisterClassW(&wc);
+
+        let hwnd = CreateWindowExW(
+            Default::default(),
+            PCWSTR(class_name.as_ptr()),
+            PCWSTR::null(),
+            WINDOW_STYLE(0),
+            0,
+            0,
+            0,
+            0,
+            HWND_MESSAGE,
+            None,
+            hinst,
+            None,
+        );
+        if hwnd.0 == 0 {
+            let _ = tx_ready.send(false);
+            return;
+        }
+
+        let mut filter: DEV_BROADCAST_DEVICEINTERFACE_W = zeroed();
+        filter.dbcc_size = size_of::<DEV_BROADCAST_DEVICEINTERFACE_W>() as u32;
+        filter.dbcc_devicetype = DBT_DEVTYP_DEVICEINTERFACE.0;
+        filter.dbcc_classguid = HidD_GetHidGuid();
+        let registered = RegisterDeviceNotificationW(
+            HANDLE(hwnd.0),
+            addr_of!(filter).cast::<c_void>(),
+            DEVICE_NOTIFY_WINDOW_HANDLE,
+        );
+        let Ok(notify) = registered else {
+            let _ = tx_ready.send(false);
+            return;
+        };
+        let _ = tx_ready.send(true);
+
+        let mut msg = MSG::default();
+        while GetMessageW(addr_of_mut!(msg), None, 0, 0).into() {
+            let _ = TranslateMessage(&msg);
+            DispatchMessageW(&msg);
+        }
+        let _ = UnregisterDeviceNotification(notify);
+    });
+
+    if rx_ready.recv().unwrap_or(false) {
+        logs.push("HID: device-change notifications registered");
+        Some(flag)
+    } else {
+        logs.push("HID: device-change notifications unavailable → periodic rescan only");
+        None
+    }
+}
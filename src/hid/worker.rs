@@ -1,105 +1,30 @@
-use std::collections::{HashMap, HashSet};
-use std::sync::{
-    atomic::{AtomicBool, Ordering},
-    Arc,
-};
-use std::time::{Duration, Instant};
-
-use crossbeam_channel::Receiver;
-use hidapi::{HidApi, HidDevice};
-
-use crate::hid::protocol::{
-    build_simapp_vibe_frame, channel_byte_for, handed_label, ursa_model_label, SidestickVariant,
-    WW_VID,
-};
-use crate::hid::win32::hid_query_caps_from_path;
-use crate::{HidCmd, LogBuffer};
-
-struct HidEntry {
-    dev: HidDevice,
-    path: String,
-    pid: u16,
-    usage_page: u16,
-    usage: u16,
-    out_len: u16,
-    report_id: u8,
-}
-
-fn is_vibe_interface(d: &HidEntry) -> bool {
-    d.usage_page == 0x0001 && d.usage == 0x0004
-}
-
-fn hid_send_out(
-    devs: &[HidEntry],
-    variant: SidestickVariant,
-    intensity: u8,
-    logs: &LogBuffer,
-) -> (usize, usize) {
-    let mut ok = 0usize;
-    let mut fail = 0usize;
-
-    for d in devs {
-        if !is_vibe_interface(d) {
-            continue;
-        }
-
-        let frame = build_simapp_vibe_frame(variant, d.pid, d.report_id, d.out_len, intensity);
-        match d.dev.write(&frame) {
-            Ok(n) => {
-                if n == frame.len() {
-                    ok += 1;
-                } else {
-                    fail += 1;
-                }
-            }
-            Err(e) => {
-                fail += 1;
-                logs.push(format!(
-                    "HID: vibe write FAILED (PID=0x{:04X} {}, path='{}'): {}",
-                    d.pid,
-                    ursa_model_label(variant, d.pid),
-                    d.path,
-                    e
-                ));
-            }
-        }
-    }
-
-    (ok, fail)
-}
-
-fn log_vibe_ready_summary(devs: &[HidEntry], variant: SidestickVariant, logs: &LogBuffer) {
-    let vibe_devs: Vec<_> = devs.iter().filter(|d| is_vibe_interface(d)).collect();
-    if vibe_devs.is_empty() {
-        return;
-    }
-
-    let summary: Vec<String> = vibe_devs
-        .iter()
-        .map(|d| {
-            format!(
-                "PID=0x{:04X} hand={} channel=0x{:02X}",
-                d.pid,
-                handed_label(d.pid),
-                channel_byte_for(variant, d.pid)
-            )
-        })
-        .collect();
-
-    logs.push(format!(
-        "HID: sidestick vibe ready ({})",
-        summary.join(", ")
-    ));
-}
-
-pub fn hid_worker(controller_connected: Arc<AtomicBool>, rx: Receiver<HidCmd>, logs: LogBuffer) {
+use std::sync::Arc;
+
+use crossbeam_channel::{Receiver, Sender};
+
+use crate::hid::clock::SystemClock;
+use crate::hid::hidapi_backend::HidapiBackend;
+use crate::hid::input::InputShared;
+use crate::hid::lab::LabShared;
+use crate::hid::registry::DeviceRegistry;
+use crate::hid::session::HidSession;
+use crate::{ControllerShared, HidCmd, HidEvent, LogBuffer};
+
+pub fn hid_worker(
+    controller: ControllerShared,
+    rx: Receiver<HidCmd>,
+    events: Sender<HidEvent>,
+    registry: Arc<DeviceRegistry>,
+    lab: LabShared,
+    input: InputShared,
+    logs: LogBuffer,
+) {
     logs.push("HID: worker starting…");
 
-    let verbose_hid = std::env::var_os("URSA_VERBOSE_HID").is_some();
-    let mut api = match HidApi::new() {
-        Ok(a) => {
+    let backend = match HidapiBackend::new(logs.clone()) {
+        Ok(b) => {
             logs.push("HID: HidApi initialized");
-            a
+            b
         }
         Err(e) => {
             logs.push(format!("HID: HidApi::new FAILED: {e}"));
@@ -107,240 +32,10 @@ pub fn hid_worker(controller_connected: Arc<AtomicBool>, rx: Receiver<HidCmd>, l
         }
     };
 
-    let mut devices: Vec<HidEntry> = vec![];
-    let mut last_scan = Instant::now() - Duration::from_secs(10);
-    let mut last_status_log = Instant::now() - Duration::from_secs(10);
-    let mut last_missing_log = Instant::now() - Duration::from_secs(10);
-
-    const SEND_INTERVAL: Duration = Duration::from_millis(50);
-
-    let mut desired_intensity: u8 = 0;
-    let mut last_sent_intensity: u8 = 255;
-    let mut last_send = Instant::now() - SEND_INTERVAL;
-    let mut hold: bool = false;
-    let mut current_variant = SidestickVariant::Airbus;
-    let mut prev_scan_sig = String::new();
-    let mut prev_vibe_ready = false;
-
-    let mut ensure_open = |api: &mut HidApi,
-                           devices: &mut Vec<HidEntry>,
-                           current_variant: SidestickVariant,
-                           prev_vibe_ready: &mut bool| {
-        if last_scan.elapsed() < Duration::from_secs(2) && !devices.is_empty() {
-            return;
-        }
-
-        let mut idx_by_path: HashMap<String, usize> = HashMap::new();
-        for (i, d) in devices.iter().enumerate() {
-            idx_by_path.insert(d.path.clone(), i);
-        }
-
-        if let Err(e) = api.refresh_devices() {
-            logs.push(format!("HID: refresh_devices FAILED: {e}"));
-        }
-
-        let mut seen_paths: HashSet<String> = HashSet::new();
-        let mut found_summary: Vec<String> = Vec::new();
-
-        for devinfo in api.device_list() {
-            if devinfo.vendor_id() != WW_VID {
-                continue;
-            }
-
-            let path = devinfo.path().to_string_lossy().to_string();
-            let pid = devinfo.product_id();
-            let ifnum = devinfo.interface_number();
-            let up = devinfo.usage_page();
-            let u = devinfo.usage();
-
-            seen_paths.insert(path.clone());
-            found_summary.push(format!(
-                "pid=0x{pid:04X} ({}) if#{ifnum} up=0x{up:04X} u=0x{u:04X} path='{path}'",
-                ursa_model_label(current_variant, pid),
-            ));
-        }
-
-        found_summary.sort();
-        let scan_sig = found_summary.join(" | ");
-        if scan_sig != prev_scan_sig {
-            logs.push(if found_summary.is_empty() {
-                "HID: scan found 0 Winwing devices".to_string()
-            } else {
-                format!(
-                    "HID: scan found {} Winwing devices: {}",
-                    found_summary.len(),
-                    scan_sig
-                )
-            });
-            prev_scan_sig = scan_sig;
-        }
-
-        for devinfo in api.device_list() {
-            if devinfo.vendor_id() != WW_VID {
-                continue;
-            }
-
-            let path = devinfo.path().to_string_lossy().to_string();
-            if idx_by_path.contains_key(&path) {
-                continue;
-            }
-
-            let vid = devinfo.vendor_id();
-            let pid = devinfo.product_id();
-            let (out_len, report_id) =
-                hid_query_caps_from_path(&path, &logs).unwrap_or((14u16, 0x02u8));
-
-            let d = match devinfo.open_device(api) {
-                Ok(d) => d,
-                Err(e) => {
-                    logs.push(format!("HID: open failed on '{path}' : {e}"));
-                    continue;
-                }
-            };
-
-            let channel = channel_byte_for(current_variant, pid);
-            logs.push(format!(
-                "HID: sidestick connected (VID=0x{vid:04X}, PID=0x{pid:04X}, hand={}, variant={}, channel=0x{channel:02X}, out_len={out_len}, report_id=0x{report_id:02X}) path='{path}'",
-                handed_label(pid),
-                current_variant.label(),
-            ));
-
-            devices.push(HidEntry {
-                dev: d,
-                path: path.clone(),
-                pid,
-                usage_page: devinfo.usage_page(),
-                usage: devinfo.usage(),
-                out_len,
-                report_id,
-            });
-        }
-
-        if !devices.is_empty() {
-            devices.retain(|d| {
-                if seen_paths.contains(&d.path) {
-                    true
-                } else {
-                    logs.push(format!("HID: device REMOVED path='{}'", d.path));
-                    false
-                }
-            });
-        }
-
-        if seen_paths.is_empty() && last_missing_log.elapsed() > Duration::from_secs(5) {
-            logs.push("HID: no Winwing devices found (VID=0x4098)".to_string());
-            last_missing_log = Instant::now();
-        }
-
-        let vibe_ready = devices.iter().any(is_vibe_interface);
-        if vibe_ready && !*prev_vibe_ready {
-            log_vibe_ready_summary(devices, current_variant, &logs);
-        }
-        *prev_vibe_ready = vibe_ready;
-        controller_connected.store(vibe_ready, Ordering::Relaxed);
-        last_scan = Instant::now();
-    };
-
-    ensure_open(
-        &mut api,
-        &mut devices,
-        current_variant,
-        &mut prev_vibe_ready,
-    );
-
-    loop {
-        match rx.recv_timeout(Duration::from_millis(100)) {
-            Ok(cmd) => match cmd {
-                HidCmd::SendIntensity(level) => {
-                    desired_intensity = level;
-                    if verbose_hid
-                        && (i16::from(desired_intensity) - i16::from(last_sent_intensity)).abs()
-                            >= 15
-                    {
-                        logs.push(format!("HID: cmd SendIntensity({desired_intensity})"));
-                    }
-                }
-                HidCmd::SendRaw(bytes) => {
-                    logs.push(format!("HID: cmd SendRaw(len={})", bytes.len()));
-                    for d in &devices {
-                        if let Err(e) = d.dev.write(&bytes) {
-                            logs.push(format!(
-                                "HID: raw write FAILED (PID=0x{:04X} {}, path='{}'): {}",
-                                d.pid,
-                                ursa_model_label(current_variant, d.pid),
-                                d.path,
-                                e
-                            ));
-                        }
-                    }
-                }
-                HidCmd::StopAll => {
-                    logs.push("HID: cmd StopAll");
-                    desired_intensity = 0;
-                    last_send = Instant::now() - SEND_INTERVAL;
-                }
-                HidCmd::SetHold(x) => {
-                    hold = x;
-                    logs.push(format!("HID: cmd SetHold({hold})"));
-                    if hold {
-                        let (_ok, _fail) = hid_send_out(&devices, current_variant, 0, &logs);
-                        last_sent_intensity = 0;
-                    }
-                }
-                HidCmd::ReopenDevices => {
-                    logs.push("HID: cmd ReopenDevices");
-                    ensure_open(
-                        &mut api,
-                        &mut devices,
-                        current_variant,
-                        &mut prev_vibe_ready,
-                    );
-                }
-                HidCmd::SetSidestickVariant(v) => {
-                    current_variant = v;
-                    let (left, right) = v.channel_pair();
-                    logs.push(format!(
-                        "HID: sidestick variant → {} (channel L=0x{left:02X}, R=0x{right:02X})",
-                        v.label()
-                    ));
-                    last_sent_intensity = 255;
-                }
-            },
-            Err(crossbeam_channel::RecvTimeoutError::Timeout) => {}
-            Err(crossbeam_channel::RecvTimeoutError::Disconnected) => {
-                logs.push("HID: channel disconnected → worker exit");
-                break;
-            }
-        }
-
-        ensure_open(
-            &mut api,
-            &mut devices,
-            current_variant,
-            &mut prev_vibe_ready,
-        );
-
-        if last_send.elapsed() >= SEND_INTERVAL {
-            let out = if hold { 0 } else { desired_intensity };
-            if out != last_sent_intensity {
-                let (ok, fail) = hid_send_out(&devices, current_variant, out, &logs);
-
-                let now = Instant::now();
-                if out > 0
-                    || fail > 0
-                    || ok == 0
-                    || now.duration_since(last_status_log) > Duration::from_millis(900)
-                {
-                    logs.push(format!(
-                        "HID: send intensity {out} → ok={ok} fail={fail} (devs={}, hold={hold})",
-                        devices.len(),
-                    ));
-                    last_status_log = now;
-                }
-
-                last_sent_intensity = out;
-            }
-            last_send = Instant::now();
-        }
-    }
+    let mut session = HidSession::new(backend, SystemClock, controller, logs);
+    session.set_event_sink(events);
+    session.set_registry(registry);
+    session.set_lab_log(lab);
+    session.set_input_sink(input);
+    session.run(&rx);
 }
@@ -0,0 +1,275 @@
+//! One app per machine: the first instance listens on a fixed loopback port, and a later launch
+//! hands its command (`--hold`, `--resume`, `--quit`, or just "show the window") to it over
+//! that socket and exits, instead of opening the sidestick a second time.
+//!
+//! Any local process can connect to a loopback port, so requests carry a random token the
+//! running instance writes to a file in the user's temp folder; only launches by the same user
+//! can read it. A port that stays busy without a valid answer stops the launch rather than let
+//! a second HID worker start.
+
+use std::collections::hash_map::RandomState;
+use std::fs;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::LogBuffer;
+
+pub const INSTANCE_ADDR: SocketAddr =
+    SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 47_631));
+/// How long a launch keeps trying to reach the running instance before giving up.
+pub const INSTANCE_PATIENCE: Duration = Duration::from_secs(10);
+const TOKEN_FILE: &str = "UrsaMinorFFB.instance";
+/// First word of every request, so an unrelated program on the port is not mistaken for us.
+const MAGIC: &str = "ursa-minor-ffb/2";
+const REPLY_OK: &str = "ok";
+const IO_TIMEOUT: Duration = Duration::from_secs(2);
+const RETRY_INTERVAL: Duration = Duration::from_millis(500);
+const MAX_REQUEST_LEN: u64 = 128;
+
+/// Where the running instance keeps its token.
+pub fn token_path() -> PathBuf {
+    std::env::temp_dir().join(TOKEN_FILE)
+}
+
+/// What a launch asks of the app, from its command line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InstanceCmd {
+    #[default]
+    Show,
+    Hold,
+    Resume,
+    Quit,
+}
+
+impl InstanceCmd {
+    /// The last of `--show`, `--hold`, `--resume`, `--quit` given; `Show` when none is.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        args.into_iter()
+            .filter_map(|arg| arg.strip_prefix("--").and_then(Self::from_name))
+            .last()
+            .unwrap_or_default()
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "show" => Self::Show,
+            "hold" => Self::Hold,
+            "resume" => Self::Resume,
+            "quit" => Self::Quit,
+            _ => return None,
+        })
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Show => "show",
+            Self::Hold => "hold",
+            Self::Resume => "resume",
+            Self::Quit => "quit",
+        }
+    }
+}
+
+/// The primary instance's end of the socket; `serve` it.
+pub struct InstanceServer {
+    listener: TcpListener,
+    token: String,
+}
+
+pub enum Instance {
+    /// No other instance is running.
+    Primary(InstanceServer),
+    /// The running instance took the command; this process should exit.
+    Forwarded,
+    /// The port stayed busy without a valid answer for `patience`; this process should exit.
+    Blocked(String),
+}
+
+/// Become the primary instance at `addr`, or forward `cmd` to the one already there. While
+/// neither works (an instance still starting up, or one just exiting), retry for `patience`.
+pub fn acquire(
+    addr: SocketAddr,
+    token_path: &Path,
+    cmd: InstanceCmd,
+    patience: Duration,
+) -> Instance {
+    let deadline = Instant::now() + patience;
+    loop {
+        let bind_error = match TcpListener::bind(addr) {
+            Ok(listener) => {
+                let token = new_token();
+                if let Err(e) = fs::write(token_path, &token) {
+                    return Instance::Blocked(format!(
+                        "could not write {}: {e}",
+                        token_path.display()
+                    ));
+                }
+                return Instance::Primary(InstanceServer { listener, token });
+            }
+            Err(e) => e,
+        };
+        let error = match forward(addr, token_path, cmd) {
+            Ok(()) => return Instance::Forwarded,
+            Err(e) => e,
+        };
+        if Instant::now() >= deadline {
+            return Instance::Blocked(format!(
+                "port {} is busy ({bind_error}) but no instance answered ({error})",
+                addr.port()
+            ));
+        }
+        thread::sleep(RETRY_INTERVAL);
+    }
+}
+
+fn new_token() -> String {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u32(std::process::id());
+    format!("{:016x}", hasher.finish())
+}
+
+fn forward(addr: SocketAddr, token_path: &Path, cmd: InstanceCmd) -> std::io::Result<()> {
+    let token = fs::read_to_string(token_path)?;
+    let mut stream = TcpStream::connect_timeout(&addr, IO_TIMEOUT)?;
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    writeln!(stream, "{MAGIC} {} {}", token.trim(), cmd.name())?;
+    let mut reply = String::new();
+    BufReader::new(stream.take(MAX_REQUEST_LEN)).read_line(&mut reply)?;
+    if reply.trim_end() == REPLY_OK {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(format!("unexpected reply {reply:?}")))
+    }
+}
+
+/// Take commands from later launches on a background thread, from right after `acquire` on.
+/// `on_cmd` acts on them directly: the UI runs no frames while minimized, so it cannot be the
+/// one to pick them up.
+pub fn serve(
+    server: InstanceServer,
+    logs: LogBuffer,
+    on_cmd: impl Fn(InstanceCmd) + Send + 'static,
+) {
+    thread::spawn(move || {
+        for stream in server.listener.incoming() {
+            let Ok(stream) = stream else {
+                continue;
+            };
+            let cmd = match read_request(&stream, &server.token) {
+                Ok(cmd) => cmd,
+                Err(e) => {
+                    logs.push(format!("Instance: ignored a connection ({e})"));
+                    continue;
+                }
+            };
+            logs.push(format!("Instance: another launch asked to {}", cmd.name()));
+            on_cmd(cmd);
+            let _ = writeln!(&stream, "{REPLY_OK}");
+        }
+    });
+}
+
+fn read_request(stream: &TcpStream, token: &str) -> std::io::Result<InstanceCmd> {
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let mut line = String::new();
+    BufReader::new(stream.take(MAX_REQUEST_LEN)).read_line(&mut line)?;
+    let mut words = line.split_whitespace();
+    let (Some(MAGIC), Some(sent), Some(name), None) =
+        (words.next(), words.next(), words.next(), words.next())
+    else {
+        return Err(std::io::Error::other(format!("bad request {line:?}")));
+    };
+    if sent != token {
+        return Err(std::io::Error::other("wrong token"));
+    }
+    InstanceCmd::from_name(name)
+        .ok_or_else(|| std::io::Error::other(format!("unknown command {name:?}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossbeam_channel::unbounded;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn any_port() -> SocketAddr {
+        SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0))
+    }
+
+    fn temp_token(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("ursa-instance-{name}-{}", std::process::id()))
+    }
+
+    #[test]
+    fn command_line_picks_the_last_action() {
+        assert_eq!(InstanceCmd::from_args(args(&[])), InstanceCmd::Show);
+        assert_eq!(
+            InstanceCmd::from_args(args(&["--config-dir", "/cfg", "--hold"])),
+            InstanceCmd::Hold
+        );
+        assert_eq!(
+            InstanceCmd::from_args(args(&["--hold", "--resume"])),
+            InstanceCmd::Resume
+        );
+    }
+
+    #[test]
+    fn a_second_launch_forwards_its_command_to_the_first() {
+        let token = temp_token("forward");
+        let Instance::Primary(server) =
+            acquire(any_port(), &token, InstanceCmd::Show, Duration::ZERO)
+        else {
+            panic!("first launch is primary");
+        };
+        let addr = server.listener.local_addr().unwrap();
+        let (tx, rx) = unbounded();
+        serve(server, LogBuffer::default(), move |cmd| {
+            let _ = tx.send(cmd);
+        });
+
+        assert!(matches!(
+            acquire(addr, &token, InstanceCmd::Hold, Duration::ZERO),
+            Instance::Forwarded
+        ));
+        assert_eq!(
+            rx.try_recv(),
+            Ok(InstanceCmd::Hold),
+            "handled before the reply"
+        );
+
+        fs::write(&token, "guessed").unwrap();
+        assert!(matches!(
+            acquire(addr, &token, InstanceCmd::Quit, Duration::ZERO),
+            Instance::Blocked(_)
+        ));
+        assert!(rx.try_recv().is_err(), "a wrong token is ignored");
+        let _ = fs::remove_file(&token);
+    }
+
+    #[test]
+    fn a_busy_port_that_never_answers_blocks_the_launch() {
+        let token = temp_token("foreign");
+        fs::write(&token, "0").unwrap();
+        let foreign = TcpListener::bind(any_port()).unwrap();
+        let addr = foreign.local_addr().unwrap();
+        thread::spawn(move || {
+            for stream in foreign.incoming() {
+                drop(stream);
+            }
+        });
+        assert!(matches!(
+            acquire(addr, &token, InstanceCmd::Show, Duration::ZERO),
+            Instance::Blocked(_)
+        ));
+        let _ = fs::remove_file(&token);
+    }
+}
@@ -1,7 +1,11 @@
+pub mod bindings;
+pub mod config_dir;
 pub mod hid;
+pub mod instance;
 pub mod log;
 pub mod preset;
 pub mod rumble;
+pub mod shutdown;
 pub mod sim;
 pub mod types;
 
@@ -15,7 +19,7 @@ pub mod updater;
 pub use hid::protocol::SidestickVariant;
 pub use log::LogBuffer;
 pub use preset::{
-    AppSettings, LayoutField, Preset, PresetKind, PresetShared, PresetStore, SimVarLayout,
-    SimVarProfile,
+    AppSettings, LayoutField, Preset, PresetId, PresetKind, PresetShared, PresetStore,
+    SimVarLayout, SimVarProfile,
 };
 pub use types::*;
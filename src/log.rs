@@ -2,7 +2,7 @@ use parking_lot::Mutex;
 use std::{
     fs::{File, OpenOptions},
     io::Write,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::Arc,
 };
 
@@ -46,24 +46,13 @@ impl LogBuffer {
         self.inner.lock().clone()
     }
 
-    /// Initialize file logging, preferring the EXE directory. Truncates per session.
-    pub fn try_init_file_prefer_exe_dir(&self) -> std::io::Result<PathBuf> {
-        if let Ok(p) = std::env::current_exe() {
-            if let Some(dir) = p.parent() {
-                let mut log_path = dir.to_path_buf();
-                log_path.push("UrsaMinorFFB.log");
-                if self.attach_file_at(&log_path).is_ok() {
-                    return Ok(log_path);
-                }
-            }
+    /// Initialize file logging at `preferred`, falling back to the temp folder. Truncates per session.
+    pub fn try_init_file(&self, preferred: &Path) -> std::io::Result<PathBuf> {
+        if let Some(dir) = preferred.parent() {
+            let _ = std::fs::create_dir_all(dir);
         }
-        if let Some(base) = std::env::var_os("LOCALAPPDATA") {
-            let mut p = PathBuf::from(base);
-            p.push("UrsaMinorFFB");
-            let _ = std::fs::create_dir_all(&p);
-            p.push("UrsaMinorFFB.log");
-            self.attach_file_at(&p)?;
-            return Ok(p);
+        if self.attach_file_at(&preferred.to_path_buf()).is_ok() {
+            return Ok(preferred.to_path_buf());
         }
         let mut p = std::env::temp_dir();
         p.push("UrsaMinorFFB.log");
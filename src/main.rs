@@ -1,33 +1,125 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use ursa_minor_ffb::{
-    hid::hid_worker,
+    bindings::{BindingsShared, ButtonDispatcher, ButtonOutcome, ControlsShared, OutputControls},
+    config_dir::{self, ConfigDirs},
+    hid::{hid_worker, input::InputShared, lab::LabShared, registry::DeviceRegistry},
+    instance::{self, Instance, InstanceCmd, INSTANCE_ADDR, INSTANCE_PATIENCE},
     log::LogBuffer,
-    preset::{PresetShared, PresetStore},
+    preset::{
+        load_aircraft_overrides, spawn_watcher, AppSettings, FileTarget, PresetId, PresetKind,
+        PresetShared, PresetStore,
+    },
+    rumble::plugin::{PluginHost, PluginState},
+    shutdown::{self, Shutdown, BUTTONS_THREAD, HID_THREAD, SIM_THREAD},
     sim::sim_worker,
     ui::UiState,
-    EffectsShared, EffectsState, FlightVars, HidCmd, UiCmd,
+    ControllerShared, EffectsShared, EffectsState, FlightVars, HidCmd, HidEvent, UiCmd,
 };
 
 use anyhow::Result;
-use crossbeam_channel::unbounded;
+use crossbeam_channel::{unbounded, RecvTimeoutError};
 use parking_lot::Mutex;
-use std::sync::{atomic::AtomicBool, Arc};
-use std::{thread, time::Duration};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::time::Duration;
 
 fn main() -> Result<()> {
+    // Before anything touches the devices or the log file: a second launch only hands its
+    // command to the running instance.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let launch_cmd = InstanceCmd::from_args(args.clone());
+    let server = match instance::acquire(
+        INSTANCE_ADDR,
+        &instance::token_path(),
+        launch_cmd,
+        INSTANCE_PATIENCE,
+    ) {
+        Instance::Primary(_) if launch_cmd == InstanceCmd::Quit => return Ok(()),
+        Instance::Primary(server) => server,
+        Instance::Forwarded => return Ok(()),
+        Instance::Blocked(reason) => {
+            let message = format!("Ursa Minor FFB is not starting: {reason}.");
+            show_startup_error(&message);
+            anyhow::bail!(message);
+        }
+    };
+
     let (tx_hid, rx_hid) = unbounded::<HidCmd>();
     let (tx_ui, rx_ui) = unbounded::<UiCmd>();
+    let (tx_hid_events, rx_hid_events) = unbounded::<HidEvent>();
 
-    let controller_connected = Arc::new(AtomicBool::new(false));
+    let controller: ControllerShared = Arc::default();
     let last_vars = Arc::new(Mutex::new(None::<FlightVars>));
+    let stick_input = InputShared::default();
     let effects: EffectsShared = Arc::new(EffectsState::default());
     let hold = Arc::new(AtomicBool::new(false));
     let status = Arc::new(Mutex::new(ursa_minor_ffb::SimStatus::Disconnected));
     let aircraft_title = Arc::new(Mutex::new(String::new()));
     let logs = LogBuffer::default();
 
-    let preset_store = PresetStore::at_exe_dir();
+    // Acted on here rather than through `UiCmd`: the UI runs no frames while minimized. Until
+    // the window and tray exist, these queue up for them.
+    let on_instance_cmd = {
+        let hold = hold.clone();
+        let tx_hid = tx_hid.clone();
+        let tx_ui = tx_ui.clone();
+        move |cmd| match cmd {
+            InstanceCmd::Show => ursa_minor_ffb::tray::bring_main_to_front(),
+            InstanceCmd::Hold | InstanceCmd::Resume => {
+                let held = cmd == InstanceCmd::Hold;
+                hold.store(held, Ordering::Relaxed);
+                let _ = tx_hid.send(HidCmd::SetHold(held));
+                ursa_minor_ffb::tray::notify_held(held);
+            }
+            InstanceCmd::Quit => {
+                if !ursa_minor_ffb::tray::close_main_window() {
+                    let _ = tx_ui.send(UiCmd::Quit);
+                }
+            }
+        }
+    };
+    if launch_cmd != InstanceCmd::Show {
+        on_instance_cmd(launch_cmd);
+    }
+    instance::serve(server, logs.clone(), on_instance_cmd);
+
+    // Dropped last: zeroes the motors and joins the workers on every way out of `main`.
+    let shutdown = Shutdown::new(tx_hid.clone(), tx_ui.clone(), logs.clone());
+    shutdown.install_panic_hook();
+    if let Err(e) = shutdown::install_ctrl_handler(&shutdown) {
+        logs.push(format!("Console control handler unavailable: {e}"));
+    }
+    let _shutdown_guard = shutdown.guard();
+
+    let dirs = ConfigDirs::from_env(args);
+    match logs.try_init_file(&dirs.log_path()) {
+        Ok(p) => logs.push(format!("File logging enabled → {}", p.display())),
+        Err(e) => logs.push(format!("File logging disabled: {}", e)),
+    }
+    logs.push(format!(
+        "Config directory ({:?}) → {}",
+        dirs.source,
+        dirs.config.display()
+    ));
+    if let Some(exe_dir) = config_dir::exe_dir() {
+        match dirs.migrate_from_exe_dir(&exe_dir) {
+            Ok(copied) if !copied.is_empty() => logs.push(format!(
+                "Copied {} preset file(s) from {} (originals kept)",
+                copied.len(),
+                exe_dir.display()
+            )),
+            Ok(_) => {}
+            Err(e) => logs.push(format!(
+                "Could not copy presets from {}: {e}",
+                exe_dir.display()
+            )),
+        }
+    }
+
+    let preset_store = PresetStore::new(dirs.presets_dir());
     if let Err(e) = preset_store.bootstrap() {
         logs.push(format!("Preset bootstrap failed: {e}"));
     } else {
@@ -37,25 +129,107 @@ fn main() -> Result<()> {
         ));
     }
 
-    let app_settings = preset_store.load_settings();
-    let active_kind = app_settings.active;
-    let initial_preset = preset_store.load(active_kind);
+    // Files that fail to load are shown in the UI; until then the app runs on defaults.
+    let mut file_errors = Vec::new();
+    let app_settings = preset_store.load_settings().unwrap_or_else(|e| {
+        file_errors.push((FileTarget::Settings, e));
+        AppSettings::default()
+    });
+    let initial_preset = preset_store
+        .load_id(&app_settings.active)
+        .unwrap_or_else(|e| {
+            file_errors.push((FileTarget::Preset(app_settings.active.clone()), e));
+            match &app_settings.active {
+                PresetId::BuiltIn(kind) => kind.built_in_default(),
+                PresetId::User(_) => PresetKind::default().built_in_default(),
+            }
+        });
     let saved_baseline = initial_preset.clone();
     let config = Arc::new(PresetShared::new(initial_preset));
+    match load_aircraft_overrides(&preset_store.aircraft_path()) {
+        Ok(table) => config.set_aircraft_overrides(table),
+        Err(e) => file_errors.push((FileTarget::Aircraft, e)),
+    }
+    for note in preset_store.take_notes() {
+        logs.push(note);
+    }
+    let controls: ControlsShared = Arc::new(Mutex::new(OutputControls::with_gain(
+        app_settings.master_gain_pct,
+    )));
 
-    match logs.try_init_file_prefer_exe_dir() {
-        Ok(p) => logs.push(format!("File logging enabled → {}", p.display())),
-        Err(e) => logs.push(format!("File logging disabled: {}", e)),
+    let plugins = PluginHost::load_dir(&dirs.plugins_dir());
+    for plugin in plugins.statuses() {
+        match &plugin.state {
+            PluginState::Rejected(e) => {
+                logs.push(format!("Plugin {} not loaded: {e}", plugin.path.display()))
+            }
+            _ => logs.push(format!(
+                "Plugin `{}` {} loaded from {}",
+                plugin.name,
+                plugin.version,
+                plugin.path.display()
+            )),
+        }
     }
+    plugins.configure(&config.plugin_sections());
+
+    let registry = DeviceRegistry::load_with_override(&preset_store.devices_path(), &logs);
+    let lab = LabShared::default();
 
     {
-        let controller_flag = controller_connected.clone();
+        let controller = controller.clone();
         let rx = rx_hid.clone();
+        let lab = lab.clone();
+        let input = stick_input.clone();
         let logs = logs.clone();
-        thread::spawn(move || hid_worker(controller_flag, rx, logs));
+        shutdown.spawn(HID_THREAD, move || {
+            hid_worker(controller, rx, tx_hid_events, registry, lab, input, logs)
+        });
     }
 
     let _ = tx_hid.send(HidCmd::SetSidestickVariant(app_settings.sidestick_variant));
+    let _ = tx_hid.send(HidCmd::SetSendRate(app_settings.hid_send_hz));
+    let _ = tx_hid.send(HidCmd::SetWatchdogTimeout(Duration::from_millis(
+        u64::from(app_settings.output_watchdog_ms),
+    )));
+    let _ = tx_hid.send(HidCmd::SetDutyLimits(app_settings.motor_duty));
+
+    // Between the HID worker and the UI: runs bound actions even while the window is minimized
+    // and passes every event on.
+    let button_bindings: BindingsShared = Arc::new(Mutex::new(app_settings.button_bindings));
+    let button_learning = Arc::new(AtomicBool::new(false));
+    let (tx_stick_events, rx_stick_events) = unbounded::<HidEvent>();
+    let (tx_buttons, rx_buttons) = unbounded();
+    {
+        let dispatcher = ButtonDispatcher {
+            bindings: button_bindings.clone(),
+            learning: button_learning.clone(),
+            controls: controls.clone(),
+            hold: hold.clone(),
+            tx_hid: tx_hid.clone(),
+            store: preset_store.clone(),
+            logs: logs.clone(),
+        };
+        let shutdown_c = shutdown.clone();
+        shutdown.spawn(BUTTONS_THREAD, move || {
+            while !shutdown_c.is_requested() {
+                let event = match rx_hid_events.recv_timeout(Duration::from_millis(100)) {
+                    Ok(event) => event,
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    Err(RecvTimeoutError::Disconnected) => break,
+                };
+                if let HidEvent::ButtonPressed { button, .. } = &event {
+                    for outcome in dispatcher.dispatch(*button) {
+                        if let ButtonOutcome::Held(held) = outcome {
+                            ursa_minor_ffb::tray::notify_held(held);
+                        }
+                        let _ = tx_buttons.send(outcome);
+                    }
+                }
+                let _ = tx_stick_events.send(event);
+            }
+        });
+    }
 
     {
         let last_vars_c = last_vars.clone();
@@ -66,7 +240,11 @@ fn main() -> Result<()> {
         let hold_c = hold.clone();
         let status_c = status.clone();
         let ac_title = aircraft_title.clone();
-        thread::spawn(move || {
+        let input = stick_input.clone();
+        let controls = controls.clone();
+        let plugins = plugins.clone();
+        let shutdown_c = shutdown.clone();
+        shutdown.spawn(SIM_THREAD, move || {
             sim_worker(
                 last_vars_c,
                 tx_hid_c,
@@ -76,6 +254,10 @@ fn main() -> Result<()> {
                 hold_c,
                 status_c,
                 ac_title,
+                input,
+                controls,
+                plugins,
+                shutdown_c,
             )
         });
     }
@@ -93,8 +275,10 @@ fn main() -> Result<()> {
         ..Default::default()
     };
 
-    let app = UiState::new(
-        controller_connected,
+    let preset_dir = preset_store.dir().to_path_buf();
+    let mut app = UiState::new(
+        controller,
+        rx_stick_events,
         status,
         aircraft_title,
         config,
@@ -102,19 +286,37 @@ fn main() -> Result<()> {
         saved_baseline,
         app_settings.show_live_aircraft_data,
         app_settings.sidestick_variant,
+        app_settings.hid_send_hz,
+        app_settings.output_watchdog_ms,
+        app_settings.motor_duty,
         effects,
         tx_hid.clone(),
         logs.clone(),
         last_vars,
-        hold,
+        hold.clone(),
         rx_ui,
         tx_ui.clone(),
+        lab,
+        stick_input,
+        controls,
+        button_bindings,
+        button_learning,
+        rx_buttons,
+        plugins,
     );
+    for (target, error) in file_errors {
+        app.report_file_error(target, error);
+    }
 
     let tx_ui_for_tray = tx_ui.clone();
+    let shutdown_for_tray = shutdown.clone();
 
     ursa_minor_ffb::updater::spawn_startup_check(tx_ui.clone(), env!("CARGO_PKG_VERSION"));
 
+    if let Err(e) = spawn_watcher(preset_dir, tx_ui.clone(), logs.clone()) {
+        logs.push(format!("Preset hot reload unavailable: {e}"));
+    }
+
     let run = eframe::run_native(
         "Ursa Minor FFB",
         native_options,
@@ -123,14 +325,30 @@ fn main() -> Result<()> {
             ursa_minor_ffb::tray::spawn_tray_with_ctx(
                 tx_ui_for_tray.clone(),
                 ctx.clone(),
+                shutdown_for_tray.clone(),
                 env!("CARGO_PKG_VERSION"),
             );
             Box::new(app)
         }),
     );
 
-    let _ = tx_hid.send(HidCmd::SendIntensity(0));
-    thread::sleep(Duration::from_millis(60));
-
+    shutdown.finish("window closed");
     run.map_err(|e| anyhow::anyhow!("eframe failed: {e}"))
 }
+
+/// Startup errors have no window or console to go to (release builds use the Windows
+/// subsystem), so show them in a message box.
+fn show_startup_error(message: &str) {
+    use windows::core::{w, HSTRING};
+    use windows::Win32::UI::WindowsAndMessaging::{MessageBoxW, MB_ICONERROR, MB_OK};
+
+    // SAFETY: both strings outlive the call, and no owner window is needed.
+    unsafe {
+        MessageBoxW(
+            None,
+            &HSTRING::from(message),
+            w!("Ursa Minor FFB"),
+            MB_OK | MB_ICONERROR,
+        );
+    }
+}
@@ -1,15 +1,41 @@
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 
+use crate::bindings::{ButtonBinding, MAX_GAIN_PCT};
+use crate::hid::duty::DutyLimits;
+use crate::hid::pattern::Patterns;
 use crate::hid::protocol::SidestickVariant;
+use crate::hid::session::{
+    DEFAULT_SEND_HZ, DEFAULT_WATCHDOG_TIMEOUT, MAX_SEND_HZ, MAX_WATCHDOG_TIMEOUT, MIN_SEND_HZ,
+    MIN_WATCHDOG_TIMEOUT,
+};
+use crate::rumble::custom::CustomEffectDef;
+use crate::rumble::plugin::PluginSections;
+use crate::rumble::wave::Textures;
 use crate::RumbleConfig;
 
+mod backup;
+mod compare;
+mod layers;
+pub mod migrate;
+mod package;
 mod simvars;
+mod user;
+mod validate;
+mod watch;
+pub use backup::{Backup, BACKUP_HISTORY};
+pub use compare::{changes, field_info, FieldChange, FieldInfo};
+pub use layers::{load_aircraft_overrides, AircraftOverride, RumbleOverrides};
+pub use package::{PackageError, PresetPackage, PACKAGE_FORMAT};
 pub use simvars::{canonical_extras_for, is_engine_extra_key, CORE_SIMVARS, CORE_SIMVAR_COUNT};
+pub use user::{slugify, PresetId, PresetSummary};
+pub use validate::{Location, PresetError, RumbleIssue, SimVarIssue, MAX_DECLARED_SIMVARS};
+pub use watch::{spawn_watcher, FileTarget};
 
 pub const SIMCONNECT_UNUSED_DATUM: u32 = 0xFFFF_FFFF;
 
@@ -50,8 +76,6 @@ impl PresetKind {
             "general_aviation" => PresetKind::GeneralAviation,
             "commercial" => PresetKind::Commercial,
             "fighter" => PresetKind::Fighter,
-            // Legacy: custom preset slot removed; treat as Commercial.
-            "custom" => PresetKind::Commercial,
             _ => PresetKind::Commercial,
         }
     }
@@ -126,15 +150,25 @@ impl PresetKind {
         }
 
         Preset {
+            id: PresetId::BuiltIn(self),
+            name: self.label().to_string(),
+            extends: None,
             kind: self,
             rumble,
             simvars,
+            extra_simvars: Vec::new(),
+            custom_effects: Vec::new(),
+            plugins: PluginSections::new(),
+            textures: Textures::new(),
+            patterns: Patterns::new(),
         }
     }
 }
 
+/// One subscribed simvar. Its value reaches effects and the live-data panel as `extras[key]`.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SimVarDef {
+    /// SimConnect name, e.g. `SPOILERS LEFT POSITION`; `NAME:N` is read as `datum_index: N`.
     pub name: String,
     pub unit: String,
     pub key: String,
@@ -269,25 +303,60 @@ impl SimVarLayout {
     }
 }
 
-/// On-disk preset: slider values only. SimConnect simvars stay in code defaults.
+/// On-disk preset: slider values that differ from the family defaults. SimConnect simvars stay in
+/// code defaults; `extra_simvars` adds to them (or replaces one with the same key).
+/// `custom_effects` adds effects written as expressions, `textures` reshapes built-in ones,
+/// `patterns` replaces the flaps and gear bumps with haptic patterns and `plugins` holds settings
+/// for native plugins.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 struct PresetFile {
     pub kind: PresetKind,
-    pub rumble: RumbleConfig,
+    #[serde(default)]
+    pub rumble: RumbleOverrides,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_simvars: Vec<SimVarDef>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub custom_effects: Vec<CustomEffectDef>,
+    #[serde(default, skip_serializing_if = "Textures::is_empty")]
+    pub textures: Textures,
+    #[serde(default, skip_serializing_if = "Patterns::is_empty")]
+    pub patterns: Patterns,
+    #[serde(default, skip_serializing_if = "PluginSections::is_empty")]
+    pub plugins: PluginSections,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Preset {
+    pub id: PresetId,
+    pub name: String,
+    /// Parent preset for user presets; `None` for the built-in slots.
+    pub extends: Option<PresetId>,
+    /// Family: canonical simvars and engine profile (and the slot for built-in presets).
     pub kind: PresetKind,
     pub rumble: RumbleConfig,
     pub simvars: SimVarProfile,
+    /// Simvars declared by preset files along the `extends` chain, already merged into `simvars`.
+    pub extra_simvars: Vec<SimVarDef>,
+    /// Effects defined by preset files along the `extends` chain.
+    pub custom_effects: Vec<CustomEffectDef>,
+    /// Plugin settings along the `extends` chain; a later file replaces a plugin's whole section.
+    pub plugins: PluginSections,
+    /// Waveform overrides for built-in effects; a later file replaces an effect's whole entry.
+    pub textures: Textures,
+    /// Haptic patterns for the bumps; a later file replaces an effect's whole pattern.
+    pub patterns: Patterns,
 }
 
 impl Preset {
     fn to_file(&self) -> PresetFile {
         PresetFile {
             kind: self.kind,
-            rumble: self.rumble.clone(),
+            rumble: layers::diff(&self.kind.built_in_default().rumble, &self.rumble),
+            extra_simvars: self.extra_simvars.clone(),
+            custom_effects: self.custom_effects.clone(),
+            textures: self.textures.clone(),
+            patterns: self.patterns.clone(),
+            plugins: self.plugins.clone(),
         }
     }
 
@@ -298,43 +367,117 @@ impl Preset {
     pub fn apply_canonical_simvars(&mut self, kind: PresetKind) {
         let canonical = kind.built_in_default();
         self.simvars.apply_canonical_extras(&canonical.simvars);
+        self.simvars.merge_declared(&self.extra_simvars);
     }
 
-    pub fn merge_rumble_from(&mut self, default: &Preset) {
-        if self.rumble.eng_rpm_spool_min <= 0.0 {
-            self.rumble.eng_rpm_spool_min = default.rumble.eng_rpm_spool_min;
-        }
-        if self.rumble.eng_rpm_startup_max <= 0.0 {
-            self.rumble.eng_rpm_startup_max = default.rumble.eng_rpm_startup_max;
-        }
-        if self.rumble.eng_rpm_idle <= 0.0 {
-            self.rumble.eng_rpm_idle = default.rumble.eng_rpm_idle;
+    /// Add simvars declared in a preset file; a key declared again replaces the earlier one.
+    pub fn declare_simvars(&mut self, defs: &[SimVarDef]) {
+        for def in defs {
+            let mut def = def.clone();
+            def.normalize_datum_suffix();
+            match self.extra_simvars.iter_mut().find(|d| d.key == def.key) {
+                Some(existing) => *existing = def,
+                None => self.extra_simvars.push(def),
+            }
         }
-        if self.rumble.eng_rpm_max <= self.rumble.eng_rpm_idle {
-            self.rumble.eng_rpm_max = default.rumble.eng_rpm_max;
+        self.simvars.merge_declared(&self.extra_simvars);
+    }
+
+    /// Add effects defined in a preset file; an effect defined again under the same name replaces
+    /// the earlier one.
+    pub fn define_effects(&mut self, defs: &[CustomEffectDef]) {
+        for def in defs {
+            match self.custom_effects.iter_mut().find(|d| d.name == def.name) {
+                Some(existing) => *existing = def.clone(),
+                None => self.custom_effects.push(def.clone()),
+            }
         }
     }
+
+    /// Add waveform overrides from a preset file, replacing earlier ones for the same effects.
+    pub fn add_textures(&mut self, textures: &Textures) {
+        self.textures.extend(textures.iter().map(|(e, t)| (*e, *t)));
+    }
+
+    /// Add haptic patterns from a preset file, replacing earlier ones for the same effects.
+    pub fn add_patterns(&mut self, patterns: &Patterns) {
+        self.patterns
+            .extend(patterns.iter().map(|(e, p)| (*e, p.clone())));
+    }
+
+    /// Add plugin settings from a preset file, replacing earlier sections for the same plugins.
+    pub fn configure_plugins(&mut self, sections: &PluginSections) {
+        self.plugins
+            .extend(sections.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+}
+
+/// Files saved before the engine RPM settings existed carry them as 0; give those the family
+/// defaults so the file still validates and keeps the rest of its tuning.
+fn repair_engine_rpms(rumble: &mut RumbleConfig, default: &RumbleConfig) {
+    if rumble.eng_rpm_spool_min <= 0.0 {
+        rumble.eng_rpm_spool_min = default.eng_rpm_spool_min;
+    }
+    if rumble.eng_rpm_startup_max <= 0.0 {
+        rumble.eng_rpm_startup_max = default.eng_rpm_startup_max;
+    }
+    if rumble.eng_rpm_idle <= 0.0 {
+        rumble.eng_rpm_idle = default.eng_rpm_idle;
+    }
+    if rumble.eng_rpm_max <= rumble.eng_rpm_idle {
+        rumble.eng_rpm_max = default.eng_rpm_max;
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct AppSettings {
-    pub active: PresetKind,
+    pub active: PresetId,
     #[serde(default = "default_show_live_aircraft_data")]
     pub show_live_aircraft_data: bool,
     #[serde(default)]
     pub sidestick_variant: SidestickVariant,
+    #[serde(default = "default_master_gain_pct")]
+    pub master_gain_pct: u8,
+    #[serde(default)]
+    pub button_bindings: Vec<ButtonBinding>,
+    /// Vibe report rate in Hz, capped per device by `max_send_hz` in the device registry.
+    #[serde(default = "default_hid_send_hz")]
+    pub hid_send_hz: u32,
+    /// Milliseconds without fresh intensity before the HID worker fades the output to zero.
+    #[serde(default = "default_output_watchdog_ms")]
+    pub output_watchdog_ms: u32,
+    /// Rolling-window limits on sustained motor output.
+    #[serde(default)]
+    pub motor_duty: DutyLimits,
+}
+
+fn default_hid_send_hz() -> u32 {
+    DEFAULT_SEND_HZ
+}
+
+fn default_output_watchdog_ms() -> u32 {
+    DEFAULT_WATCHDOG_TIMEOUT.as_millis() as u32
 }
 
 fn default_show_live_aircraft_data() -> bool {
     true
 }
 
+fn default_master_gain_pct() -> u8 {
+    MAX_GAIN_PCT
+}
+
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
-            active: PresetKind::Commercial,
+            active: PresetId::BuiltIn(PresetKind::Commercial),
             show_live_aircraft_data: true,
             sidestick_variant: SidestickVariant::Airbus,
+            master_gain_pct: MAX_GAIN_PCT,
+            button_bindings: Vec::new(),
+            hid_send_hz: DEFAULT_SEND_HZ,
+            output_watchdog_ms: default_output_watchdog_ms(),
+            motor_duty: DutyLimits::default(),
         }
     }
 }
@@ -346,28 +489,50 @@ struct SettingsFile {
     show_live_aircraft_data: bool,
     #[serde(default)]
     sidestick_variant: String,
+    #[serde(default = "default_master_gain_pct")]
+    master_gain_pct: u8,
+    /// Parsed entry by entry so an error can point at the binding that is wrong.
+    #[serde(default)]
+    button_bindings: Vec<serde_yaml::Value>,
+    #[serde(default = "default_hid_send_hz")]
+    hid_send_hz: u32,
+    #[serde(default = "default_output_watchdog_ms")]
+    output_watchdog_ms: u32,
+    #[serde(default)]
+    motor_duty: DutyLimits,
+}
+
+/// A preset or settings file after migration.
+struct FileRead {
+    /// Original text, for locating errors.
+    text: String,
+    value: serde_yaml::Value,
+    upgrade: Upgrade,
 }
 
+/// Migration steps that changed a file, to report once the file has loaded.
+struct Upgrade {
+    from: u32,
+    applied: Vec<&'static str>,
+}
+
+/// Clones share the settings lock, so threads that each hold one never interleave their
+/// read-modify-write of `settings.yml`.
+#[derive(Clone)]
 pub struct PresetStore {
     dir: PathBuf,
+    /// Migration reports waiting to be logged.
+    notes: Arc<Mutex<Vec<String>>>,
+    settings_lock: Arc<Mutex<()>>,
 }
 
 impl PresetStore {
-    pub fn exe_presets_dir() -> PathBuf {
-        if let Ok(exe) = std::env::current_exe() {
-            if let Some(parent) = exe.parent() {
-                return parent.join("presets");
-            }
-        }
-        PathBuf::from("presets")
-    }
-
     pub fn new(dir: PathBuf) -> Self {
-        Self { dir }
-    }
-
-    pub fn at_exe_dir() -> Self {
-        Self::new(Self::exe_presets_dir())
+        Self {
+            dir,
+            notes: Arc::default(),
+            settings_lock: Arc::default(),
+        }
     }
 
     pub fn dir(&self) -> &Path {
@@ -391,102 +556,295 @@ impl PresetStore {
         self.dir.join("settings.yml")
     }
 
-    pub fn load(&self, kind: PresetKind) -> Preset {
-        let default = kind.built_in_default();
-        let path = self.preset_path(kind);
+    /// Optional per-aircraft rumble overrides, matched against the sim's aircraft title.
+    pub fn aircraft_path(&self) -> PathBuf {
+        self.dir.join("aircraft.yml")
+    }
 
-        if !path.exists() {
-            return default;
+    /// Optional device registry override merged over the bundled list.
+    pub fn devices_path(&self) -> PathBuf {
+        self.dir.join("devices.yml")
+    }
+
+    /// Drain migration reports collected while loading files.
+    pub fn take_notes(&self) -> Vec<String> {
+        std::mem::take(&mut *self.notes.lock())
+    }
+
+    /// Read `path` and bring it to the current schema in memory; the caller passes the upgrade
+    /// to `finish_upgrade` once the file has loaded. A file from a newer schema is an error, so
+    /// saving never downgrades it.
+    fn read_migrated(
+        &self,
+        path: &Path,
+        migrate: fn(&str) -> Result<migrate::Migrated, serde_yaml::Error>,
+    ) -> Result<FileRead, PresetError> {
+        let text = fs::read_to_string(path).map_err(|e| PresetError::read(path, e))?;
+        let migrated = migrate(&text).map_err(|e| PresetError::syntax(path, e))?;
+        if migrated.from > migrated.supported {
+            return Err(PresetError::newer_schema(
+                path,
+                &text,
+                migrated.from,
+                migrated.supported,
+            ));
         }
+        Ok(FileRead {
+            text,
+            value: migrated.value,
+            upgrade: Upgrade {
+                from: migrated.from,
+                applied: migrated.applied,
+            },
+        })
+    }
 
-        let text = match fs::read_to_string(&path) {
-            Ok(text) => text,
-            Err(_) => return default,
+    /// For a file that loaded after migrating: keep the original as `<stem>.v<N>.bak`, `write`
+    /// the upgraded file and queue a note. A file that fails to load never gets here, so it is
+    /// left as it was and reported as an error instead.
+    fn finish_upgrade(
+        &self,
+        path: &Path,
+        upgrade: &Upgrade,
+        write: impl FnOnce() -> std::io::Result<()>,
+    ) {
+        if upgrade.applied.is_empty() {
+            return;
+        }
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let backup = migrate::backup_path(path, upgrade.from);
+        if let Err(e) = fs::copy(path, &backup) {
+            self.notes.lock().push(format!(
+                "{name}: could not back up before migrating ({e}); file left unchanged"
+            ));
+            return;
+        }
+        let note = match write() {
+            Ok(()) => format!(
+                "Migrated {name} from schema v{}: {} (original kept as {})",
+                upgrade.from,
+                upgrade.applied.join("; "),
+                backup.file_name().unwrap_or_default().to_string_lossy()
+            ),
+            Err(e) => format!("{name}: could not save the migrated file ({e})"),
         };
+        self.notes.lock().push(note);
+    }
 
-        let from_disk = serde_yaml::from_str::<PresetFile>(&text).ok();
+    /// A built-in slot with its saved overrides; no file means the family defaults.
+    pub fn load(&self, kind: PresetKind) -> Result<Preset, PresetError> {
+        let default = kind.built_in_default();
+        let path = self.preset_path(kind);
 
-        let Some(from_disk) = from_disk else {
-            return default;
-        };
+        if !path.exists() {
+            return Ok(default);
+        }
+
+        let read = self.read_migrated(&path, migrate::preset)?;
+        let from_disk = serde_yaml::from_value::<PresetFile>(read.value)
+            .map_err(|e| PresetError::syntax(&path, e))?;
 
         let mut preset = default;
-        preset.rumble = from_disk.rumble;
-        preset.merge_rumble_from(&kind.built_in_default());
+        preset.rumble = layers::apply_checked(&preset.rumble, &from_disk.rumble)
+            .and_then(|mut rumble| {
+                repair_engine_rpms(&mut rumble, &kind.built_in_default().rumble);
+                validate::check(&rumble).map(|()| rumble)
+            })
+            .map_err(|issue| PresetError::invalid(&path, &read.text, issue))?;
+        validate::check_simvars(&from_disk.extra_simvars)
+            .map_err(|issue| PresetError::invalid_simvar(&path, &read.text, issue))?;
+        preset.declare_simvars(&from_disk.extra_simvars);
+        preset.define_effects(&from_disk.custom_effects);
+        preset.configure_plugins(&from_disk.plugins);
+        preset.add_textures(&from_disk.textures);
+        preset.add_patterns(&from_disk.patterns);
+        validate::check_effects(&preset.custom_effects, &preset.simvars)
+            .and_then(|()| validate::check_textures(&preset.textures))
+            .and_then(|()| validate::check_patterns(&preset.patterns))
+            .map_err(|issue| PresetError::invalid_effect(&path, &read.text, issue))?;
         preset.kind = kind;
+        self.finish_upgrade(&path, &read.upgrade, || {
+            self.write_preset_file(kind, &preset)
+        });
 
-        if text.contains("simvars:") {
-            let _ = self.write_preset_file(kind, &preset);
-        }
-
-        preset
+        Ok(preset)
     }
 
     pub fn save(&self, preset: &Preset) -> std::io::Result<()> {
         fs::create_dir_all(&self.dir)?;
-        self.write_preset_file(preset.kind, preset)
+        match &preset.id {
+            PresetId::BuiltIn(kind) => self.write_preset_file(*kind, preset),
+            PresetId::User(slug) => self.write_user_file(slug, preset),
+        }
     }
 
     fn write_preset_file(&self, kind: PresetKind, preset: &Preset) -> std::io::Result<()> {
-        let path = self.preset_path(kind);
         let mut file = preset.to_file();
         file.kind = kind;
-        let text = serde_yaml::to_string(&file)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
-        fs::write(path, text)
+        let text = migrate::to_yaml(&file, migrate::PRESET_SCHEMA_VERSION)?;
+        self.write_preset_text(&PresetId::BuiltIn(kind), &text)
     }
 
-    pub fn load_settings(&self) -> AppSettings {
+    /// No file means defaults; a file that does not parse is an error so it is never overwritten.
+    pub fn load_settings(&self) -> Result<AppSettings, PresetError> {
+        let _guard = self.settings_lock.lock();
+        self.read_settings()
+    }
+
+    fn read_settings(&self) -> Result<AppSettings, PresetError> {
         let path = self.settings_path();
-        if path.exists() {
-            if let Ok(text) = fs::read_to_string(&path) {
-                if let Ok(settings) = serde_yaml::from_str::<SettingsFile>(&text) {
-                    return AppSettings {
-                        active: PresetKind::from_settings_str(&settings.active),
-                        show_live_aircraft_data: settings.show_live_aircraft_data,
-                        sidestick_variant: SidestickVariant::from_settings_str(
-                            &settings.sidestick_variant,
-                        ),
-                    };
-                }
-            }
+        if !path.exists() {
+            return Ok(AppSettings::default());
         }
-        AppSettings::default()
+        let read = self.read_migrated(&path, migrate::settings)?;
+        let file = serde_yaml::from_value::<SettingsFile>(read.value)
+            .map_err(|e| PresetError::syntax(&path, e))?;
+        let settings = AppSettings {
+            active: PresetId::from_settings_str(&file.active),
+            show_live_aircraft_data: file.show_live_aircraft_data,
+            sidestick_variant: SidestickVariant::from_settings_str(&file.sidestick_variant),
+            master_gain_pct: file.master_gain_pct.min(MAX_GAIN_PCT),
+            button_bindings: file
+                .button_bindings
+                .iter()
+                .map(|v| {
+                    serde_yaml::from_value(v.clone())
+                        .map_err(|e| PresetError::bad_binding(&path, &read.text, v, e))
+                })
+                .collect::<Result<_, _>>()?,
+            hid_send_hz: file.hid_send_hz.clamp(MIN_SEND_HZ, MAX_SEND_HZ),
+            output_watchdog_ms: file.output_watchdog_ms.clamp(
+                MIN_WATCHDOG_TIMEOUT.as_millis() as u32,
+                MAX_WATCHDOG_TIMEOUT.as_millis() as u32,
+            ),
+            motor_duty: file.motor_duty.sanitized(),
+        };
+        self.finish_upgrade(&path, &read.upgrade, || self.write_settings(&settings));
+        Ok(settings)
+    }
+
+    /// Change some settings and write them back; refuses while `settings.yml` is invalid.
+    pub fn update_settings(&self, f: impl FnOnce(&mut AppSettings)) -> std::io::Result<()> {
+        let _guard = self.settings_lock.lock();
+        let mut settings = self.read_settings()?;
+        f(&mut settings);
+        self.write_settings(&settings)
     }
 
     pub fn save_settings(&self, settings: &AppSettings) -> std::io::Result<()> {
+        let _guard = self.settings_lock.lock();
+        self.write_settings(settings)
+    }
+
+    fn write_settings(&self, settings: &AppSettings) -> std::io::Result<()> {
         fs::create_dir_all(&self.dir)?;
-        let text = serde_yaml::to_string(settings)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
-        fs::write(self.settings_path(), text)
+        let text = migrate::to_yaml(settings, migrate::SETTINGS_SCHEMA_VERSION)?;
+        backup::write_atomic(&self.settings_path(), &text)
     }
 
-    pub fn load_active(&self) -> PresetKind {
-        self.load_settings().active
+    pub fn load_active(&self) -> PresetId {
+        self.load_settings().map(|s| s.active).unwrap_or_default()
     }
 
-    pub fn save_active(&self, kind: PresetKind) -> std::io::Result<()> {
-        let mut settings = self.load_settings();
-        settings.active = kind;
-        self.save_settings(&settings)
+    pub fn save_active(&self, id: impl Into<PresetId>) -> std::io::Result<()> {
+        let id = id.into();
+        self.update_settings(|s| s.active = id)
+    }
+
+    /// Set an unusable file aside as `<stem>.invalid.bak` and put a working one in its place:
+    /// defaults for settings and built-in slots, no overrides for a user preset, an empty aircraft table.
+    /// Returns the backup path.
+    pub fn revert_file(&self, file: &Path) -> std::io::Result<PathBuf> {
+        let stem = file.file_stem().and_then(|s| s.to_str()).unwrap_or("file");
+        let backup = file.with_file_name(format!("{stem}.invalid.bak"));
+        let text = fs::read_to_string(file).unwrap_or_default();
+        fs::rename(file, &backup)?;
+        if file == self.settings_path() {
+            self.save_settings(&AppSettings::default())?;
+        } else if file.parent() == Some(self.user_dir().as_path()) {
+            self.write_blank_user_file(stem, &text)?;
+        }
+        Ok(backup)
     }
 
+    /// Drops the override file; it stays in the backup history so the reset can be undone.
     pub fn reset_to_built_in(&self, kind: PresetKind) -> Preset {
-        let path = self.preset_path(kind);
-        let _ = fs::remove_file(path);
+        let _ = self.backup_current(&PresetId::BuiltIn(kind));
+        let _ = fs::remove_file(self.preset_path(kind));
         kind.built_in_default()
     }
+
+    /// Values `reset` would restore: the parent's for user presets, the family default for built-ins.
+    pub fn reset_target(&self, preset: &Preset) -> RumbleConfig {
+        let family = preset.kind.built_in_default().rumble;
+        match &preset.extends {
+            Some(parent) => self.load_id(parent).map_or(family, |p| p.rumble),
+            None => family,
+        }
+    }
+
+    /// Built-ins drop their override file; user presets drop their overrides and match their parent.
+    pub fn reset(&self, id: &PresetId) -> Result<Preset, PresetError> {
+        match id {
+            PresetId::BuiltIn(kind) => Ok(self.reset_to_built_in(*kind)),
+            PresetId::User(slug) => {
+                let mut preset = self.load_id(id)?;
+                preset.rumble = self.reset_target(&preset);
+                let _ = self.write_user_file(slug, &preset);
+                Ok(preset)
+            }
+        }
+    }
+}
+
+/// Layers above the preset: aircraft overrides and the saved values that session tweaks are measured from.
+#[derive(Default)]
+struct RuntimeLayers {
+    saved: RumbleConfig,
+    aircraft_table: Vec<AircraftOverride>,
+    aircraft: Option<AircraftOverride>,
+}
+
+/// Side of an A/B comparison.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AbSlot {
+    A,
+    B,
+}
+
+impl AbSlot {
+    pub fn other(self) -> AbSlot {
+        match self {
+            AbSlot::A => AbSlot::B,
+            AbSlot::B => AbSlot::A,
+        }
+    }
+}
+
+/// The side of an A/B comparison that is not playing, with its own saved baseline.
+struct AbParked {
+    preset: Preset,
+    saved: RumbleConfig,
+    showing: AbSlot,
 }
 
 pub struct PresetShared {
     inner: Mutex<Preset>,
+    layers: Mutex<RuntimeLayers>,
+    ab: Mutex<Option<AbParked>>,
     rev: AtomicU64,
 }
 
 impl PresetShared {
     pub fn new(preset: Preset) -> Self {
+        let layers = RuntimeLayers {
+            saved: preset.rumble.clone(),
+            ..Default::default()
+        };
         Self {
             inner: Mutex::new(preset),
+            layers: Mutex::new(layers),
+            ab: Mutex::new(None),
             rev: AtomicU64::new(1),
         }
     }
@@ -495,11 +853,88 @@ impl PresetShared {
         self.inner.lock().clone()
     }
 
+    /// Switch to a freshly loaded preset; its values become the saved baseline.
+    /// Ends an A/B comparison.
     pub fn set(&self, v: Preset) {
+        *self.ab.lock() = None;
+        self.layers.lock().saved = v.rumble.clone();
         *self.inner.lock() = v;
         self.rev.fetch_add(1, Ordering::Relaxed);
     }
 
+    /// Current values were written to disk: they stop counting as session tweaks.
+    pub fn mark_saved(&self) {
+        self.layers.lock().saved = self.inner.lock().rumble.clone();
+    }
+
+    /// New identity after a rename, keeping unsaved edits.
+    pub fn rename(&self, id: PresetId, name: String) {
+        let mut g = self.inner.lock();
+        g.id = id;
+        g.name = name;
+    }
+
+    /// Start an A/B comparison: the current preset plays as A and `b` waits as B.
+    /// `b_saved` is B's saved baseline, for layering aircraft overrides under its session tweaks.
+    pub fn ab_start(&self, b: Preset, b_saved: RumbleConfig) {
+        *self.ab.lock() = Some(AbParked {
+            preset: b,
+            saved: b_saved,
+            showing: AbSlot::A,
+        });
+    }
+
+    /// Swap the playing side with the parked one; edits on either side are kept.
+    /// Returns the side now playing, or `None` outside a comparison.
+    pub fn ab_toggle(&self) -> Option<AbSlot> {
+        let mut ab = self.ab.lock();
+        let parked = ab.as_mut()?;
+        std::mem::swap(&mut *self.inner.lock(), &mut parked.preset);
+        std::mem::swap(&mut self.layers.lock().saved, &mut parked.saved);
+        parked.showing = parked.showing.other();
+        self.rev.fetch_add(1, Ordering::Relaxed);
+        Some(parked.showing)
+    }
+
+    /// Side playing now, if comparing.
+    pub fn ab_slot(&self) -> Option<AbSlot> {
+        self.ab.lock().as_ref().map(|p| p.showing)
+    }
+
+    /// The side that is not playing.
+    pub fn ab_parked(&self) -> Option<Preset> {
+        self.ab.lock().as_ref().map(|p| p.preset.clone())
+    }
+
+    /// End the comparison with `keep` playing; the other side is dropped.
+    pub fn ab_end(&self, keep: AbSlot) {
+        if self.ab_slot().is_some_and(|s| s != keep) {
+            self.ab_toggle();
+        }
+        *self.ab.lock() = None;
+    }
+
+    pub fn set_aircraft_overrides(&self, table: Vec<AircraftOverride>) {
+        let mut l = self.layers.lock();
+        l.aircraft_table = table;
+        l.aircraft = None;
+        self.rev.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Pick the aircraft override for the sim's current title (empty when no aircraft).
+    pub fn set_aircraft_title(&self, title: &str) {
+        let mut l = self.layers.lock();
+        let matched = layers::match_aircraft(&l.aircraft_table, title).cloned();
+        if matched != l.aircraft {
+            l.aircraft = matched;
+            self.rev.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn aircraft_override(&self) -> Option<AircraftOverride> {
+        self.layers.lock().aircraft.clone()
+    }
+
     pub fn with_mut_rumble<F: FnOnce(&mut RumbleConfig, PresetKind) -> PresetKind>(&self, f: F) {
         let mut g = self.inner.lock();
         let kind = g.kind;
@@ -507,8 +942,16 @@ impl PresetShared {
         self.rev.fetch_add(1, Ordering::Relaxed);
     }
 
+    /// Effective config: preset, then the matching aircraft override, then unsaved session tweaks.
     pub fn rumble_config(&self) -> RumbleConfig {
-        self.inner.lock().rumble.clone()
+        let current = self.inner.lock().rumble.clone();
+        let l = self.layers.lock();
+        let Some(aircraft) = &l.aircraft else {
+            return current;
+        };
+        let session = layers::diff(&l.saved, &current);
+        let with_aircraft = layers::apply(&l.saved, &aircraft.rumble);
+        layers::apply(&with_aircraft, &session)
     }
 
     pub fn layout(&self) -> SimVarLayout {
@@ -519,10 +962,39 @@ impl PresetShared {
         self.inner.lock().simvars.clone()
     }
 
+    pub fn custom_effects(&self) -> Vec<CustomEffectDef> {
+        self.inner.lock().custom_effects.clone()
+    }
+
+    pub fn textures(&self) -> Textures {
+        self.inner.lock().textures.clone()
+    }
+
+    pub fn patterns(&self) -> Patterns {
+        self.inner.lock().patterns.clone()
+    }
+
+    pub fn plugin_sections(&self) -> PluginSections {
+        self.inner.lock().plugins.clone()
+    }
+
+    /// Simvars the preset files declare on top of the family's.
+    pub fn declared_simvars(&self) -> Vec<SimVarDef> {
+        self.inner.lock().extra_simvars.clone()
+    }
+
     pub fn kind(&self) -> PresetKind {
         self.inner.lock().kind
     }
 
+    pub fn id(&self) -> PresetId {
+        self.inner.lock().id.clone()
+    }
+
+    pub fn name(&self) -> String {
+        self.inner.lock().name.clone()
+    }
+
     pub fn current_rev(&self) -> u64 {
         self.rev.load(Ordering::Relaxed)
     }
@@ -532,6 +1004,42 @@ impl PresetShared {
 mod tests {
     use super::*;
 
+    #[test]
+    fn ab_toggle_keeps_both_sides_and_their_edits() {
+        let a = PresetKind::Commercial.built_in_default();
+        let b = PresetKind::Fighter.built_in_default();
+        let shared = PresetShared::new(a.clone());
+        assert_eq!(shared.ab_toggle(), None);
+
+        shared.ab_start(b.clone(), b.rumble.clone());
+        assert_eq!(shared.ab_slot(), Some(AbSlot::A));
+        let rev = shared.current_rev();
+        assert_eq!(shared.ab_toggle(), Some(AbSlot::B));
+        assert!(shared.current_rev() > rev, "workers pick up the swap");
+        assert_eq!(shared.rumble_config(), b.rumble);
+        shared.with_mut_rumble(|cfg, kind| {
+            cfg.ground_roll = 1.0;
+            kind
+        });
+
+        assert_eq!(shared.ab_toggle(), Some(AbSlot::A));
+        assert_eq!(shared.get(), a);
+        assert_eq!(shared.ab_parked().unwrap().rumble.ground_roll, 1.0);
+
+        shared.ab_end(AbSlot::B);
+        assert_eq!(shared.ab_slot(), None);
+        assert_eq!(shared.id(), b.id);
+        assert_eq!(shared.rumble_config().ground_roll, 1.0);
+
+        shared.ab_start(a.clone(), a.rumble.clone());
+        shared.set(a);
+        assert_eq!(
+            shared.ab_slot(),
+            None,
+            "loading a preset ends the comparison"
+        );
+    }
+
     #[test]
     fn built_in_defaults_differ_by_kind() {
         let ga = PresetKind::GeneralAviation.built_in_default();
@@ -571,7 +1079,7 @@ mod tests {
         let text = fs::read_to_string(dir.join("commercial.yml")).unwrap();
         assert!(!text.contains("simvars:"));
 
-        let loaded = store.load(PresetKind::Commercial);
+        let loaded = store.load(PresetKind::Commercial).unwrap();
         assert_eq!(loaded.rumble.base_airspeed, 42.0);
         assert_eq!(loaded.simvars.extra.len(), 20);
         assert!(loaded.simvars.extra.iter().any(|d| d.key == "eng_rpm_1"));
@@ -595,7 +1103,7 @@ mod tests {
         fs::write(dir.join("commercial.yml"), yaml).unwrap();
 
         let store = PresetStore::new(dir.clone());
-        let loaded = store.load(PresetKind::Commercial);
+        let loaded = store.load(PresetKind::Commercial).unwrap();
         assert_eq!(loaded.rumble.base_airspeed, 77.0);
         assert_eq!(
             loaded.simvars,
@@ -620,7 +1128,11 @@ mod tests {
             assert!(!yaml.contains("simvars:"));
             let parsed: PresetFile = serde_yaml::from_str(&yaml).unwrap();
             assert_eq!(parsed.kind, kind);
-            assert_eq!(parsed.rumble, preset.rumble);
+            assert!(parsed.rumble.is_empty(), "defaults store no overrides");
+            assert_eq!(
+                layers::apply(&kind.built_in_default().rumble, &parsed.rumble),
+                preset.rumble
+            );
         }
     }
 
@@ -661,7 +1173,7 @@ mod tests {
         assert!(!text.contains("simvars:"));
         assert!(text.contains("base_airspeed: 33"));
 
-        let loaded = store.load(PresetKind::GeneralAviation);
+        let loaded = store.load(PresetKind::GeneralAviation).unwrap();
         assert_eq!(loaded.rumble.base_airspeed, 33.0);
         assert_eq!(
             loaded.simvars.extra,
@@ -689,7 +1201,7 @@ mod tests {
         assert!(!path.exists());
         assert_eq!(reset.rumble.base_airspeed, 18.0);
 
-        let loaded = store.load(PresetKind::Commercial);
+        let loaded = store.load(PresetKind::Commercial).unwrap();
         assert_eq!(loaded.rumble.base_airspeed, 18.0);
 
         let _ = fs::remove_dir_all(&dir);
@@ -703,8 +1215,8 @@ mod tests {
         fs::create_dir_all(&dir).unwrap();
         fs::write(dir.join("settings.yml"), "active: custom\n").unwrap();
 
-        let settings = store.load_settings();
-        assert_eq!(settings.active, PresetKind::Commercial);
+        let settings = store.load_settings().unwrap();
+        assert_eq!(settings.active, PresetId::BuiltIn(PresetKind::Commercial));
         assert!(settings.show_live_aircraft_data);
 
         let _ = fs::remove_dir_all(&dir);
@@ -717,17 +1229,20 @@ mod tests {
         let store = PresetStore::new(dir.clone());
         store.bootstrap().unwrap();
 
-        let mut settings = store.load_settings();
+        let mut settings = store.load_settings().unwrap();
         settings.show_live_aircraft_data = false;
         store.save_settings(&settings).unwrap();
 
-        let loaded = store.load_settings();
-        assert_eq!(loaded.active, PresetKind::Commercial);
+        let loaded = store.load_settings().unwrap();
+        assert_eq!(loaded.active, PresetId::BuiltIn(PresetKind::Commercial));
         assert!(!loaded.show_live_aircraft_data);
 
         store.save_active(PresetKind::Fighter).unwrap();
-        let after_preset_change = store.load_settings();
-        assert_eq!(after_preset_change.active, PresetKind::Fighter);
+        let after_preset_change = store.load_settings().unwrap();
+        assert_eq!(
+            after_preset_change.active,
+            PresetId::BuiltIn(PresetKind::Fighter)
+        );
         assert!(!after_preset_change.show_live_aircraft_data);
 
         let _ = fs::remove_dir_all(&dir);
@@ -741,16 +1256,41 @@ mod tests {
         let store = PresetStore::new(dir.clone());
         store.bootstrap().unwrap();
 
-        let mut settings = store.load_settings();
+        let mut settings = store.load_settings().unwrap();
         settings.sidestick_variant = SidestickVariant::Fighter;
         store.save_settings(&settings).unwrap();
 
-        let loaded = store.load_settings();
+        let loaded = store.load_settings().unwrap();
         assert_eq!(loaded.sidestick_variant, SidestickVariant::Fighter);
+        assert_eq!(loaded.hid_send_hz, DEFAULT_SEND_HZ);
+
+        fs::write(
+            dir.join("settings.yml"),
+            "active: fighter\nhid_send_hz: 5000\n",
+        )
+        .unwrap();
+        assert_eq!(store.load_settings().unwrap().hid_send_hz, MAX_SEND_HZ);
+        fs::write(
+            dir.join("settings.yml"),
+            "active: fighter\noutput_watchdog_ms: 1\n",
+        )
+        .unwrap();
+        assert_eq!(store.load_settings().unwrap().output_watchdog_ms, 100);
+        fs::write(
+            dir.join("settings.yml"),
+            "active: fighter\nmotor_duty: {window_s: 1, full_pct: 20}\n",
+        )
+        .unwrap();
+        let duty = store.load_settings().unwrap().motor_duty;
+        assert_eq!((duty.window_s, duty.onset_pct, duty.full_pct), (5, 45, 46));
+        store.save_settings(&loaded).unwrap();
 
         store.save_active(PresetKind::GeneralAviation).unwrap();
-        let after_preset_change = store.load_settings();
-        assert_eq!(after_preset_change.active, PresetKind::GeneralAviation);
+        let after_preset_change = store.load_settings().unwrap();
+        assert_eq!(
+            after_preset_change.active,
+            PresetId::BuiltIn(PresetKind::GeneralAviation)
+        );
         assert_eq!(
             after_preset_change.sidestick_variant,
             SidestickVariant::Fighter
@@ -0,0 +1,166 @@
+//! Crash-safe writes and a rolling history of earlier versions of each preset file.
+//! History lives in `presets/backups/<preset>/<timestamp>.yml`, newest `BACKUP_HISTORY` kept.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use chrono::{Local, NaiveDateTime};
+
+use super::{Preset, PresetError, PresetId, PresetStore};
+
+/// Earlier versions kept per preset.
+pub const BACKUP_HISTORY: usize = 10;
+
+const STAMP_FORMAT: &str = "%Y%m%d-%H%M%S%.3f";
+
+/// One saved earlier version of a preset file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backup {
+    pub path: PathBuf,
+    /// When the version was replaced (local time).
+    pub saved_at: NaiveDateTime,
+}
+
+/// Write via a temp file in the same folder, flushed to disk, then renamed over `path`,
+/// so a crash leaves either the old file or the new one, never a truncated one. Each write
+/// gets its own temp name, so concurrent writers never rename each other's half-written file.
+pub(super) fn write_atomic(path: &Path, contents: &str) -> io::Result<()> {
+    static NEXT_TMP: AtomicU64 = AtomicU64::new(0);
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(format!(
+        ".{}-{}.tmp",
+        std::process::id(),
+        NEXT_TMP.fetch_add(1, Ordering::Relaxed)
+    ));
+    let tmp = path.with_file_name(tmp_name);
+    {
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+    }
+    fs::rename(&tmp, path)
+}
+
+impl PresetStore {
+    fn backups_dir(&self, id: &PresetId) -> PathBuf {
+        let key = match id {
+            PresetId::BuiltIn(kind) => kind.file_stem().to_string(),
+            PresetId::User(slug) => format!("user-{slug}"),
+        };
+        self.dir.join("backups").join(key)
+    }
+
+    fn preset_file(&self, id: &PresetId) -> PathBuf {
+        match id {
+            PresetId::BuiltIn(kind) => self.preset_path(*kind),
+            PresetId::User(slug) => self.user_path(slug),
+        }
+    }
+
+    /// Replace `id`'s file with `text`, first adding the version it replaces to the history.
+    pub(super) fn write_preset_text(&self, id: &PresetId, text: &str) -> io::Result<()> {
+        let path = self.preset_file(id);
+        if let Ok(old) = fs::read_to_string(&path) {
+            if old != text {
+                self.push_backup(id, &old)?;
+            }
+        }
+        write_atomic(&path, text)
+    }
+
+    /// Add the current file of `id` to its history, if there is one (before a reset or delete).
+    pub(super) fn backup_current(&self, id: &PresetId) -> io::Result<()> {
+        match fs::read_to_string(self.preset_file(id)) {
+            Ok(text) => self.push_backup(id, &text),
+            Err(_) => Ok(()),
+        }
+    }
+
+    fn push_backup(&self, id: &PresetId, text: &str) -> io::Result<()> {
+        let dir = self.backups_dir(id);
+        fs::create_dir_all(&dir)?;
+        let stamp = Local::now().format(STAMP_FORMAT);
+        write_atomic(&dir.join(format!("{stamp}.yml")), text)?;
+        for old in self.backups(id).into_iter().skip(BACKUP_HISTORY) {
+            let _ = fs::remove_file(old.path);
+        }
+        Ok(())
+    }
+
+    /// Keep the history with a user preset when it is renamed.
+    pub(super) fn move_backups(&self, from: &PresetId, to: &PresetId) {
+        let (from, to) = (self.backups_dir(from), self.backups_dir(to));
+        if from.exists() && !to.exists() {
+            let _ = fs::rename(from, to);
+        }
+    }
+
+    /// Earlier versions of `id`, newest first.
+    pub fn backups(&self, id: &PresetId) -> Vec<Backup> {
+        let Ok(entries) = fs::read_dir(self.backups_dir(id)) else {
+            return Vec::new();
+        };
+        let mut out: Vec<Backup> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                if path.extension().and_then(|e| e.to_str()) != Some("yml") {
+                    return None;
+                }
+                let stem = path.file_stem()?.to_str()?;
+                let saved_at = NaiveDateTime::parse_from_str(stem, STAMP_FORMAT).ok()?;
+                Some(Backup { path, saved_at })
+            })
+            .collect();
+        out.sort_by_key(|b| std::cmp::Reverse(b.saved_at));
+        out
+    }
+
+    /// Put `backup` back as `id`'s file. The version it replaces joins the history, so a restore
+    /// can itself be undone; a backup that no longer loads is rejected and nothing changes.
+    pub fn restore_backup(&self, id: &PresetId, backup: &Backup) -> Result<Preset, PresetError> {
+        let path = self.preset_file(id);
+        let text =
+            fs::read_to_string(&backup.path).map_err(|e| PresetError::read(&backup.path, e))?;
+        let previous = fs::read_to_string(&path).ok();
+        self.write_preset_text(id, &text)
+            .map_err(|e| PresetError::read(&path, e))?;
+        match self.load_id(id) {
+            Ok(preset) => Ok(preset),
+            Err(e) => {
+                let _ = match previous {
+                    Some(previous) => write_atomic(&path, &previous),
+                    None => fs::remove_file(&path),
+                };
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn atomic_write_replaces_and_leaves_no_temp_file() {
+        let dir = std::env::temp_dir().join(format!("ursa-atomic-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("fighter.yml");
+
+        write_atomic(&path, "a").unwrap();
+        write_atomic(&path, "b").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "b");
+        let names: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .flatten()
+            .map(|e| e.file_name())
+            .collect();
+        assert_eq!(names, ["fighter.yml"]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
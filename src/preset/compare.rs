@@ -0,0 +1,172 @@
+//! Field-by-field differences between two rumble configs, with the labels and units the UI uses.
+
+use std::fmt;
+
+use serde_yaml::Value;
+
+use super::layers::to_mapping;
+use crate::RumbleConfig;
+
+/// How a `RumbleConfig` field is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldInfo {
+    pub key: &'static str,
+    pub label: &'static str,
+    /// Empty for effect strengths and plain numbers.
+    pub unit: &'static str,
+}
+
+const fn field(key: &'static str, label: &'static str, unit: &'static str) -> FieldInfo {
+    FieldInfo { key, label, unit }
+}
+
+/// Every `RumbleConfig` field, in declaration order.
+pub const FIELDS: [FieldInfo; 26] = [
+    field("base_airspeed", "Base (airspeed)", ""),
+    field("ground_roll", "Ground Roll", ""),
+    field("flaps_peak", "Flaps (bump)", ""),
+    field("gear_peak", "Landing Gear (bump)", ""),
+    field("stall_ceiling", "Stall", ""),
+    field("bank", "Bank / Turb", ""),
+    field("max_output", "Max output", ""),
+    field("smoothing_alpha", "Smoothing", ""),
+    field("ias_deadband_kn", "Airspeed deadband", "kt"),
+    field("taxi_start_kn", "Taxi thump start", "kt"),
+    field("taxi_end_kn", "Taxi thump end", "kt"),
+    field("thump_min_period_s", "Thump min period", "s"),
+    field("thump_max_period_s", "Thump max period", "s"),
+    field("thump_duty", "Thump duty", ""),
+    field("flaps_bump_duration_s", "Flaps bump length", "s"),
+    field("flaps_bump_eps_pct", "Flaps change threshold", "%"),
+    field("gear_bump_duration_s", "Gear bump length", "s"),
+    field("spoilers", "Spoilers", ""),
+    field("engine_vibe", "Engine", ""),
+    field("engine_idle_n1_pct", "Engine idle N1", "%"),
+    field("eng_rpm_spool_min", "Spool-up RPM", "rpm"),
+    field("eng_rpm_startup_max", "Start-up RPM", "rpm"),
+    field("eng_rpm_idle", "Idle RPM", "rpm"),
+    field("eng_rpm_max", "Max RPM", "rpm"),
+    field("hands_off_cut", "Cut when hand is off", ""),
+    field("deflection_gain", "Stick deflection", "%"),
+];
+
+pub fn field_info(key: &str) -> Option<&'static FieldInfo> {
+    FIELDS.iter().find(|f| f.key == key)
+}
+
+/// One setting that differs between two configs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    pub key: String,
+    pub label: &'static str,
+    pub unit: &'static str,
+    pub from: Value,
+    pub to: Value,
+}
+
+impl FieldChange {
+    /// `to - from` for numeric fields.
+    pub fn delta(&self) -> Option<f64> {
+        Some(self.to.as_f64()? - self.from.as_f64()?)
+    }
+
+    pub fn from_text(&self) -> String {
+        with_unit(&self.from, self.unit)
+    }
+
+    pub fn to_text(&self) -> String {
+        with_unit(&self.to, self.unit)
+    }
+}
+
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} → {}",
+            self.label,
+            self.from_text(),
+            self.to_text()
+        )
+    }
+}
+
+/// Short form of a setting value; floats lose the noise of their `f32` origin.
+fn show_value(v: &Value) -> String {
+    match v {
+        Value::Number(n) if n.is_f64() => {
+            let f = n.as_f64().unwrap_or_default();
+            format!("{}", (f * 1000.0).round() / 1000.0)
+        }
+        Value::Bool(b) => if *b { "on" } else { "off" }.to_string(),
+        Value::String(s) => s.clone(),
+        other => serde_yaml::to_string(other)
+            .unwrap_or_default()
+            .trim()
+            .to_string(),
+    }
+}
+
+fn with_unit(v: &Value, unit: &str) -> String {
+    match unit {
+        "" => show_value(v),
+        "%" => format!("{}%", show_value(v)),
+        unit => format!("{} {unit}", show_value(v)),
+    }
+}
+
+/// Settings that differ between `from` and `to`, in field order.
+pub fn changes(from: &RumbleConfig, to: &RumbleConfig) -> Vec<FieldChange> {
+    let to = to_mapping(to);
+    to_mapping(from)
+        .into_iter()
+        .filter_map(|(k, from)| {
+            let to = to.get(&k)?;
+            let key = k.as_str()?;
+            let info = field_info(key);
+            (to != &from).then(|| FieldChange {
+                key: key.to_string(),
+                label: info.map_or("", |i| i.label),
+                unit: info.map_or("", |i| i.unit),
+                from,
+                to: to.clone(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_field_has_a_label() {
+        let keys: Vec<String> = to_mapping(&RumbleConfig::default())
+            .keys()
+            .filter_map(|k| k.as_str().map(str::to_string))
+            .collect();
+        let table: Vec<&str> = FIELDS.iter().map(|f| f.key).collect();
+        assert_eq!(keys, table);
+    }
+
+    #[test]
+    fn changes_pair_old_and_new_values_with_units() {
+        let base = RumbleConfig::default();
+        let mut cfg = base.clone();
+        cfg.ground_roll = 71.0;
+        cfg.taxi_start_kn = 4.5;
+        cfg.hands_off_cut = !base.hands_off_cut;
+        let diff = changes(&base, &cfg);
+        let keys: Vec<&str> = diff.iter().map(|c| c.key.as_str()).collect();
+        assert_eq!(keys, ["ground_roll", "taxi_start_kn", "hands_off_cut"]);
+
+        assert_eq!(
+            diff[0].to_string(),
+            format!("Ground Roll: {} → 71", base.ground_roll)
+        );
+        assert_eq!(diff[0].delta(), Some(71.0 - base.ground_roll as f64));
+        assert_eq!(diff[1].to_text(), "4.5 kt");
+        assert_eq!(diff[2].delta(), None);
+        assert!(changes(&cfg, &cfg).is_empty());
+    }
+}
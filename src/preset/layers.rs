@@ -0,0 +1,171 @@
+//! Layered rumble configuration. Each layer stores only the fields it overrides:
+//! built-in default → family → preset chain (`extends`) → per-aircraft override → session tweaks.
+
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+
+use super::validate::{PresetError, RumbleIssue};
+use crate::RumbleConfig;
+
+/// Sparse `RumbleConfig`: field name → value, as written under `rumble:` in preset files.
+pub type RumbleOverrides = Mapping;
+
+pub(super) fn to_mapping(cfg: &RumbleConfig) -> Mapping {
+    match serde_yaml::to_value(cfg) {
+        Ok(Value::Mapping(m)) => m,
+        _ => Mapping::new(),
+    }
+}
+
+/// Fields of `cfg` that differ from `base`.
+pub fn diff(base: &RumbleConfig, cfg: &RumbleConfig) -> RumbleOverrides {
+    let base = to_mapping(base);
+    to_mapping(cfg)
+        .into_iter()
+        .filter(|(k, v)| base.get(k) != Some(v))
+        .collect()
+}
+
+/// Put one override into `merged`, leaving it unchanged if the key or value is unusable.
+fn set_field(merged: &mut Mapping, key: &Value, value: &Value) -> Result<(), RumbleIssue> {
+    let name = || match key {
+        Value::String(s) => s.clone(),
+        other => serde_yaml::to_string(other)
+            .unwrap_or_default()
+            .trim()
+            .to_string(),
+    };
+    if !merged.contains_key(key) {
+        return Err(RumbleIssue::UnknownKey(name()));
+    }
+    let previous = merged.insert(key.clone(), value.clone());
+    if let Err(e) = serde_yaml::from_value::<RumbleConfig>(Value::Mapping(merged.clone())) {
+        if let Some(previous) = previous {
+            merged.insert(key.clone(), previous);
+        }
+        return Err(RumbleIssue::BadValue {
+            key: name(),
+            message: e.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// `base` with `overrides` laid on top. Unknown fields and values of the wrong type are skipped.
+pub fn apply(base: &RumbleConfig, overrides: &RumbleOverrides) -> RumbleConfig {
+    if overrides.is_empty() {
+        return base.clone();
+    }
+    let mut merged = to_mapping(base);
+    for (k, v) in overrides {
+        let _ = set_field(&mut merged, k, v);
+    }
+    serde_yaml::from_value(Value::Mapping(merged)).unwrap_or_else(|_| base.clone())
+}
+
+/// Like `apply`, but the first unknown field or bad value is an error.
+pub fn apply_checked(
+    base: &RumbleConfig,
+    overrides: &RumbleOverrides,
+) -> Result<RumbleConfig, RumbleIssue> {
+    let mut merged = to_mapping(base);
+    for (k, v) in overrides {
+        set_field(&mut merged, k, v)?;
+    }
+    Ok(serde_yaml::from_value(Value::Mapping(merged)).unwrap_or_else(|_| base.clone()))
+}
+
+/// Rumble overrides for aircraft whose sim title contains `title_contains` (case-insensitive).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AircraftOverride {
+    pub title_contains: String,
+    #[serde(default)]
+    pub rumble: RumbleOverrides,
+}
+
+/// First entry matching `title`; an empty title (no aircraft loaded) matches nothing.
+pub fn match_aircraft<'a>(
+    table: &'a [AircraftOverride],
+    title: &str,
+) -> Option<&'a AircraftOverride> {
+    if title.is_empty() {
+        return None;
+    }
+    let title = title.to_lowercase();
+    table
+        .iter()
+        .find(|o| !o.title_contains.is_empty() && title.contains(&o.title_contains.to_lowercase()))
+}
+
+/// Read `aircraft.yml`; a missing file is an empty table.
+pub fn load_aircraft_overrides(path: &Path) -> Result<Vec<AircraftOverride>, PresetError> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let text = fs::read_to_string(path).map_err(|e| PresetError::read(path, e))?;
+    let table = serde_yaml::from_str::<Option<Vec<AircraftOverride>>>(&text)
+        .map_err(|e| PresetError::syntax(path, e))?
+        .unwrap_or_default();
+    for entry in &table {
+        apply_checked(&RumbleConfig::default(), &entry.rumble)
+            .map_err(|issue| PresetError::invalid(path, &text, issue))?;
+    }
+    Ok(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_then_apply_round_trips() {
+        let base = RumbleConfig::default();
+        let mut cfg = base.clone();
+        cfg.ground_roll = 71.0;
+        cfg.hands_off_cut = true;
+
+        let overrides = diff(&base, &cfg);
+        assert_eq!(overrides.len(), 2);
+        assert_eq!(apply(&base, &overrides), cfg);
+        assert!(diff(&cfg, &cfg).is_empty());
+    }
+
+    #[test]
+    fn apply_skips_unknown_fields_and_bad_values() {
+        let base = RumbleConfig::default();
+        let overrides: RumbleOverrides =
+            serde_yaml::from_str("ground_roll: 12\nbank: loud\nwarp: 9\n").unwrap();
+        let cfg = apply(&base, &overrides);
+        assert_eq!(cfg.ground_roll, 12.0);
+        assert_eq!(cfg.bank, base.bank);
+    }
+
+    #[test]
+    fn apply_checked_reports_the_offending_field() {
+        let base = RumbleConfig::default();
+        let overrides: RumbleOverrides = serde_yaml::from_str("groud_roll: 12\n").unwrap();
+        assert_eq!(
+            apply_checked(&base, &overrides),
+            Err(RumbleIssue::UnknownKey("groud_roll".to_string()))
+        );
+        let overrides: RumbleOverrides = serde_yaml::from_str("bank: loud\n").unwrap();
+        assert!(matches!(
+            apply_checked(&base, &overrides),
+            Err(RumbleIssue::BadValue { key, .. }) if key == "bank"
+        ));
+    }
+
+    #[test]
+    fn aircraft_match_is_case_insensitive_substring() {
+        let table: Vec<AircraftOverride> = serde_yaml::from_str(
+            "- { title_contains: PMDG 737, rumble: { ground_roll: 60 } }\n- { title_contains: '', rumble: {} }\n",
+        )
+        .unwrap();
+        assert!(match_aircraft(&table, "pmdg 737-800 Boeing House").is_some());
+        assert!(match_aircraft(&table, "Fenix A320").is_none());
+        assert!(match_aircraft(&table, "").is_none());
+    }
+}
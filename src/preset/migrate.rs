@@ -0,0 +1,249 @@
+//! `schema_version` for preset and settings files, and the steps that bring older files up to date.
+//! Files without a version are version 0. Each step takes a file from version `i` to `i + 1`.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_yaml::{Mapping, Value};
+
+pub const PRESET_SCHEMA_VERSION: u32 = 3;
+pub const SETTINGS_SCHEMA_VERSION: u32 = 1;
+
+const VERSION_KEY: &str = "schema_version";
+
+struct Migration {
+    summary: &'static str,
+    /// Returns whether the file had anything this step changes.
+    apply: fn(&mut Mapping) -> bool,
+}
+
+/// Built-in slot files and user preset files share one schema.
+const PRESET_MIGRATIONS: [Migration; PRESET_SCHEMA_VERSION as usize] = [
+    Migration {
+        summary: "dropped simvars (they come from the preset family)",
+        apply: drop_simvars,
+    },
+    Migration {
+        summary: "renamed ground_spoilers to spoilers",
+        apply: rename_ground_spoilers,
+    },
+    Migration {
+        summary: "renamed family to extends",
+        apply: rename_family_to_extends,
+    },
+];
+
+const SETTINGS_MIGRATIONS: [Migration; SETTINGS_SCHEMA_VERSION as usize] = [Migration {
+    summary: "replaced the removed custom slot with commercial",
+    apply: replace_custom_active,
+}];
+
+fn drop_simvars(map: &mut Mapping) -> bool {
+    map.remove("simvars").is_some()
+}
+
+fn rename_key(map: &mut Mapping, from: &str, to: &str) -> bool {
+    let Some(v) = map.remove(from) else {
+        return false;
+    };
+    if !map.contains_key(to) {
+        map.insert(to.into(), v);
+    }
+    true
+}
+
+fn rename_ground_spoilers(map: &mut Mapping) -> bool {
+    match map.get_mut("rumble") {
+        Some(Value::Mapping(rumble)) => rename_key(rumble, "ground_spoilers", "spoilers"),
+        _ => false,
+    }
+}
+
+fn rename_family_to_extends(map: &mut Mapping) -> bool {
+    rename_key(map, "family", "extends")
+}
+
+fn replace_custom_active(map: &mut Mapping) -> bool {
+    let custom = map.get("active").and_then(Value::as_str) == Some("custom");
+    if custom {
+        map.insert("active".into(), "commercial".into());
+    }
+    custom
+}
+
+/// A parsed file brought up to the current schema.
+#[derive(Debug)]
+pub struct Migrated {
+    /// File contents without `schema_version`, ready for typed parsing.
+    pub value: Value,
+    /// Version the file was written with.
+    pub from: u32,
+    /// Newest version these steps know; `from` above it means a newer app wrote the file.
+    pub supported: u32,
+    /// Summaries of the steps that changed the file, oldest first; empty when none did.
+    pub applied: Vec<&'static str>,
+}
+
+fn run(text: &str, steps: &[Migration]) -> Result<Migrated, serde_yaml::Error> {
+    Ok(run_value(serde_yaml::from_str(text)?, steps))
+}
+
+fn run_value(mut value: Value, steps: &[Migration]) -> Migrated {
+    let supported = steps.len() as u32;
+    let Value::Mapping(map) = &mut value else {
+        return Migrated {
+            value,
+            from: 0,
+            supported,
+            applied: Vec::new(),
+        };
+    };
+    let from = map
+        .remove(VERSION_KEY)
+        .and_then(|v| v.as_u64())
+        .map_or(0, |v| u32::try_from(v).unwrap_or(u32::MAX));
+    let mut applied = Vec::new();
+    for step in steps.iter().skip(from as usize) {
+        if (step.apply)(map) {
+            applied.push(step.summary);
+        }
+    }
+    Migrated {
+        value,
+        from,
+        supported,
+        applied,
+    }
+}
+
+pub fn preset(text: &str) -> Result<Migrated, serde_yaml::Error> {
+    run(text, &PRESET_MIGRATIONS)
+}
+
+/// Like `preset`, for YAML that is already parsed.
+pub fn preset_value(value: Value) -> Migrated {
+    run_value(value, &PRESET_MIGRATIONS)
+}
+
+pub fn settings(text: &str) -> Result<Migrated, serde_yaml::Error> {
+    run(text, &SETTINGS_MIGRATIONS)
+}
+
+/// YAML for `value` with `schema_version` as the first key.
+pub fn to_yaml<T: Serialize>(value: &T, version: u32) -> io::Result<String> {
+    let body =
+        serde_yaml::to_value(value).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let mut map = Mapping::new();
+    map.insert(VERSION_KEY.into(), version.into());
+    match body {
+        Value::Mapping(body) => map.extend(body),
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "expected a mapping",
+            ))
+        }
+    }
+    serde_yaml::to_string(&map).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Where the pre-migration copy of `path` goes, e.g. `settings.v0.bak`.
+pub fn backup_path(path: &Path, from: u32) -> PathBuf {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("file");
+    path.with_file_name(format!("{stem}.v{from}.bak"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get<'a>(v: &'a Value, key: &str) -> Option<&'a Value> {
+        v.as_mapping()?.get(key)
+    }
+
+    #[test]
+    fn unversioned_preset_runs_every_step() {
+        let m =
+            preset("kind: commercial\nsimvars:\n  extra: []\nrumble:\n  ground_spoilers: 12.0\n")
+                .unwrap();
+        assert_eq!(m.from, 0);
+        assert_eq!(
+            m.applied,
+            [PRESET_MIGRATIONS[0].summary, PRESET_MIGRATIONS[1].summary]
+        );
+        assert!(get(&m.value, "simvars").is_none());
+        let rumble = get(&m.value, "rumble").unwrap();
+        assert_eq!(get(rumble, "spoilers").and_then(Value::as_f64), Some(12.0));
+        assert!(get(rumble, "ground_spoilers").is_none());
+    }
+
+    #[test]
+    fn ground_spoilers_does_not_replace_spoilers() {
+        let mut map: Mapping =
+            serde_yaml::from_str("rumble: { ground_spoilers: 1.0, spoilers: 2.0 }").unwrap();
+        rename_ground_spoilers(&mut map);
+        let rumble = map.get("rumble").unwrap();
+        assert_eq!(get(rumble, "spoilers").and_then(Value::as_f64), Some(2.0));
+        assert!(get(rumble, "ground_spoilers").is_none());
+    }
+
+    #[test]
+    fn family_becomes_extends() {
+        let m = preset("schema_version: 2\nname: Cub\nfamily: general_aviation\n").unwrap();
+        assert_eq!(m.applied, ["renamed family to extends"]);
+        assert_eq!(
+            get(&m.value, "extends").and_then(Value::as_str),
+            Some("general_aviation")
+        );
+        assert!(get(&m.value, "family").is_none());
+    }
+
+    #[test]
+    fn settings_custom_slot_becomes_commercial() {
+        let m = settings("active: custom\n").unwrap();
+        assert_eq!(m.applied.len(), 1);
+        assert_eq!(
+            get(&m.value, "active").and_then(Value::as_str),
+            Some("commercial")
+        );
+        let m = settings("active: user:custom\n").unwrap();
+        assert_eq!(
+            get(&m.value, "active").and_then(Value::as_str),
+            Some("user:custom")
+        );
+    }
+
+    #[test]
+    fn steps_that_change_nothing_are_not_reported() {
+        let m = preset("kind: fighter\nrumble:\n  spoilers: 3.0\n").unwrap();
+        assert_eq!(m.from, 0);
+        assert!(m.applied.is_empty(), "{:?}", m.applied);
+        let m = settings("active: fighter\n").unwrap();
+        assert!(m.applied.is_empty(), "{:?}", m.applied);
+    }
+
+    #[test]
+    fn current_and_newer_files_get_no_steps() {
+        let text = format!("schema_version: {PRESET_SCHEMA_VERSION}\nkind: fighter\n");
+        assert!(preset(&text).unwrap().applied.is_empty());
+        let m = preset("schema_version: 99\nkind: fighter\n").unwrap();
+        assert_eq!((m.from, m.supported), (99, PRESET_SCHEMA_VERSION));
+        assert!(m.applied.is_empty());
+        assert_eq!(settings("{}").unwrap().supported, SETTINGS_SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn to_yaml_puts_version_first() {
+        #[derive(Serialize)]
+        struct File {
+            kind: &'static str,
+        }
+        let text = to_yaml(&File { kind: "fighter" }, 3).unwrap();
+        assert!(text.starts_with("schema_version: 3\n"), "{text}");
+        assert_eq!(
+            backup_path(Path::new("/p/settings.yml"), 0),
+            Path::new("/p/settings.v0.bak")
+        );
+    }
+}
@@ -0,0 +1,310 @@
+//! Single-file preset packages for sharing tunings, e.g. pasted into a forum post.
+//! A package names its family, author and target aircraft, stores the sliders that differ from
+//! the family default and can carry declared simvars, custom effects, textures, haptic patterns,
+//! plugin settings and per-aircraft overrides. Written with `schema_version` first.
+
+use std::io;
+
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+
+use super::compare::{self, FieldChange};
+use super::layers::{self, AircraftOverride, RumbleOverrides};
+use super::migrate;
+use super::validate::{self, PresetError, RumbleIssue, SimVarIssue};
+use super::{backup, load_aircraft_overrides, Preset, PresetKind, PresetStore, SimVarDef};
+use crate::hid::pattern::Patterns;
+use crate::rumble::custom::{CustomEffectDef, EffectIssue};
+use crate::rumble::plugin::PluginSections;
+use crate::rumble::wave::Textures;
+use crate::RumbleConfig;
+
+/// Value of `format:` that marks a file as a preset package.
+pub const PACKAGE_FORMAT: &str = "ursa-minor-ffb-preset";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PresetPackage {
+    pub format: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub author: String,
+    /// App version that wrote the package.
+    #[serde(default)]
+    pub app_version: String,
+    pub family: PresetKind,
+    /// Aircraft titles the tuning was made for.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aircraft: Vec<String>,
+    /// Sliders that differ from the family default.
+    #[serde(default)]
+    pub rumble: RumbleOverrides,
+    /// Simvars the sliders' effects rely on, e.g. an add-on's own speedbrake var.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_simvars: Vec<SimVarDef>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub custom_effects: Vec<CustomEffectDef>,
+    #[serde(default, skip_serializing_if = "Textures::is_empty")]
+    pub textures: Textures,
+    #[serde(default, skip_serializing_if = "Patterns::is_empty")]
+    pub patterns: Patterns,
+    /// Settings for native plugins; the plugins themselves are installed separately.
+    #[serde(default, skip_serializing_if = "PluginSections::is_empty")]
+    pub plugins: PluginSections,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aircraft_overrides: Vec<AircraftOverride>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PackageError {
+    #[error("{0}")]
+    Syntax(String),
+    #[error("not a preset package (expected `format: {PACKAGE_FORMAT}`)")]
+    NotAPackage,
+    #[error(
+        "made by a newer version of the app (schema {found}; this version reads up to {supported})"
+    )]
+    NewerSchema { found: u32, supported: u32 },
+    #[error("{0}")]
+    Invalid(RumbleIssue),
+    #[error("aircraft override `{title}`: {issue}")]
+    InvalidOverride { title: String, issue: RumbleIssue },
+    #[error("{0}")]
+    InvalidSimVar(SimVarIssue),
+    #[error("{0}")]
+    InvalidEffect(EffectIssue),
+}
+
+impl PresetPackage {
+    /// Package for `preset` as it is now, including unsaved slider values.
+    pub fn new(preset: &Preset, author: &str, aircraft: Vec<String>) -> Self {
+        Self {
+            format: PACKAGE_FORMAT.to_string(),
+            name: preset.name.clone(),
+            author: author.trim().to_string(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            family: preset.kind,
+            aircraft,
+            rumble: layers::diff(&preset.kind.built_in_default().rumble, &preset.rumble),
+            extra_simvars: preset.extra_simvars.clone(),
+            custom_effects: preset.custom_effects.clone(),
+            textures: preset.textures.clone(),
+            patterns: preset.patterns.clone(),
+            plugins: preset.plugins.clone(),
+            aircraft_overrides: Vec::new(),
+        }
+    }
+
+    pub fn to_yaml(&self) -> io::Result<String> {
+        migrate::to_yaml(self, migrate::PRESET_SCHEMA_VERSION)
+    }
+
+    /// Read and validate a package. Packages without `schema_version` are taken as current.
+    pub fn parse(text: &str) -> Result<Self, PackageError> {
+        let syntax = |e: serde_yaml::Error| PackageError::Syntax(e.to_string());
+        let mut value: Value = serde_yaml::from_str(text).map_err(syntax)?;
+        let Value::Mapping(map) = &mut value else {
+            return Err(PackageError::NotAPackage);
+        };
+        if map.get("format").and_then(Value::as_str) != Some(PACKAGE_FORMAT) {
+            return Err(PackageError::NotAPackage);
+        }
+        if !map.contains_key("schema_version") {
+            map.insert(
+                "schema_version".into(),
+                migrate::PRESET_SCHEMA_VERSION.into(),
+            );
+        }
+        let migrated = migrate::preset_value(value);
+        if migrated.from > migrate::PRESET_SCHEMA_VERSION {
+            return Err(PackageError::NewerSchema {
+                found: migrated.from,
+                supported: migrate::PRESET_SCHEMA_VERSION,
+            });
+        }
+        let package: Self = serde_yaml::from_value(migrated.value).map_err(syntax)?;
+        package.rumble_config().map_err(PackageError::Invalid)?;
+        validate::check_simvars(&package.extra_simvars).map_err(PackageError::InvalidSimVar)?;
+        let mut preset = package.family.built_in_default();
+        preset.declare_simvars(&package.extra_simvars);
+        validate::check_effects(&package.custom_effects, &preset.simvars)
+            .and_then(|()| validate::check_textures(&package.textures))
+            .and_then(|()| validate::check_patterns(&package.patterns))
+            .map_err(PackageError::InvalidEffect)?;
+        for entry in &package.aircraft_overrides {
+            layers::apply_checked(&RumbleConfig::default(), &entry.rumble).map_err(|issue| {
+                PackageError::InvalidOverride {
+                    title: entry.title_contains.clone(),
+                    issue,
+                }
+            })?;
+        }
+        Ok(package)
+    }
+
+    /// The packaged sliders over the family default.
+    pub fn rumble_config(&self) -> Result<RumbleConfig, RumbleIssue> {
+        let rumble = layers::apply_checked(&self.family.built_in_default().rumble, &self.rumble)?;
+        validate::check(&rumble)?;
+        Ok(rumble)
+    }
+
+    /// What importing would change compared to `current`.
+    pub fn preview(&self, current: &RumbleConfig) -> Vec<FieldChange> {
+        match self.rumble_config() {
+            Ok(rumble) => compare::changes(current, &rumble),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+impl PresetStore {
+    /// Package `preset`; with `include_overrides`, entries of `aircraft.yml` matching one of
+    /// `aircraft` go along.
+    pub fn export_package(
+        &self,
+        preset: &Preset,
+        author: &str,
+        aircraft: Vec<String>,
+        include_overrides: bool,
+    ) -> PresetPackage {
+        let mut package = PresetPackage::new(preset, author, aircraft);
+        if include_overrides {
+            let table = load_aircraft_overrides(&self.aircraft_path()).unwrap_or_default();
+            package.aircraft_overrides = table
+                .into_iter()
+                .filter(|o| {
+                    package
+                        .aircraft
+                        .iter()
+                        .any(|t| layers::match_aircraft(std::slice::from_ref(o), t).is_some())
+                })
+                .collect();
+        }
+        package
+    }
+
+    /// Create a user preset named `name` from `package` in its family, and with
+    /// `include_overrides` merge its aircraft overrides into `aircraft.yml`.
+    pub fn import_package(
+        &self,
+        package: &PresetPackage,
+        name: &str,
+        include_overrides: bool,
+    ) -> io::Result<Preset> {
+        let rumble = package
+            .rumble_config()
+            .map_err(|issue| io::Error::new(io::ErrorKind::InvalidData, issue.to_string()))?;
+        // Check the name first so a failed import leaves `aircraft.yml` alone.
+        self.check_name(name, None)?;
+        if include_overrides && !package.aircraft_overrides.is_empty() {
+            self.merge_aircraft_overrides(&package.aircraft_overrides)?;
+        }
+        let mut preset = self.create_user(name, package.family, rumble)?;
+        self.add_definitions(
+            &mut preset,
+            &package.extra_simvars,
+            &package.custom_effects,
+            &package.textures,
+            &package.patterns,
+            &package.plugins,
+        )?;
+        Ok(preset)
+    }
+
+    /// `name`, or `name (2)`, `name (3)`, ... if a preset already uses it.
+    pub fn unused_name(&self, name: &str) -> String {
+        let name = name.trim();
+        let mut candidate = name.to_string();
+        let mut n = 2;
+        while self.check_name(&candidate, None).is_err() && !candidate.trim().is_empty() {
+            candidate = format!("{name} ({n})");
+            n += 1;
+        }
+        candidate
+    }
+
+    /// Add `entries` to `aircraft.yml`, replacing entries for the same title.
+    /// Refuses while the file is invalid so hand edits are never lost.
+    pub fn merge_aircraft_overrides(
+        &self,
+        entries: &[AircraftOverride],
+    ) -> Result<(), PresetError> {
+        let path = self.aircraft_path();
+        let mut table = load_aircraft_overrides(&path)?;
+        for entry in entries {
+            let same =
+                |o: &AircraftOverride| o.title_contains.eq_ignore_ascii_case(&entry.title_contains);
+            match table.iter_mut().find(|o| same(o)) {
+                Some(existing) => *existing = entry.clone(),
+                None => table.push(entry.clone()),
+            }
+        }
+        let text = serde_yaml::to_string(&table)
+            .map_err(|e| PresetError::read(&path, io::Error::new(io::ErrorKind::InvalidData, e)))?;
+        backup::write_atomic(&path, &text).map_err(|e| PresetError::read(&path, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fighter(ground_roll: f32) -> Preset {
+        let mut preset = PresetKind::Fighter.built_in_default();
+        preset.name = "Viper".to_string();
+        preset.rumble.ground_roll = ground_roll;
+        preset
+    }
+
+    #[test]
+    fn package_round_trips_through_yaml() {
+        let package = PresetPackage::new(&fighter(12.0), " Ana ", vec!["F-16C".to_string()]);
+        let text = package.to_yaml().unwrap();
+        assert!(text.starts_with("schema_version: "), "{text}");
+        assert!(text.contains("format: ursa-minor-ffb-preset"), "{text}");
+        let parsed = PresetPackage::parse(&text).unwrap();
+        assert_eq!(parsed, package);
+        assert_eq!(parsed.author, "Ana");
+        assert_eq!(parsed.rumble_config().unwrap().ground_roll, 12.0);
+    }
+
+    #[test]
+    fn preview_lists_changed_sliders_only() {
+        let package = PresetPackage::new(&fighter(12.0), "", Vec::new());
+        let current = fighter(30.0).rumble;
+        let changes = package.preview(&current);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].key, "ground_roll");
+    }
+
+    #[test]
+    fn parse_rejects_other_files_newer_schemas_and_bad_values() {
+        assert!(matches!(
+            PresetPackage::parse("kind: fighter\nrumble: {}\n"),
+            Err(PackageError::NotAPackage)
+        ));
+
+        let hand_written = "format: ursa-minor-ffb-preset\nname: Cub\nfamily: general_aviation\n";
+        assert_eq!(PresetPackage::parse(hand_written).unwrap().name, "Cub");
+
+        let newer = format!("schema_version: 99\n{hand_written}");
+        assert!(matches!(
+            PresetPackage::parse(&newer),
+            Err(PackageError::NewerSchema { found: 99, .. })
+        ));
+
+        let bad = format!("{hand_written}rumble: {{ smoothing_alpha: 3.0 }}\n");
+        assert!(matches!(
+            PresetPackage::parse(&bad),
+            Err(PackageError::Invalid(RumbleIssue::SmoothingAlpha(_)))
+        ));
+
+        let bad_override = format!(
+            "{hand_written}aircraft_overrides:\n  - {{ title_contains: Cub, rumble: {{ warp: 1 }} }}\n"
+        );
+        assert!(matches!(
+            PresetPackage::parse(&bad_override),
+            Err(PackageError::InvalidOverride { .. })
+        ));
+    }
+}
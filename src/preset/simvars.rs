@@ -202,6 +202,18 @@ impl SimVarProfile {
         self.normalize();
     }
 
+    /// Add simvars declared by preset files, replacing a canonical entry with the same key
+    /// (e.g. a dedicated speedbrake var for `spoilers_pct`).
+    pub fn merge_declared(&mut self, defs: &[SimVarDef]) {
+        for def in defs {
+            match self.extra.iter_mut().find(|d| d.key == def.key) {
+                Some(existing) => *existing = def.clone(),
+                None => self.extra.push(def.clone()),
+            }
+        }
+        self.normalize();
+    }
+
     pub fn strip_obsolete_extras(&mut self) {
         self.extra
             .retain(|d| !OBSOLETE_EXTRA_KEYS.contains(&d.key.as_str()));
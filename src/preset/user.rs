@@ -0,0 +1,528 @@
+//! Named user presets: any number of tunings, each extending a built-in family
+//! (`PresetKind`) or another user preset and storing only the sliders it changes.
+//! The family at the root of the chain supplies canonical simvars and the engine profile;
+//! each file in the chain may declare more (`extra_simvars`), add `custom_effects`, reshape
+//! built-in effects (`textures`), attach haptic `patterns` to the bumps and set `plugins` sections.
+//! Stored as `presets/user/<slug>.yml`.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::layers::{self, RumbleOverrides};
+use super::migrate;
+use super::validate::{self, PresetError};
+use super::{Preset, PresetKind, PresetStore, SimVarDef, Upgrade};
+use crate::hid::pattern::Patterns;
+use crate::rumble::custom::CustomEffectDef;
+use crate::rumble::plugin::PluginSections;
+use crate::rumble::wave::Textures;
+use crate::RumbleConfig;
+
+const USER_ID_PREFIX: &str = "user:";
+/// Longest `extends` chain followed before falling back to the default family.
+const MAX_EXTENDS_DEPTH: usize = 8;
+
+/// Which preset is meant: one of the built-in family slots or a user preset by slug.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PresetId {
+    BuiltIn(PresetKind),
+    User(String),
+}
+
+impl Default for PresetId {
+    fn default() -> Self {
+        PresetId::BuiltIn(PresetKind::default())
+    }
+}
+
+impl From<PresetKind> for PresetId {
+    fn from(kind: PresetKind) -> Self {
+        PresetId::BuiltIn(kind)
+    }
+}
+
+impl PresetId {
+    /// `settings.yml` form: the family stem for built-ins, `user:<slug>` otherwise.
+    pub fn settings_str(&self) -> String {
+        match self {
+            PresetId::BuiltIn(kind) => kind.file_stem().to_string(),
+            PresetId::User(slug) => format!("{USER_ID_PREFIX}{slug}"),
+        }
+    }
+
+    pub fn from_settings_str(s: &str) -> Self {
+        match s.strip_prefix(USER_ID_PREFIX) {
+            Some(slug) if !slug.is_empty() => PresetId::User(slug.to_string()),
+            _ => PresetId::BuiltIn(PresetKind::from_settings_str(s)),
+        }
+    }
+}
+
+impl fmt::Display for PresetId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.settings_str())
+    }
+}
+
+impl Serialize for PresetId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.settings_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for PresetId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Ok(PresetId::from_settings_str(&s))
+    }
+}
+
+/// One entry of the preset picker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PresetSummary {
+    pub id: PresetId,
+    pub name: String,
+    pub family: PresetKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct UserPresetFile {
+    name: String,
+    extends: PresetId,
+    #[serde(default)]
+    rumble: RumbleOverrides,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    extra_simvars: Vec<SimVarDef>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    custom_effects: Vec<CustomEffectDef>,
+    #[serde(default, skip_serializing_if = "Textures::is_empty")]
+    textures: Textures,
+    #[serde(default, skip_serializing_if = "Patterns::is_empty")]
+    patterns: Patterns,
+    #[serde(default, skip_serializing_if = "PluginSections::is_empty")]
+    plugins: PluginSections,
+}
+
+/// File-name-safe form of a preset name: lowercase ASCII letters/digits separated by `-`.
+pub fn slugify(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.trim().chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    while slug.ends_with('-') {
+        slug.pop();
+    }
+    if slug.is_empty() {
+        slug.push_str("preset");
+    }
+    slug
+}
+
+fn invalid_name(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg.to_string())
+}
+
+impl PresetStore {
+    pub(super) fn user_dir(&self) -> PathBuf {
+        self.dir.join("user")
+    }
+
+    pub(super) fn user_path(&self, slug: &str) -> PathBuf {
+        self.user_dir().join(format!("{slug}.yml"))
+    }
+
+    /// Slug for `name` that no other user preset file uses (`keep` may be reused).
+    fn unique_slug(&self, name: &str, keep: Option<&str>) -> String {
+        let base = slugify(name);
+        let mut slug = base.clone();
+        let mut n = 2;
+        while Some(slug.as_str()) != keep && self.user_path(&slug).exists() {
+            slug = format!("{base}-{n}");
+            n += 1;
+        }
+        slug
+    }
+
+    pub(super) fn check_name(&self, name: &str, skip: Option<&PresetId>) -> io::Result<String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(invalid_name("Preset name is empty"));
+        }
+        let taken = self
+            .all_presets()
+            .into_iter()
+            .any(|p| Some(&p.id) != skip && p.name.eq_ignore_ascii_case(name));
+        if taken {
+            return Err(invalid_name("A preset with that name already exists"));
+        }
+        Ok(name.to_string())
+    }
+
+    /// Built-in families first, then user presets by name.
+    pub fn all_presets(&self) -> Vec<PresetSummary> {
+        let mut out: Vec<PresetSummary> = PresetKind::ALL
+            .iter()
+            .map(|kind| PresetSummary {
+                id: PresetId::BuiltIn(*kind),
+                name: kind.label().to_string(),
+                family: *kind,
+            })
+            .collect();
+        out.extend(self.user_presets());
+        out
+    }
+
+    /// Every user preset file; ones that fail to load are listed by slug so they can be fixed.
+    pub fn user_presets(&self) -> Vec<PresetSummary> {
+        let mut out: Vec<PresetSummary> = self
+            .user_presets_raw()
+            .into_iter()
+            .map(|(slug, file)| {
+                let family = self.load_user(&slug).map(|p| p.kind).unwrap_or_default();
+                let name = file.map_or_else(|_| slug.clone(), |f| f.name);
+                PresetSummary {
+                    id: PresetId::User(slug),
+                    name,
+                    family,
+                }
+            })
+            .collect();
+        out.sort_by_key(|p| p.name.to_lowercase());
+        out
+    }
+
+    /// Preset after `current` in `all_presets`, wrapping around.
+    pub fn next_preset(&self, current: &PresetId) -> PresetId {
+        let all = self.all_presets();
+        let i = all.iter().position(|p| &p.id == current);
+        let next = i.map_or(0, |i| (i + 1) % all.len());
+        all[next].id.clone()
+    }
+
+    /// The file, its text and the migration `load_user_chain` finishes once the preset loads.
+    fn read_user_file(&self, slug: &str) -> Result<(UserPresetFile, String, Upgrade), PresetError> {
+        let path = self.user_path(slug);
+        let read = self.read_migrated(&path, migrate::preset)?;
+        let file: UserPresetFile =
+            serde_yaml::from_value(read.value).map_err(|e| PresetError::syntax(&path, e))?;
+        Ok((file, read.text, read.upgrade))
+    }
+
+    /// Fresh file for `slug` with no overrides, keeping the name and parent from `old_text` if readable.
+    pub(super) fn write_blank_user_file(&self, slug: &str, old_text: &str) -> io::Result<()> {
+        let old: serde_yaml::Mapping = serde_yaml::from_str(old_text).unwrap_or_default();
+        let field = |key: &str| old.get(key).and_then(|v| v.as_str()).map(str::to_string);
+        let extends = field("extends")
+            .or_else(|| field("family"))
+            .map(|s| PresetId::from_settings_str(&s))
+            .filter(|id| !matches!(id, PresetId::User(s) if !self.user_path(s).exists()))
+            .unwrap_or_default();
+        let file = UserPresetFile {
+            name: field("name").unwrap_or_else(|| slug.to_string()),
+            extends,
+            rumble: RumbleOverrides::new(),
+            extra_simvars: Vec::new(),
+            custom_effects: Vec::new(),
+            textures: Textures::new(),
+            patterns: Patterns::new(),
+            plugins: PluginSections::new(),
+        };
+        self.write_raw_user_file(slug, &file)
+    }
+
+    fn write_raw_user_file(&self, slug: &str, file: &UserPresetFile) -> io::Result<()> {
+        fs::create_dir_all(self.user_dir())?;
+        let text = migrate::to_yaml(file, migrate::PRESET_SCHEMA_VERSION)?;
+        self.write_preset_text(&PresetId::User(slug.to_string()), &text)
+    }
+
+    /// Slugs of user presets whose `extends` is `parent`.
+    fn children_of(&self, parent: &PresetId) -> Vec<String> {
+        self.user_presets_raw()
+            .into_iter()
+            .filter(|(_, file)| file.as_ref().is_ok_and(|f| &f.extends == parent))
+            .map(|(slug, _)| slug)
+            .collect()
+    }
+
+    fn user_presets_raw(&self) -> Vec<(String, Result<UserPresetFile, PresetError>)> {
+        let Ok(entries) = fs::read_dir(self.user_dir()) else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                if path.extension().and_then(|e| e.to_str()) != Some("yml") {
+                    return None;
+                }
+                let slug = path.file_stem()?.to_str()?.to_string();
+                let file = self.read_user_file(&slug).map(|(file, _, _)| file);
+                Some((slug, file))
+            })
+            .collect()
+    }
+
+    pub fn load_user(&self, slug: &str) -> Result<Preset, PresetError> {
+        self.load_user_chain(slug, &mut Vec::new())
+    }
+
+    /// Resolve `slug` over its parent. A missing parent or a cycle in hand-edited files falls
+    /// back to the default family; a parent that fails to load is reported as its own error.
+    fn load_user_chain(&self, slug: &str, seen: &mut Vec<String>) -> Result<Preset, PresetError> {
+        let (file, text, upgrade) = self.read_user_file(slug)?;
+        let upgraded = (!upgrade.applied.is_empty()).then(|| file.clone());
+        seen.push(slug.to_string());
+        let parent = match &file.extends {
+            PresetId::BuiltIn(kind) => self.load(*kind)?,
+            PresetId::User(parent)
+                if seen.contains(parent)
+                    || seen.len() > MAX_EXTENDS_DEPTH
+                    || !self.user_path(parent).exists() =>
+            {
+                self.load(PresetKind::default())?
+            }
+            PresetId::User(parent) => self.load_user_chain(parent, seen)?,
+        };
+        let path = self.user_path(slug);
+        let mut preset = parent;
+        preset.id = PresetId::User(slug.to_string());
+        preset.name = file.name;
+        let family = preset.kind.built_in_default();
+        preset.rumble = layers::apply_checked(&preset.rumble, &file.rumble)
+            .and_then(|mut rumble| {
+                super::repair_engine_rpms(&mut rumble, &family.rumble);
+                validate::check(&rumble).map(|()| rumble)
+            })
+            .map_err(|issue| PresetError::invalid(&path, &text, issue))?;
+        validate::check_simvars(&file.extra_simvars)
+            .map_err(|issue| PresetError::invalid_simvar(&path, &text, issue))?;
+        preset.declare_simvars(&file.extra_simvars);
+        preset.define_effects(&file.custom_effects);
+        preset.configure_plugins(&file.plugins);
+        preset.add_textures(&file.textures);
+        preset.add_patterns(&file.patterns);
+        validate::check_effects(&preset.custom_effects, &preset.simvars)
+            .and_then(|()| validate::check_textures(&preset.textures))
+            .and_then(|()| validate::check_patterns(&preset.patterns))
+            .map_err(|issue| PresetError::invalid_effect(&path, &text, issue))?;
+        preset.extends = Some(file.extends);
+        if let Some(upgraded) = upgraded {
+            self.finish_upgrade(&path, &upgrade, || {
+                self.write_raw_user_file(slug, &upgraded)
+            });
+        }
+        Ok(preset)
+    }
+
+    /// Load any preset, built-in or user.
+    pub fn load_id(&self, id: &PresetId) -> Result<Preset, PresetError> {
+        match id {
+            PresetId::BuiltIn(kind) => self.load(*kind),
+            PresetId::User(slug) => self.load_user(slug),
+        }
+    }
+
+    /// Write `preset` as the sliders, simvars, effects, textures, patterns and plugin settings that
+    /// differ from its parent.
+    pub(super) fn write_user_file(&self, slug: &str, preset: &Preset) -> io::Result<()> {
+        let extends = preset
+            .extends
+            .clone()
+            .unwrap_or(PresetId::BuiltIn(preset.kind));
+        let parent = self.load_id(&extends)?;
+        let file = UserPresetFile {
+            name: preset.name.clone(),
+            rumble: layers::diff(&parent.rumble, &preset.rumble),
+            extra_simvars: preset
+                .extra_simvars
+                .iter()
+                .filter(|d| !parent.extra_simvars.contains(d))
+                .cloned()
+                .collect(),
+            custom_effects: preset
+                .custom_effects
+                .iter()
+                .filter(|e| !parent.custom_effects.contains(e))
+                .cloned()
+                .collect(),
+            textures: preset
+                .textures
+                .iter()
+                .filter(|(e, t)| parent.textures.get(*e) != Some(*t))
+                .map(|(e, t)| (*e, *t))
+                .collect(),
+            patterns: preset
+                .patterns
+                .iter()
+                .filter(|(e, p)| parent.patterns.get(*e) != Some(*p))
+                .map(|(e, p)| (*e, p.clone()))
+                .collect(),
+            plugins: preset
+                .plugins
+                .iter()
+                .filter(|(k, v)| parent.plugins.get(*k) != Some(*v))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            extends,
+        };
+        self.write_raw_user_file(slug, &file)
+    }
+
+    /// New user preset extending `extends` (a family or another user preset) with the given sliders.
+    pub fn create_user(
+        &self,
+        name: &str,
+        extends: impl Into<PresetId>,
+        rumble: RumbleConfig,
+    ) -> io::Result<Preset> {
+        let name = self.check_name(name, None)?;
+        let slug = self.unique_slug(&name, None);
+        let extends = extends.into();
+        let mut preset = self.load_id(&extends)?;
+        preset.id = PresetId::User(slug.clone());
+        preset.name = name;
+        preset.rumble = rumble;
+        preset.extends = Some(extends);
+        self.write_user_file(&slug, &preset)?;
+        Ok(preset)
+    }
+
+    /// Copy of `source` (built-in or user, including unsaved slider values) under a new name.
+    /// The copy shares the source's parent, so both keep following upstream changes.
+    pub fn duplicate(&self, source: &Preset, name: &str) -> io::Result<Preset> {
+        let extends = source
+            .extends
+            .clone()
+            .unwrap_or(PresetId::BuiltIn(source.kind));
+        let mut preset = self.create_user(name, extends, source.rumble.clone())?;
+        self.add_definitions(
+            &mut preset,
+            &source.extra_simvars,
+            &source.custom_effects,
+            &source.textures,
+            &source.patterns,
+            &source.plugins,
+        )?;
+        Ok(preset)
+    }
+
+    /// Declare `simvars`, define `effects`, set `textures`, `patterns` and `plugins` sections on
+    /// `preset` and save it; nothing is written when it already has them all.
+    pub(super) fn add_definitions(
+        &self,
+        preset: &mut Preset,
+        simvars: &[SimVarDef],
+        effects: &[CustomEffectDef],
+        textures: &Textures,
+        patterns: &Patterns,
+        plugins: &PluginSections,
+    ) -> io::Result<()> {
+        if simvars.iter().all(|d| preset.extra_simvars.contains(d))
+            && effects.iter().all(|e| preset.custom_effects.contains(e))
+            && textures
+                .iter()
+                .all(|(e, t)| preset.textures.get(e) == Some(t))
+            && patterns
+                .iter()
+                .all(|(e, p)| preset.patterns.get(e) == Some(p))
+            && plugins
+                .iter()
+                .all(|(k, v)| preset.plugins.get(k) == Some(v))
+        {
+            return Ok(());
+        }
+        preset.declare_simvars(simvars);
+        preset.define_effects(effects);
+        preset.add_textures(textures);
+        preset.add_patterns(patterns);
+        preset.configure_plugins(plugins);
+        self.save(preset)
+    }
+
+    /// Rename a user preset; its file (and the active pointer, if it was active) follow the new name.
+    pub fn rename_user(&self, id: &PresetId, name: &str) -> io::Result<Preset> {
+        let PresetId::User(old_slug) = id else {
+            return Err(invalid_name("Built-in presets cannot be renamed"));
+        };
+        let name = self.check_name(name, Some(id))?;
+        let mut preset = self.load_user(old_slug)?;
+        let slug = self.unique_slug(&name, Some(old_slug));
+        preset.id = PresetId::User(slug.clone());
+        preset.name = name;
+        self.write_user_file(&slug, &preset)?;
+        if slug != *old_slug {
+            for child in self.children_of(id) {
+                if let Ok((mut file, _, _)) = self.read_user_file(&child) {
+                    file.extends = preset.id.clone();
+                    self.write_raw_user_file(&child, &file)?;
+                }
+            }
+            fs::remove_file(self.user_path(old_slug))?;
+            self.move_backups(id, &preset.id);
+        }
+        if &self.load_active() == id {
+            self.save_active(preset.id.clone())?;
+        }
+        Ok(preset)
+    }
+
+    /// Delete a user preset; presets extending it move to its parent with its values baked in.
+    /// If it was active, its family becomes active.
+    pub fn delete_user(&self, id: &PresetId) -> io::Result<PresetId> {
+        let PresetId::User(slug) = id else {
+            return Err(invalid_name("Built-in presets cannot be deleted"));
+        };
+        let deleted = self.load_user(slug).ok();
+        let family = deleted.as_ref().map(|p| p.kind).unwrap_or_default();
+        let grandparent = deleted
+            .and_then(|p| p.extends)
+            .unwrap_or(PresetId::BuiltIn(family));
+        for child in self.children_of(id) {
+            if let Ok(mut preset) = self.load_user(&child) {
+                preset.extends = Some(grandparent.clone());
+                self.write_user_file(&child, &preset)?;
+            }
+        }
+        fs::remove_file(self.user_path(slug))?;
+        let fallback = PresetId::BuiltIn(family);
+        if &self.load_active() == id {
+            self.save_active(fallback.clone())?;
+        }
+        Ok(fallback)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slugify_keeps_ascii_words() {
+        assert_eq!(slugify("PMDG 737-800"), "pmdg-737-800");
+        assert_eq!(slugify("  Fenix A320 (CFM) "), "fenix-a320-cfm");
+        assert_eq!(slugify("★"), "preset");
+    }
+
+    #[test]
+    fn preset_id_settings_str_round_trips() {
+        for id in [
+            PresetId::BuiltIn(PresetKind::Fighter),
+            PresetId::User("bonanza".to_string()),
+        ] {
+            assert_eq!(PresetId::from_settings_str(&id.settings_str()), id);
+        }
+        assert_eq!(
+            PresetId::from_settings_str("custom"),
+            PresetId::BuiltIn(PresetKind::Commercial)
+        );
+    }
+}
@@ -0,0 +1,473 @@
+//! Errors for preset and settings files that cannot be used, with the line and column to fix.
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::{SimVarDef, SimVarProfile};
+use crate::hid::pattern::{PatternIssue, Patterns, PATTERN_EFFECTS};
+use crate::rumble::custom::{self, CustomEffectDef, EffectIssue};
+use crate::rumble::wave::Textures;
+use crate::RumbleConfig;
+
+/// Most simvars one preset chain may declare, to keep the SimConnect packet small.
+pub const MAX_DECLARED_SIMVARS: usize = 32;
+
+/// 1-based position in a YAML file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+fn at(location: &Option<Location>) -> String {
+    location.map(|l| format!(":{l}")).unwrap_or_default()
+}
+
+/// A rumble value that parses but cannot be used.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum RumbleIssue {
+    #[error("unknown setting `{0}`")]
+    UnknownKey(String),
+    #[error("`{key}`: {message}")]
+    BadValue { key: String, message: String },
+    #[error("taxi_start_kn ({start}) must be below taxi_end_kn ({end})")]
+    TaxiRange { start: f64, end: f64 },
+    #[error("eng_rpm_max ({max}) must be above eng_rpm_idle ({idle})")]
+    EngineRpmRange { idle: f32, max: f32 },
+    #[error("`{key}` must not be negative ({value})")]
+    NegativeDuration { key: &'static str, value: f64 },
+    #[error("smoothing_alpha ({0}) must be between 0 and 1")]
+    SmoothingAlpha(f32),
+}
+
+impl RumbleIssue {
+    /// Setting the issue points at, for locating it in the file.
+    pub fn key(&self) -> &str {
+        match self {
+            RumbleIssue::UnknownKey(key) | RumbleIssue::BadValue { key, .. } => key,
+            RumbleIssue::TaxiRange { .. } => "taxi_start_kn",
+            RumbleIssue::EngineRpmRange { .. } => "eng_rpm_max",
+            RumbleIssue::NegativeDuration { key, .. } => key,
+            RumbleIssue::SmoothingAlpha(_) => "smoothing_alpha",
+        }
+    }
+}
+
+/// A declared simvar that cannot be subscribed.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum SimVarIssue {
+    #[error("simvar key `{0}` must be lowercase letters, digits and `_`")]
+    BadKey(String),
+    #[error("simvar key `{0}` is declared twice")]
+    DuplicateKey(String),
+    #[error("simvar `{key}` needs a SimConnect name")]
+    BadName { key: String },
+    #[error("simvar `{key}` needs a unit")]
+    MissingUnit { key: String },
+    #[error("{0} simvars declared; at most {MAX_DECLARED_SIMVARS} are allowed")]
+    TooMany(usize),
+}
+
+impl SimVarIssue {
+    pub fn key(&self) -> Option<&str> {
+        match self {
+            SimVarIssue::BadKey(key)
+            | SimVarIssue::DuplicateKey(key)
+            | SimVarIssue::BadName { key }
+            | SimVarIssue::MissingUnit { key } => Some(key),
+            SimVarIssue::TooMany(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PresetError {
+    #[error("cannot read {}: {source}", file.display())]
+    Read {
+        file: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("{}{}: {message}", file.display(), at(location))]
+    Syntax {
+        file: PathBuf,
+        location: Option<Location>,
+        message: String,
+    },
+    #[error("{}{}: {issue}", file.display(), at(location))]
+    Invalid {
+        file: PathBuf,
+        location: Option<Location>,
+        issue: RumbleIssue,
+    },
+    #[error("{}{}: {issue}", file.display(), at(location))]
+    InvalidSimVar {
+        file: PathBuf,
+        location: Option<Location>,
+        issue: SimVarIssue,
+    },
+    #[error("{}{}: {issue}", file.display(), at(location))]
+    InvalidEffect {
+        file: PathBuf,
+        location: Option<Location>,
+        issue: Box<EffectIssue>,
+    },
+    #[error(
+        "{}{}: written by a newer version of the app (schema {found}; this version reads up to {supported})",
+        file.display(),
+        at(location)
+    )]
+    NewerSchema {
+        file: PathBuf,
+        location: Option<Location>,
+        found: u32,
+        supported: u32,
+    },
+}
+
+impl PresetError {
+    pub fn file(&self) -> &Path {
+        match self {
+            PresetError::Read { file, .. }
+            | PresetError::Syntax { file, .. }
+            | PresetError::Invalid { file, .. }
+            | PresetError::InvalidSimVar { file, .. }
+            | PresetError::InvalidEffect { file, .. }
+            | PresetError::NewerSchema { file, .. } => file,
+        }
+    }
+
+    pub fn location(&self) -> Option<Location> {
+        match self {
+            PresetError::Read { .. } => None,
+            PresetError::Syntax { location, .. }
+            | PresetError::Invalid { location, .. }
+            | PresetError::InvalidSimVar { location, .. }
+            | PresetError::InvalidEffect { location, .. }
+            | PresetError::NewerSchema { location, .. } => *location,
+        }
+    }
+
+    pub(super) fn read(file: &Path, source: io::Error) -> Self {
+        PresetError::Read {
+            file: file.to_path_buf(),
+            source,
+        }
+    }
+
+    /// YAML that does not parse or does not match the file layout.
+    pub(super) fn syntax(file: &Path, e: serde_yaml::Error) -> Self {
+        PresetError::Syntax {
+            file: file.to_path_buf(),
+            location: e.location().map(|l| Location {
+                line: l.line(),
+                column: l.column(),
+            }),
+            message: e.to_string(),
+        }
+    }
+
+    /// A `button_bindings` entry that does not parse. Points at the entry's `button:` line, or
+    /// at `button_bindings:` when it has none.
+    pub(super) fn bad_binding(
+        file: &Path,
+        text: &str,
+        entry: &serde_yaml::Value,
+        e: serde_yaml::Error,
+    ) -> Self {
+        let button = entry.get("button").and_then(serde_yaml::Value::as_u64);
+        let location = button.and_then(|b| locate_entry(text, "button", &b.to_string()));
+        PresetError::Syntax {
+            file: file.to_path_buf(),
+            location: location.or_else(|| locate(text, "button_bindings")),
+            message: format!("button binding: {e}"),
+        }
+    }
+
+    /// Points at `schema_version:`.
+    pub(super) fn newer_schema(file: &Path, text: &str, found: u32, supported: u32) -> Self {
+        PresetError::NewerSchema {
+            file: file.to_path_buf(),
+            location: locate(text, "schema_version"),
+            found,
+            supported,
+        }
+    }
+
+    pub(super) fn invalid(file: &Path, text: &str, issue: RumbleIssue) -> Self {
+        PresetError::Invalid {
+            file: file.to_path_buf(),
+            location: locate(text, issue.key()),
+            issue,
+        }
+    }
+
+    /// Points at the entry's `key:` line, or at `extra_simvars:` when there is none.
+    pub(super) fn invalid_simvar(file: &Path, text: &str, issue: SimVarIssue) -> Self {
+        let entry = issue.key().and_then(|key| locate_entry(text, "key", key));
+        PresetError::InvalidSimVar {
+            file: file.to_path_buf(),
+            location: entry.or_else(|| locate(text, "extra_simvars")),
+            issue,
+        }
+    }
+
+    /// Points at the effect's `name:` line, or at `custom_effects:` (`textures:` for a texture,
+    /// `patterns:` for a pattern).
+    pub(super) fn invalid_effect(file: &Path, text: &str, issue: EffectIssue) -> Self {
+        let entry = issue
+            .effect()
+            .and_then(|name| locate_entry(text, "name", name));
+        let section = match issue {
+            EffectIssue::BadTexture { .. } => "textures",
+            EffectIssue::BadPattern { .. } => "patterns",
+            _ => "custom_effects",
+        };
+        PresetError::InvalidEffect {
+            file: file.to_path_buf(),
+            location: entry.or_else(|| locate(text, section)),
+            issue: Box::new(issue),
+        }
+    }
+}
+
+/// First `field: value` in `text`, block or flow style, quoted or not.
+fn locate_entry(text: &str, field: &str, value: &str) -> Option<Location> {
+    let needle = format!("{field}:");
+    text.lines().enumerate().find_map(|(i, line)| {
+        let start = line.find(&needle)?;
+        let rest = line[start + needle.len()..].trim_start();
+        let found = rest.split([',', '}']).next()?.trim();
+        (found.trim_matches(['"', '\'']) == value).then(|| Location {
+            line: i + 1,
+            column: line[..start].chars().count() + 1,
+        })
+    })
+}
+
+/// Store methods return `io::Result`; keep the message when an error passes through one.
+impl From<PresetError> for io::Error {
+    fn from(e: PresetError) -> Self {
+        match e {
+            PresetError::Read { source, .. } => source,
+            other => io::Error::new(io::ErrorKind::InvalidData, other.to_string()),
+        }
+    }
+}
+
+/// First `key:` in `text`, block or flow style.
+pub fn locate(text: &str, key: &str) -> Option<Location> {
+    let needle = format!("{key}:");
+    text.lines().enumerate().find_map(|(i, line)| {
+        let mut from = 0;
+        while let Some(pos) = line[from..].find(&needle) {
+            let start = from + pos;
+            let boundary = line[..start]
+                .chars()
+                .next_back()
+                .is_none_or(|c| !(c.is_ascii_alphanumeric() || c == '_'));
+            if boundary {
+                return Some(Location {
+                    line: i + 1,
+                    column: line[..start].chars().count() + 1,
+                });
+            }
+            from = start + needle.len();
+        }
+        None
+    })
+}
+
+/// Declared simvars that SimConnect could not register or that would shadow each other.
+pub fn check_simvars(defs: &[SimVarDef]) -> Result<(), SimVarIssue> {
+    if defs.len() > MAX_DECLARED_SIMVARS {
+        return Err(SimVarIssue::TooMany(defs.len()));
+    }
+    for (i, def) in defs.iter().enumerate() {
+        let key = || def.key.clone();
+        let key_ok = def.key.starts_with(|c: char| c.is_ascii_lowercase())
+            && def
+                .key
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+        if !key_ok {
+            return Err(SimVarIssue::BadKey(key()));
+        }
+        if defs[..i].iter().any(|d| d.key == def.key) {
+            return Err(SimVarIssue::DuplicateKey(key()));
+        }
+        // SimConnect names are at most 255 characters.
+        let name = def.name.trim();
+        if name.is_empty() || name.len() > 255 || name.chars().any(char::is_control) {
+            return Err(SimVarIssue::BadName { key: key() });
+        }
+        if def.unit.trim().is_empty() {
+            return Err(SimVarIssue::MissingUnit { key: key() });
+        }
+    }
+    Ok(())
+}
+
+/// Custom effects that do not compile against the simvars the preset subscribes to.
+pub fn check_effects(defs: &[CustomEffectDef], simvars: &SimVarProfile) -> Result<(), EffectIssue> {
+    custom::compile(defs, &simvars.layout().extra_keys()).map(|_| ())
+}
+
+pub fn check_textures(textures: &Textures) -> Result<(), EffectIssue> {
+    textures.iter().try_for_each(|(effect, texture)| {
+        texture.check().map_err(|issue| EffectIssue::BadTexture {
+            effect: *effect,
+            issue,
+        })
+    })
+}
+
+pub fn check_patterns(patterns: &Patterns) -> Result<(), EffectIssue> {
+    patterns.iter().try_for_each(|(effect, pattern)| {
+        let supported = if PATTERN_EFFECTS.contains(effect) {
+            Ok(())
+        } else {
+            Err(PatternIssue::Unsupported)
+        };
+        supported
+            .and_then(|()| pattern.check())
+            .map_err(|issue| EffectIssue::BadPattern {
+                effect: *effect,
+                issue,
+            })
+    })
+}
+
+/// Values that parse but would misbehave at runtime.
+pub fn check(cfg: &RumbleConfig) -> Result<(), RumbleIssue> {
+    if cfg.taxi_start_kn >= cfg.taxi_end_kn {
+        return Err(RumbleIssue::TaxiRange {
+            start: cfg.taxi_start_kn,
+            end: cfg.taxi_end_kn,
+        });
+    }
+    if cfg.eng_rpm_max <= cfg.eng_rpm_idle {
+        return Err(RumbleIssue::EngineRpmRange {
+            idle: cfg.eng_rpm_idle,
+            max: cfg.eng_rpm_max,
+        });
+    }
+    for (key, value) in [
+        ("thump_min_period_s", cfg.thump_min_period_s),
+        ("thump_max_period_s", cfg.thump_max_period_s),
+        ("flaps_bump_duration_s", cfg.flaps_bump_duration_s),
+        ("gear_bump_duration_s", cfg.gear_bump_duration_s),
+    ] {
+        if value < 0.0 {
+            return Err(RumbleIssue::NegativeDuration { key, value });
+        }
+    }
+    if !(0.0..=1.0).contains(&cfg.smoothing_alpha) {
+        return Err(RumbleIssue::SmoothingAlpha(cfg.smoothing_alpha));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preset::PresetKind;
+
+    #[test]
+    fn built_in_defaults_pass_checks() {
+        for kind in PresetKind::ALL {
+            assert_eq!(check(&kind.built_in_default().rumble), Ok(()), "{kind:?}");
+        }
+    }
+
+    #[test]
+    fn checks_catch_each_rule() {
+        let ok = RumbleConfig::default();
+        let mut cfg = ok.clone();
+        cfg.taxi_start_kn = cfg.taxi_end_kn;
+        assert!(matches!(check(&cfg), Err(RumbleIssue::TaxiRange { .. })));
+
+        let mut cfg = ok.clone();
+        cfg.eng_rpm_max = cfg.eng_rpm_idle - 1.0;
+        assert!(matches!(
+            check(&cfg),
+            Err(RumbleIssue::EngineRpmRange { .. })
+        ));
+
+        let mut cfg = ok.clone();
+        cfg.gear_bump_duration_s = -0.1;
+        assert_eq!(
+            check(&cfg),
+            Err(RumbleIssue::NegativeDuration {
+                key: "gear_bump_duration_s",
+                value: -0.1
+            })
+        );
+
+        let mut cfg = ok;
+        cfg.smoothing_alpha = 1.5;
+        assert_eq!(check(&cfg), Err(RumbleIssue::SmoothingAlpha(1.5)));
+    }
+
+    #[test]
+    fn simvar_checks_catch_each_rule() {
+        let def = |key: &str, name: &str, unit: &str| SimVarDef {
+            name: name.to_string(),
+            unit: unit.to_string(),
+            key: key.to_string(),
+            datum_index: crate::preset::SIMCONNECT_UNUSED_DATUM,
+        };
+        let brake = def("speedbrake_pct", "SPOILERS LEFT POSITION", "Percent");
+        assert_eq!(check_simvars(std::slice::from_ref(&brake)), Ok(()));
+        assert_eq!(
+            check_simvars(&[def("Speed Brake", "X", "Percent")]),
+            Err(SimVarIssue::BadKey("Speed Brake".to_string()))
+        );
+        assert_eq!(
+            check_simvars(&[brake.clone(), brake.clone()]),
+            Err(SimVarIssue::DuplicateKey("speedbrake_pct".to_string()))
+        );
+        assert!(matches!(
+            check_simvars(&[def("vib", " ", "Number")]),
+            Err(SimVarIssue::BadName { .. })
+        ));
+        assert!(matches!(
+            check_simvars(&[def("vib", "L:VIB", "")]),
+            Err(SimVarIssue::MissingUnit { .. })
+        ));
+        let many: Vec<SimVarDef> = (0..=MAX_DECLARED_SIMVARS)
+            .map(|i| def(&format!("v{i}"), "X", "Number"))
+            .collect();
+        assert_eq!(
+            check_simvars(&many),
+            Err(SimVarIssue::TooMany(MAX_DECLARED_SIMVARS + 1))
+        );
+    }
+
+    #[test]
+    fn locate_finds_whole_keys_in_block_and_flow_style() {
+        let text = "kind: fighter\nrumble:\n  thump_max_period_s: 1\n  thump_min_period_s: -2\n";
+        assert_eq!(
+            locate(text, "thump_min_period_s"),
+            Some(Location { line: 4, column: 3 })
+        );
+        assert_eq!(
+            locate(
+                "rumble: { taxi_end_kn: 3, taxi_start_kn: 9 }",
+                "taxi_start_kn"
+            ),
+            Some(Location {
+                line: 1,
+                column: 27
+            })
+        );
+        assert_eq!(locate(text, "period_s"), None);
+    }
+}
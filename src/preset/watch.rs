@@ -0,0 +1,127 @@
+//! Watches the presets folder so edits made in a text editor apply without a restart.
+//! Changes are debounced and forwarded as `UiCmd::FileChanged`; the UI decides what to reload.
+
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{unbounded, Sender};
+use notify::{EventKind, RecursiveMode, Watcher};
+
+use super::{PresetId, PresetKind};
+use crate::{LogBuffer, UiCmd};
+
+/// Editors often write a file in several steps; wait this long for them to settle.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// A file in the presets folder that the app loads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileTarget {
+    Settings,
+    Preset(PresetId),
+    Aircraft,
+}
+
+impl FileTarget {
+    /// Which store file `path` is, if any. Backups, `devices.yml` and other files are ignored.
+    pub fn classify(dir: &Path, path: &Path) -> Option<FileTarget> {
+        if path.extension().and_then(|e| e.to_str()) != Some("yml") {
+            return None;
+        }
+        let stem = path.file_stem()?.to_str()?;
+        let parent = path.parent()?;
+        if parent == dir.join("user") {
+            return Some(FileTarget::Preset(PresetId::User(stem.to_string())));
+        }
+        if parent != dir {
+            return None;
+        }
+        match stem {
+            "settings" => Some(FileTarget::Settings),
+            "aircraft" => Some(FileTarget::Aircraft),
+            _ => PresetKind::ALL
+                .into_iter()
+                .find(|k| k.file_stem() == stem)
+                .map(|k| FileTarget::Preset(PresetId::BuiltIn(k))),
+        }
+    }
+}
+
+/// Start watching `dir` (including `user/`). Runs for the life of the process.
+pub fn spawn_watcher(dir: PathBuf, tx_ui: Sender<UiCmd>, logs: LogBuffer) -> notify::Result<()> {
+    let (tx, rx) = unbounded::<PathBuf>();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        if let Ok(event) = res {
+            if !matches!(event.kind, EventKind::Access(_)) {
+                for path in event.paths {
+                    let _ = tx.send(path);
+                }
+            }
+        }
+    })?;
+    watcher.watch(&dir, RecursiveMode::Recursive)?;
+    logs.push(format!("Watching {} for preset edits", dir.display()));
+
+    thread::spawn(move || {
+        // Owned here so it lives as long as the loop.
+        let _watcher = watcher;
+        while let Ok(first) = rx.recv() {
+            let mut paths = vec![first];
+            while let Ok(path) = rx.recv_timeout(DEBOUNCE) {
+                paths.push(path);
+            }
+            let mut targets: Vec<FileTarget> = Vec::new();
+            for target in paths.iter().filter_map(|p| FileTarget::classify(&dir, p)) {
+                if !targets.contains(&target) {
+                    targets.push(target);
+                }
+            }
+            for target in targets {
+                if tx_ui.send(UiCmd::FileChanged(target)).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_maps_store_files_and_skips_the_rest() {
+        let dir = Path::new("/p");
+        assert_eq!(
+            FileTarget::classify(dir, Path::new("/p/settings.yml")),
+            Some(FileTarget::Settings)
+        );
+        assert_eq!(
+            FileTarget::classify(dir, Path::new("/p/fighter.yml")),
+            Some(FileTarget::Preset(PresetId::BuiltIn(PresetKind::Fighter)))
+        );
+        assert_eq!(
+            FileTarget::classify(dir, Path::new("/p/user/cub.yml")),
+            Some(FileTarget::Preset(PresetId::User("cub".to_string())))
+        );
+        assert_eq!(
+            FileTarget::classify(dir, Path::new("/p/aircraft.yml")),
+            Some(FileTarget::Aircraft)
+        );
+        for ignored in [
+            "/p/settings.v0.bak",
+            "/p/devices.yml",
+            "/p/backups/fighter/20261018-120000.000.yml",
+            "/p/fighter.yml.tmp",
+            "/p/fighter.yml~",
+            "/elsewhere/fighter.yml",
+        ] {
+            assert_eq!(
+                FileTarget::classify(dir, Path::new(ignored)),
+                None,
+                "{ignored}"
+            );
+        }
+    }
+}
@@ -1,7 +1,20 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+pub mod custom;
+pub mod expr;
+pub mod plugin;
+pub mod wave;
+
+use crate::bindings::{Effect, OutputControls};
+use crate::hid::input::StickInput;
+use crate::hid::pattern::{Pattern, PatternPlay, Patterns};
 use crate::sim::parse::{engine_power_norm, jet_vibe_drive};
 use crate::{EffectsSnapshot, FlightVars, RumbleConfig};
+use custom::{CustomEffect, CustomEffectDef, EffectIssue};
+use plugin::PluginHost;
+use wave::{textured, Shape, Textures, Waveform};
 
 #[derive(Debug, Clone)]
 pub struct RumbleState {
@@ -63,6 +76,13 @@ pub struct RumbleOutput {
 
 pub struct RumbleEngine {
     state: RumbleState,
+    stick: Option<StickInput>,
+    controls: OutputControls,
+    custom: Vec<CustomEffect>,
+    plugins: PluginHost,
+    textures: Textures,
+    patterns: BTreeMap<Effect, Arc<Pattern>>,
+    pattern_plays: Vec<PatternPlay>,
 }
 
 impl Default for RumbleEngine {
@@ -82,11 +102,78 @@ impl RumbleEngine {
                 ground_slow_since: -1.0,
                 ..Default::default()
             },
+            stick: None,
+            controls: OutputControls::default(),
+            custom: Vec::new(),
+            plugins: PluginHost::default(),
+            textures: Textures::new(),
+            patterns: BTreeMap::new(),
+            pattern_plays: Vec::new(),
+        }
+    }
+
+    /// Latest stick input for hand-on gating and deflection scaling; `None` if unavailable.
+    pub fn set_stick_input(&mut self, input: Option<StickInput>) {
+        self.stick = input;
+    }
+
+    /// Master gain and per-effect mutes from stick-button actions.
+    pub fn set_output_controls(&mut self, controls: OutputControls) {
+        self.controls = controls;
+    }
+
+    /// Use the preset's custom effects; `extras` are the simvar keys it subscribes to.
+    /// Unchanged definitions keep their envelope state.
+    pub fn set_custom_effects(
+        &mut self,
+        defs: &[CustomEffectDef],
+        extras: &[String],
+    ) -> Result<(), EffectIssue> {
+        if self.custom.len() == defs.len()
+            && self.custom.iter().zip(defs).all(|(e, d)| e.def() == d)
+        {
+            return Ok(());
         }
+        self.custom = custom::compile(defs, extras).inspect_err(|_| self.custom.clear())?;
+        Ok(())
+    }
+
+    /// The preset's waveform overrides for built-in effects.
+    pub fn set_textures(&mut self, textures: Textures) {
+        self.textures = textures;
+    }
+
+    /// The preset's haptic patterns; a bump with a pattern is played by the HID worker
+    /// (see `take_pattern_plays`) instead of being computed here.
+    pub fn set_patterns(&mut self, patterns: &Patterns) {
+        self.patterns = patterns
+            .iter()
+            .map(|(e, p)| (*e, Arc::new(p.clone())))
+            .collect();
+    }
+
+    /// Patterns triggered since the last call, for `HidCmd::PlayPattern`.
+    pub fn take_pattern_plays(&mut self) -> Vec<PatternPlay> {
+        std::mem::take(&mut self.pattern_plays)
     }
 
+    /// Native plugins to tick alongside the built-in effects.
+    pub fn set_plugins(&mut self, plugins: PluginHost) {
+        self.plugins = plugins;
+    }
+
+    /// Clear effect state; custom effect definitions, textures, patterns and plugins stay.
     pub fn reset(&mut self) {
+        let mut custom = std::mem::take(&mut self.custom);
+        custom.iter_mut().for_each(CustomEffect::reset);
+        let plugins = std::mem::take(&mut self.plugins);
+        let textures = std::mem::take(&mut self.textures);
+        let patterns = std::mem::take(&mut self.patterns);
         *self = Self::new();
+        self.custom = custom;
+        self.plugins = plugins;
+        self.textures = textures;
+        self.patterns = patterns;
     }
 
     pub fn step(
@@ -106,7 +193,8 @@ impl RumbleEngine {
 
         let parked_engine = fv.on_ground && fv.eng_rpm >= ENGINE_OFF_RPM && cfg.engine_vibe > 0.0;
 
-        if hold {
+        let hands_off = cfg.hands_off_cut && self.stick.is_some_and(|s| !s.hand_on);
+        if hold || hands_off {
             return RumbleOutput {
                 intensity: 0,
                 effects: EffectsSnapshot::default(),
@@ -134,13 +222,19 @@ impl RumbleEngine {
             ..Default::default()
         };
 
+        let muted = |effect| self.controls.is_muted(effect);
+        let textures = &self.textures;
+        let stall_warning = stall_warning_active(fv) && !muted(Effect::Stall);
         let s = &mut self.state;
 
+        let mut flap_triggered = false;
+        let mut gear_triggered = false;
         if motion_effects_enabled {
             if fv.flaps_index != s.prev_flaps_idx {
                 let steps = (fv.flaps_index - s.prev_flaps_idx).abs().max(1) as usize;
                 let duration = cfg.flaps_bump_duration_s * steps as f64;
                 trigger_flap_bump(s, duration, cfg.flaps_peak as f64);
+                flap_triggered = true;
                 s.prev_flaps_idx = fv.flaps_index;
                 s.prev_flaps_pct = fv.flaps_pct;
             } else {
@@ -152,6 +246,7 @@ impl RumbleEngine {
                         cfg.flaps_bump_duration_s,
                         (cfg.flaps_peak as f64) * scale,
                     );
+                    flap_triggered = true;
                 }
                 s.prev_flaps_pct = fv.flaps_pct;
             }
@@ -160,6 +255,7 @@ impl RumbleEngine {
             let handle_norm = normalize_gear_handle(fv.gear_handle);
             if motion_effects_enabled && gear_bump_should_trigger(handle_norm, gear_norm, s) {
                 trigger_gear_bump(s, cfg.flaps_bump_duration_s, cfg.flaps_peak as f64);
+                gear_triggered = true;
             }
             s.prev_gear_handle_norm = handle_norm;
             s.prev_gear_extended = gear_norm;
@@ -169,48 +265,52 @@ impl RumbleEngine {
 
         let mut ground_term = 0.0;
 
-        if motion_effects_enabled && fv.on_ground && gs >= start {
+        if motion_effects_enabled && fv.on_ground && gs >= start && !muted(Effect::GroundRoll) {
             let t_norm = ((gs - start) / (end - start)).clamp(0.0, 1.0);
 
             let period =
                 cfg.thump_max_period_s - t_norm * (cfg.thump_max_period_s - cfg.thump_min_period_s);
 
-            let cycle = (fv.sim_time_s / period).fract();
-
-            let duty = cfg.thump_duty.clamp(0.05, 0.4);
-            let in_pulse = cycle < duty;
-            let thump_env = if in_pulse {
-                let p = (cycle / duty).clamp(0.0, 1.0);
-                (std::f64::consts::PI * p).sin()
+            let wave = if gs >= end {
+                Waveform::new(Shape::Sine, 8.0)
             } else {
-                0.0
+                Waveform::with_period(Shape::HalfSine, period)
+                    .with_duty(cfg.thump_duty.clamp(0.05, 0.4))
             };
+            let level = textured(textures, Effect::GroundRoll, wave).sample(fv.sim_time_s);
 
-            let amp = (cfg.ground_roll as f64) * (0.35 + 0.65 * t_norm);
-            ground_term = thump_env * amp;
-
-            if gs >= end {
-                let f_hz = 8.0;
-                let phase = (2.0 * std::f64::consts::PI * f_hz * fv.sim_time_s).sin() * 0.5 + 0.5;
-                ground_term = (cfg.ground_roll as f64) * phase;
-            }
+            let amp = if gs >= end {
+                cfg.ground_roll as f64
+            } else {
+                (cfg.ground_roll as f64) * (0.35 + 0.65 * t_norm)
+            };
+            ground_term = level * amp;
         }
 
         let mut air_term = 0.0;
-        if motion_effects_enabled && !fv.on_ground && fv.airspeed_indicated > 30.0 {
-            air_term +=
-                (fv.airspeed_indicated / 250.0).clamp(0.0, 1.0) * (cfg.base_airspeed as f64);
+        if motion_effects_enabled
+            && !fv.on_ground
+            && fv.airspeed_indicated > 30.0
+            && !muted(Effect::Base)
+        {
+            let level = textured(textures, Effect::Base, Waveform::default()).sample(fv.sim_time_s);
+            air_term += (fv.airspeed_indicated / 250.0).clamp(0.0, 1.0)
+                * (cfg.base_airspeed as f64)
+                * level;
         }
 
-        let (turb_term, turb_in_pulse) = if motion_effects_enabled {
-            bank_turb_thump(fv, cfg)
+        let (turb_term, turb_in_pulse) = if motion_effects_enabled && !muted(Effect::Bank) {
+            bank_turb_thump(fv, cfg, textures)
         } else {
             (0.0, false)
         };
         air_term += turb_term;
         effects.turb_thump_active = motion_effects_enabled && !fv.on_ground && turb_in_pulse;
 
-        let (engine_term, engine_active) = engine_vibe_term(fv, cfg, s);
+        let (mut engine_term, engine_active) = engine_vibe_term(fv, cfg, s, textures);
+        if muted(Effect::Engine) {
+            engine_term = 0.0;
+        }
         effects.engine_vibe_active = engine_active;
         s.prev_eng_rpm = fv.eng_rpm;
         s.prev_eng_throttle = throttle_norm(fv);
@@ -224,47 +324,86 @@ impl RumbleEngine {
             s.bg_smoothed = s.bg_smoothed + alpha * (bg - s.bg_smoothed);
         }
 
+        let stall_level = (cfg.stall_ceiling as f64)
+            * textured(textures, Effect::Stall, Waveform::default()).sample(fv.sim_time_s);
         let mut transients: f64 = 0.0;
         if motion_effects_enabled {
-            if stall_warning_active(fv) {
-                transients = transients.max(cfg.stall_ceiling as f64);
+            if stall_warning {
+                transients = transients.max(stall_level);
             }
 
             let flap_active = flap_bump_active(s);
             let gear_active = gear_bump_active(s);
+            let deflection = self.stick.map_or(1.0, |stick| {
+                1.0 + (cfg.deflection_gain.max(0.0) / 100.0) as f64 * stick.deflection() as f64
+            });
 
-            if flap_active {
+            // A bump with a pattern is sequenced by the HID worker at its own rate.
+            let mut play = |effect, peak: f64| {
+                if let Some(pattern) = self.patterns.get(&effect) {
+                    let peak = self.controls.scale(peak * deflection);
+                    self.pattern_plays.push(PatternPlay {
+                        pattern: Arc::clone(pattern),
+                        peak: peak.clamp(0.0, cfg.max_output as f64).round() as u8,
+                        max_output: cfg.max_output,
+                    });
+                }
+            };
+            let flap_pattern = self.patterns.contains_key(&Effect::Flaps);
+            let gear_pattern = self.patterns.contains_key(&Effect::Gear);
+            if flap_triggered && !muted(Effect::Flaps) {
+                play(Effect::Flaps, s.flap_peak);
+            }
+            if gear_triggered && !muted(Effect::Gear) {
+                play(Effect::Gear, s.gear_peak);
+            }
+
+            if flap_active && !flap_pattern && !muted(Effect::Flaps) {
                 let elapsed = s.flap_bump_start.elapsed().as_secs_f64();
                 let period = 0.35_f64.max(cfg.flaps_bump_duration_s * 0.5);
-                let phase = (elapsed % period) / period;
-                transients += s.flap_peak * (std::f64::consts::PI * phase).sin().abs();
+                let wave = Waveform::with_period(Shape::HalfSine, period);
+                transients += s.flap_peak * textured(textures, Effect::Flaps, wave).sample(elapsed);
             }
-            if gear_active {
+            if gear_active && !gear_pattern && !muted(Effect::Gear) {
                 let elapsed = s.gear_bump_start.elapsed().as_secs_f64();
                 let period = 0.35_f64.max(cfg.gear_bump_duration_s * 0.5);
-                let phase = (elapsed % period) / period;
-                transients += s.gear_peak * (std::f64::consts::PI * phase).sin().abs();
+                let wave = Waveform::with_period(Shape::HalfSine, period);
+                transients += s.gear_peak * textured(textures, Effect::Gear, wave).sample(elapsed);
             }
 
             effects.flaps_bump_active = flap_active;
             effects.gear_bump_active = gear_active;
+
+            transients *= deflection;
         }
 
         let mut total = if motion_effects_enabled {
             let spoilers_pct = fv.extras.get("spoilers_pct").copied().unwrap_or(0.0) / 100.0;
-            let spoiler_term = spoiler_rumble_term(fv, cfg, s, spoilers_pct);
+            let mut spoiler_term = spoiler_rumble_term(fv, cfg, s, spoilers_pct, textures);
+            if muted(Effect::Spoilers) {
+                spoiler_term = 0.0;
+            }
             if spoiler_boost_allowed(s, fv, spoilers_pct) {
                 effects.spoilers_boost_active = true;
             }
-            s.bg_smoothed + transients + engine_term + spoiler_term
+            let mut custom_term = 0.0;
+            for (i, effect) in self.custom.iter_mut().enumerate() {
+                let term = effect.step(fv);
+                if term > 0.0 && !muted(Effect::Custom) {
+                    custom_term += term;
+                    effects.custom_active |= 1 << i;
+                }
+            }
+            let plugin_term = self.plugins.step(fv, muted(Effect::Plugins));
+            s.bg_smoothed + transients + engine_term + spoiler_term + custom_term + plugin_term
         } else {
             engine_term
         };
-        if motion_effects_enabled && stall_warning_active(fv) {
-            total = total.max(cfg.stall_ceiling as f64);
+        if motion_effects_enabled && stall_warning {
+            total = total.max(stall_level);
         }
 
-        total = total.clamp(0.0, cfg.max_output as f64);
+        total = self.controls.scale(total).clamp(0.0, cfg.max_output as f64);
 
         RumbleOutput {
             intensity: total.round() as u8,
@@ -366,6 +505,7 @@ fn spoiler_rumble_term(
     cfg: &RumbleConfig,
     s: &RumbleState,
     spoilers_pct: f64,
+    textures: &Textures,
 ) -> f64 {
     if spoilers_pct <= 0.01 || !spoiler_boost_allowed(s, fv, spoilers_pct) {
         return 0.0;
@@ -375,8 +515,8 @@ fn spoiler_rumble_term(
         let descent = (-fv.vertical_speed_fpm / 3000.0).clamp(0.0, 1.0);
         amp *= 1.0 + descent * spoilers_pct * 0.35;
     }
-    let phase = (fv.sim_time_s * 10.0).fract();
-    let buzz = (std::f64::consts::TAU * phase).sin().abs();
+    let wave = Waveform::new(Shape::HalfSine, 20.0);
+    let buzz = textured(textures, Effect::Spoilers, wave).sample(fv.sim_time_s);
     amp * (0.35 + 0.65 * buzz)
 }
 
@@ -414,20 +554,7 @@ fn rejected_takeoff_active(s: &RumbleState, fv: &FlightVars, spoilers_pct: f64)
     extra_f64(fv, "eng_throttle_1").unwrap_or(0.0) > 25.0
 }
 
-fn thump_envelope(sim_time_s: f64, period: f64, duty: f64) -> (f64, bool) {
-    let duty = duty.clamp(0.05, 0.4);
-    let cycle = (sim_time_s / period).fract();
-    let in_pulse = cycle < duty;
-    let env = if in_pulse {
-        let p = (cycle / duty).clamp(0.0, 1.0);
-        (std::f64::consts::PI * p).sin()
-    } else {
-        0.0
-    };
-    (env, in_pulse)
-}
-
-fn bank_turb_thump(fv: &FlightVars, cfg: &RumbleConfig) -> (f64, bool) {
+fn bank_turb_thump(fv: &FlightVars, cfg: &RumbleConfig, textures: &Textures) -> (f64, bool) {
     if fv.on_ground {
         return (0.0, false);
     }
@@ -441,9 +568,14 @@ fn bank_turb_thump(fv: &FlightVars, cfg: &RumbleConfig) -> (f64, bool) {
     }
 
     let period = 0.9 - severity * 0.65;
-    let (env, in_pulse) = thump_envelope(fv.sim_time_s, period, cfg.thump_duty);
+    let wave =
+        Waveform::with_period(Shape::HalfSine, period).with_duty(cfg.thump_duty.clamp(0.05, 0.4));
+    let wave = textured(textures, Effect::Bank, wave);
     let amp = (cfg.bank as f64) * severity;
-    (env * amp, in_pulse)
+    (
+        wave.sample(fv.sim_time_s) * amp,
+        wave.in_pulse(fv.sim_time_s),
+    )
 }
 
 fn extra_f64(fv: &FlightVars, key: &str) -> Option<f64> {
@@ -516,6 +648,7 @@ fn engine_thump_envelope(
     period: f64,
     drive: f64,
     profile: EngineVibeProfile,
+    textures: &Textures,
 ) -> (f64, bool) {
     let elapsed = s.engine_vibe_start.elapsed().as_secs_f64();
     let duty = match profile {
@@ -523,27 +656,15 @@ fn engine_thump_envelope(
         EngineVibeProfile::Jet => (0.30 + 0.52 * drive.powf(0.85)).clamp(0.28, 0.88),
         EngineVibeProfile::Fighter => (0.34 + 0.58 * drive.powf(0.8)).clamp(0.32, 0.92),
     };
-    let cycle = (elapsed / period.max(0.04)).fract();
-    let in_pulse = cycle < duty;
-    let env = if in_pulse {
-        let p = (cycle / duty).clamp(0.0, 1.0);
-        if profile == EngineVibeProfile::Ga {
-            (std::f64::consts::PI * p).sin().abs()
-        } else {
-            // Sharper edges for turbine buzz.
-            let edge = if p < 0.12 {
-                p / 0.12
-            } else if p > 0.88 {
-                (1.0 - p) / 0.12
-            } else {
-                1.0
-            };
-            edge.clamp(0.0, 1.0)
-        }
+    // Sharper edges for turbine buzz.
+    let shape = if profile == EngineVibeProfile::Ga {
+        Shape::HalfSine
     } else {
-        0.0
+        Shape::Trapezoid
     };
-    (env, in_pulse)
+    let wave = Waveform::with_period(shape, period.max(0.04)).with_duty(duty);
+    let wave = textured(textures, Effect::Engine, wave);
+    (wave.sample(elapsed), wave.in_pulse(elapsed))
 }
 
 fn engine_vibe_amp(
@@ -702,7 +823,12 @@ fn engine_pulse_period(
     base.max(0.04)
 }
 
-fn engine_vibe_term(fv: &FlightVars, cfg: &RumbleConfig, s: &mut RumbleState) -> (f64, bool) {
+fn engine_vibe_term(
+    fv: &FlightVars,
+    cfg: &RumbleConfig,
+    s: &mut RumbleState,
+    textures: &Textures,
+) -> (f64, bool) {
     let profile = engine_vibe_profile(cfg, fv);
     let (amp, mode, drive) = engine_vibe_amp(fv, cfg, s);
     if mode == EngineVibeMode::Off || amp < 0.5 {
@@ -712,7 +838,7 @@ fn engine_vibe_term(fv: &FlightVars, cfg: &RumbleConfig, s: &mut RumbleState) ->
     let on_ground = fv.on_ground;
     let period = engine_pulse_period(on_ground, mode, drive, profile);
 
-    let (env, in_pulse) = engine_thump_envelope(s, period, drive, profile);
+    let (env, in_pulse) = engine_thump_envelope(s, period, drive, profile, textures);
     let mut term = env * amp;
 
     // Ground pulses must exceed HID rounding dead-zone (intensity is u8).
@@ -896,6 +1022,187 @@ mod tests {
         assert_eq!(out.intensity, 0);
     }
 
+    #[test]
+    fn hands_off_cut_silences_only_when_enabled() {
+        let fv = airborne(150.0, 1.0);
+        let hands_off = StickInput {
+            hand_on: false,
+            ..Default::default()
+        };
+
+        let mut engine = RumbleEngine::new();
+        engine.set_stick_input(Some(hands_off));
+        assert!(engine.step(&fv, &cfg(), 1, false).intensity > 0);
+
+        let mut c = cfg();
+        c.hands_off_cut = true;
+        let mut engine = RumbleEngine::new();
+        engine.set_stick_input(Some(hands_off));
+        assert_eq!(engine.step(&fv, &c, 1, false).intensity, 0);
+
+        engine.set_stick_input(None);
+        assert!(
+            engine.step(&fv, &c, 1, false).intensity > 0,
+            "no input → no cut"
+        );
+    }
+
+    #[test]
+    fn stall_transient_grows_with_deflection() {
+        let mut fv = airborne(150.0, 1.0);
+        fv.stalled = true;
+        let mut c = cfg();
+        c.stall_ceiling = 100.0;
+        c.deflection_gain = 50.0;
+
+        let mut centered = RumbleEngine::new();
+        centered.set_stick_input(Some(StickInput {
+            hand_on: true,
+            ..Default::default()
+        }));
+        let mut pulled = RumbleEngine::new();
+        pulled.set_stick_input(Some(StickInput {
+            y: -1.0,
+            hand_on: true,
+            ..Default::default()
+        }));
+
+        let a = centered.step(&fv, &c, 1, false).intensity;
+        let b = pulled.step(&fv, &c, 1, false).intensity;
+        assert!(b > a, "pulled {b} should exceed centered {a}");
+    }
+
+    #[test]
+    fn output_controls_mute_effects_and_scale_total() {
+        let mut fv = airborne(150.0, 1.0);
+        fv.stalled = true;
+        let mut c = cfg();
+        c.stall_ceiling = 200.0;
+
+        let mut engine = RumbleEngine::new();
+        let full = engine.step(&fv, &c, 1, false).intensity;
+
+        engine.set_output_controls(OutputControls::with_gain(50));
+        let half = engine.step(&fv, &c, 1, false).intensity;
+        assert!(
+            (i16::from(half) - i16::from(full) / 2).abs() <= 1,
+            "{half} vs {full}"
+        );
+
+        let mut muted = OutputControls::default();
+        muted.toggle_mute(Effect::Stall);
+        engine.set_output_controls(muted);
+        let without_stall = engine.step(&fv, &c, 1, false).intensity;
+        assert!(without_stall < full / 2, "stall muted → {without_stall}");
+    }
+
+    #[test]
+    fn custom_effects_add_to_output_and_survive_reset() {
+        let mut c = cfg();
+        c.base_airspeed = 0.0;
+        c.bank = 0.0;
+        c.engine_vibe = 0.0;
+        let mut fv = airborne(150.0, 1.0);
+        fv.extras.insert("g_force".to_string(), 3.0);
+        let buffet = CustomEffectDef {
+            name: "G buffet".to_string(),
+            intensity: "clamp((g_force - 1.5) * 40, 0, 120)".to_string(),
+            trigger: Some("!on_ground".to_string()),
+            waveform: Default::default(),
+            envelope: Default::default(),
+        };
+
+        let mut engine = RumbleEngine::new();
+        assert!(engine
+            .set_custom_effects(std::slice::from_ref(&buffet), &[])
+            .is_err());
+        let keys = vec!["g_force".to_string()];
+        engine.set_custom_effects(&[buffet], &keys).unwrap();
+        let out = engine.step(&fv, &c, 1, false);
+        assert_eq!(out.intensity, 60);
+        assert_eq!(out.effects.custom_active, 1);
+
+        engine.reset();
+        assert_eq!(engine.step(&fv, &c, 1, false).intensity, 60);
+
+        let mut muted = OutputControls::default();
+        muted.toggle_mute(Effect::Custom);
+        engine.set_output_controls(muted);
+        assert_eq!(engine.step(&fv, &c, 1, false).intensity, 0);
+    }
+
+    #[test]
+    fn textures_reshape_built_in_effects_and_survive_reset() {
+        let mut c = cfg();
+        c.ground_roll = 60.0;
+        let rolling = ground_taxi(0.0, 40.0);
+        let mut engine = RumbleEngine::new();
+        assert_eq!(
+            engine.step(&rolling, &c, 1, false).intensity,
+            30,
+            "8 Hz sine at mid level"
+        );
+
+        let steady = wave::Texture {
+            shape: Some(Shape::Constant),
+            ..Default::default()
+        };
+        engine.set_textures(Textures::from([(Effect::GroundRoll, steady)]));
+        engine.reset();
+        assert_eq!(engine.step(&rolling, &c, 1, false).intensity, 60);
+
+        let noisy = wave::Texture {
+            shape: Some(Shape::Noise),
+            seed: Some(3),
+            ..Default::default()
+        };
+        let noise_at = |t: f64| {
+            let mut engine = RumbleEngine::new();
+            engine.set_textures(Textures::from([(Effect::GroundRoll, noisy)]));
+            engine.step(&ground_taxi(t, 40.0), &c, 1, false).intensity
+        };
+        assert_eq!(noise_at(1.23), noise_at(1.23), "seeded");
+    }
+
+    #[test]
+    fn patterns_replace_bumps_with_hid_plays() {
+        let mut c = cfg();
+        c.engine_vibe = 0.0;
+        let pattern =
+            Pattern::parse("keyframes: [{at_ms: 0, level: 1}, {at_ms: 60, level: 0}]").unwrap();
+        let mut engine = RumbleEngine::new();
+        engine.set_patterns(&Patterns::from([(Effect::Flaps, pattern)]));
+        let mut fv = ground_taxi(10.0, 0.0);
+        engine.step(&fv, &c, 1, false);
+        assert!(engine.take_pattern_plays().is_empty());
+
+        fv.flaps_index = 2;
+        let out = engine.step(&fv, &c, 1, false);
+        assert!(
+            out.effects.flaps_bump_active,
+            "indicator still follows the bump"
+        );
+        assert_eq!(out.intensity, 0, "no frame-computed bump");
+        let plays = engine.take_pattern_plays();
+        assert_eq!(plays.len(), 1);
+        assert_eq!((plays[0].peak, plays[0].max_output), (65, 255));
+        engine.step(&fv, &c, 1, false);
+        assert!(engine.take_pattern_plays().is_empty(), "one play per bump");
+
+        engine.reset();
+        engine.set_output_controls(OutputControls::with_gain(50));
+        fv.flaps_index = 1;
+        engine.step(&fv, &c, 1, false);
+        assert_eq!(engine.take_pattern_plays()[0].peak, 33);
+
+        let mut muted = OutputControls::default();
+        muted.toggle_mute(Effect::Flaps);
+        engine.set_output_controls(muted);
+        fv.flaps_index = 2;
+        engine.step(&fv, &c, 1, false);
+        assert!(engine.take_pattern_plays().is_empty());
+    }
+
     #[test]
     fn ground_taxi_thump_band_produces_nonzero_output() {
         let mut engine = RumbleEngine::new();
@@ -0,0 +1,298 @@
+//! Effects defined in preset YAML (`custom_effects:`): an intensity expression over flight
+//! variables, an optional trigger, a waveform and an ADSR envelope.
+
+use serde::{Deserialize, Serialize};
+
+use super::expr::{Expr, ExprError};
+use super::wave::{Adsr, EnvelopeState, WaveIssue, Waveform};
+use crate::bindings::Effect;
+use crate::hid::pattern::PatternIssue;
+use crate::FlightVars;
+
+/// Most custom effects one preset chain may define.
+pub const MAX_CUSTOM_EFFECTS: usize = 16;
+
+/// `FlightVars` fields usable in expressions; booleans read as `1` and `0`.
+pub const FLIGHT_VARS: &[&str] = &[
+    "sim_time_s",
+    "airspeed_indicated",
+    "on_ground",
+    "bank_deg",
+    "flaps_pct",
+    "flaps_index",
+    "gear_handle",
+    "stalled",
+    "ground_speed_kt",
+    "wind_kt",
+    "wind_dir_deg",
+    "vertical_speed_fpm",
+    "paused",
+    "eng_rpm",
+    "num_engines",
+];
+
+fn flight_var(fv: &FlightVars, name: &str) -> Option<f64> {
+    let flag = |b: bool| if b { 1.0 } else { 0.0 };
+    Some(match name {
+        "sim_time_s" => fv.sim_time_s,
+        "airspeed_indicated" => fv.airspeed_indicated,
+        "on_ground" => flag(fv.on_ground),
+        "bank_deg" => fv.bank_deg,
+        "flaps_pct" => fv.flaps_pct,
+        "flaps_index" => fv.flaps_index as f64,
+        "gear_handle" => fv.gear_handle,
+        "stalled" => flag(fv.stalled),
+        "ground_speed_kt" => fv.ground_speed_kt,
+        "wind_kt" => fv.wind_kt,
+        "wind_dir_deg" => fv.wind_dir_deg,
+        "vertical_speed_fpm" => fv.vertical_speed_fpm,
+        "paused" => flag(fv.paused),
+        "eng_rpm" => fv.eng_rpm,
+        "num_engines" => fv.num_engines as f64,
+        _ => return None,
+    })
+}
+
+/// One effect as written in a preset file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomEffectDef {
+    pub name: String,
+    /// Output contribution on the slider scale (0–255), e.g. `clamp((g_force - 1.5) * 40, 0, 120)`.
+    pub intensity: String,
+    /// Plays while this is non-zero; always when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trigger: Option<String>,
+    #[serde(default, skip_serializing_if = "Waveform::is_constant")]
+    pub waveform: Waveform,
+    #[serde(default, skip_serializing_if = "Adsr::is_instant")]
+    pub envelope: Adsr,
+}
+
+/// Why a `CustomEffectDef` cannot run.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum EffectIssue {
+    #[error("custom effect needs a name")]
+    MissingName,
+    #[error("custom effect `{0}` is defined twice")]
+    DuplicateName(String),
+    #[error("{0} custom effects defined; at most {MAX_CUSTOM_EFFECTS} are allowed")]
+    TooMany(usize),
+    #[error("custom effect `{effect}` {field}: {error}")]
+    Expr {
+        effect: String,
+        field: &'static str,
+        error: ExprError,
+    },
+    #[error("custom effect `{effect}`: unknown variable `{var}` (declare it under extra_simvars)")]
+    UnknownVariable { effect: String, var: String },
+    #[error("custom effect `{effect}`: {issue}")]
+    BadWaveform { effect: String, issue: WaveIssue },
+    /// A `textures:` entry for a built-in effect.
+    #[error("texture for {}: {issue}", effect.label())]
+    BadTexture { effect: Effect, issue: WaveIssue },
+    /// A `patterns:` entry for a built-in effect.
+    #[error("pattern for {}: {issue}", effect.label())]
+    BadPattern { effect: Effect, issue: PatternIssue },
+}
+
+impl EffectIssue {
+    pub fn effect(&self) -> Option<&str> {
+        match self {
+            EffectIssue::DuplicateName(effect)
+            | EffectIssue::Expr { effect, .. }
+            | EffectIssue::UnknownVariable { effect, .. }
+            | EffectIssue::BadWaveform { effect, .. } => Some(effect),
+            EffectIssue::MissingName
+            | EffectIssue::TooMany(_)
+            | EffectIssue::BadTexture { .. }
+            | EffectIssue::BadPattern { .. } => None,
+        }
+    }
+}
+
+/// Check `defs` and compile them. `extras` are the simvar keys the preset subscribes to.
+pub fn compile(
+    defs: &[CustomEffectDef],
+    extras: &[String],
+) -> Result<Vec<CustomEffect>, EffectIssue> {
+    if defs.len() > MAX_CUSTOM_EFFECTS {
+        return Err(EffectIssue::TooMany(defs.len()));
+    }
+    let mut out: Vec<CustomEffect> = Vec::new();
+    for def in defs {
+        let effect = || def.name.clone();
+        if def.name.trim().is_empty() {
+            return Err(EffectIssue::MissingName);
+        }
+        if out.iter().any(|e| e.def.name == def.name) {
+            return Err(EffectIssue::DuplicateName(effect()));
+        }
+        let parse = |field: &'static str, text: &str| {
+            let expr = Expr::parse(text).map_err(|error| EffectIssue::Expr {
+                effect: effect(),
+                field,
+                error,
+            })?;
+            if let Some(var) = expr
+                .vars()
+                .iter()
+                .find(|v| !FLIGHT_VARS.contains(&v.as_str()) && !extras.contains(v))
+            {
+                return Err(EffectIssue::UnknownVariable {
+                    effect: effect(),
+                    var: var.clone(),
+                });
+            }
+            Ok(expr)
+        };
+        let intensity = parse("intensity", &def.intensity)?;
+        let trigger = def
+            .trigger
+            .as_deref()
+            .map(|t| parse("trigger", t))
+            .transpose()?;
+        def.waveform
+            .check()
+            .and_then(|()| def.envelope.check())
+            .map_err(|issue| EffectIssue::BadWaveform {
+                effect: effect(),
+                issue,
+            })?;
+        out.push(CustomEffect {
+            def: def.clone(),
+            intensity,
+            trigger,
+            envelope: EnvelopeState::default(),
+            last_t: None,
+        });
+    }
+    Ok(out)
+}
+
+/// A compiled effect with its envelope state.
+#[derive(Debug, Clone)]
+pub struct CustomEffect {
+    def: CustomEffectDef,
+    intensity: Expr,
+    trigger: Option<Expr>,
+    envelope: EnvelopeState,
+    last_t: Option<f64>,
+}
+
+fn eval(expr: &Expr, fv: &FlightVars) -> f64 {
+    let values: Vec<f64> = expr
+        .vars()
+        .iter()
+        .map(|name| {
+            flight_var(fv, name)
+                .or_else(|| fv.extras.get(name).copied())
+                .unwrap_or(0.0)
+        })
+        .collect();
+    expr.eval(&values)
+}
+
+impl CustomEffect {
+    pub fn def(&self) -> &CustomEffectDef {
+        &self.def
+    }
+
+    pub fn reset(&mut self) {
+        self.envelope = EnvelopeState::default();
+        self.last_t = None;
+    }
+
+    /// Contribution for this tick (never negative). Time is sim time, so pauses hold the phase.
+    pub fn step(&mut self, fv: &FlightVars) -> f64 {
+        let t = fv.sim_time_s;
+        let dt = self.last_t.map_or(0.0, |last| (t - last).clamp(0.0, 0.5));
+        self.last_t = Some(t);
+
+        let on = self.trigger.as_ref().is_none_or(|e| eval(e, fv) != 0.0);
+        let level = self.def.envelope.step(&mut self.envelope, on, dt);
+        if level <= 0.0 {
+            return 0.0;
+        }
+        eval(&self.intensity, fv).max(0.0) * level * self.def.waveform.sample(t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rumble::wave::Shape;
+
+    fn def(intensity: &str) -> CustomEffectDef {
+        CustomEffectDef {
+            name: "Buffet".to_string(),
+            intensity: intensity.to_string(),
+            trigger: None,
+            waveform: Waveform::default(),
+            envelope: Adsr::default(),
+        }
+    }
+
+    fn at(t: f64, g: f64) -> FlightVars {
+        let mut fv = FlightVars {
+            sim_time_s: t,
+            ..Default::default()
+        };
+        fv.extras.insert("g_force".to_string(), g);
+        fv
+    }
+
+    #[test]
+    fn compile_checks_names_variables_and_shapes() {
+        let extras = vec!["g_force".to_string()];
+        assert!(compile(&[def("clamp((g_force - 1.5) * 40, 0, 120)")], &extras).is_ok());
+        assert_eq!(
+            compile(&[def("g_forse * 2")], &extras).unwrap_err(),
+            EffectIssue::UnknownVariable {
+                effect: "Buffet".to_string(),
+                var: "g_forse".to_string()
+            }
+        );
+        assert!(matches!(
+            compile(&[def("1 +")], &extras),
+            Err(EffectIssue::Expr {
+                field: "intensity",
+                ..
+            })
+        ));
+        assert_eq!(
+            compile(&[def("1"), def("2")], &extras).unwrap_err(),
+            EffectIssue::DuplicateName("Buffet".to_string())
+        );
+        let mut wobble = def("10");
+        wobble.waveform.shape = Shape::Sine;
+        assert!(matches!(
+            compile(&[wobble], &extras),
+            Err(EffectIssue::BadWaveform { .. })
+        ));
+    }
+
+    #[test]
+    fn trigger_envelope_and_waveform_shape_the_output() {
+        let mut d = def("g_force * 10");
+        d.trigger = Some("g_force > 2".to_string());
+        d.envelope = Adsr {
+            attack_s: 0.2,
+            ..Default::default()
+        };
+        let mut effect = compile(&[d], &["g_force".to_string()]).unwrap().remove(0);
+        assert_eq!(effect.step(&at(0.0, 1.0)), 0.0, "trigger off");
+        assert_eq!(
+            effect.step(&at(0.1, 3.0)),
+            15.0,
+            "half way through the attack"
+        );
+        assert_eq!(effect.step(&at(0.3, 3.0)), 30.0);
+        assert_eq!(effect.step(&at(0.4, 1.0)), 0.0, "instant release");
+
+        let mut d = def("100");
+        d.waveform = Waveform::new(Shape::Square, 2.0);
+        let mut effect = compile(&[d], &[]).unwrap().remove(0);
+        assert_eq!(effect.step(&at(0.1, 0.0)), 100.0);
+        assert_eq!(effect.step(&at(0.3, 0.0)), 0.0);
+    }
+}
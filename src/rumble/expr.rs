@@ -0,0 +1,498 @@
+//! Small arithmetic language for custom effects, e.g. `clamp((g_force - 1.5) * 40, 0, 120)`.
+//!
+//! Numbers, variables, `+ - * / % ^`, comparisons, `&& || !`, `c ? a : b` and a fixed set of
+//! functions. No loops, assignments or calls out: an expression is a finite tree evaluated once
+//! per tick. Booleans are `1` and `0`; anything that ends up NaN or infinite evaluates to `0`.
+
+use std::fmt;
+
+/// Longest expression accepted, in bytes.
+pub const MAX_EXPR_LEN: usize = 512;
+/// Deepest nesting of parentheses, calls and operators.
+const MAX_DEPTH: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("column {column}: {message}")]
+pub struct ExprError {
+    /// 1-based position in the expression.
+    pub column: usize,
+    pub message: String,
+}
+
+/// Built-in functions with their argument counts (`None`: two or more).
+const FUNCTIONS: &[(&str, Option<usize>)] = &[
+    ("abs", Some(1)),
+    ("sqrt", Some(1)),
+    ("sin", Some(1)),
+    ("cos", Some(1)),
+    ("floor", Some(1)),
+    ("round", Some(1)),
+    ("min", None),
+    ("max", None),
+    ("clamp", Some(3)),
+    ("lerp", Some(3)),
+    ("step", Some(2)),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Pow,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Num(f64),
+    Var(usize),
+    Neg(Box<Node>),
+    Not(Box<Node>),
+    Bin(BinOp, Box<Node>, Box<Node>),
+    Cond(Box<Node>, Box<Node>, Box<Node>),
+    Call(&'static str, Vec<Node>),
+}
+
+/// A parsed expression. Variables are numbered in order of first use; `vars()` names them so
+/// the caller can look each one up once per tick.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr {
+    root: Node,
+    vars: Vec<String>,
+}
+
+impl Expr {
+    pub fn parse(text: &str) -> Result<Self, ExprError> {
+        if text.len() > MAX_EXPR_LEN {
+            return Err(ExprError {
+                column: MAX_EXPR_LEN + 1,
+                message: format!("longer than {MAX_EXPR_LEN} characters"),
+            });
+        }
+        let mut parser = Parser {
+            tokens: tokenize(text)?,
+            pos: 0,
+            depth: 0,
+            vars: Vec::new(),
+            end: text.chars().count() + 1,
+        };
+        let root = parser.expr()?;
+        if let Some((tok, column)) = parser.tokens.get(parser.pos) {
+            return Err(ExprError {
+                column: *column,
+                message: format!("unexpected {tok}"),
+            });
+        }
+        Ok(Self {
+            root,
+            vars: parser.vars,
+        })
+    }
+
+    /// Variable names in the order `eval` expects their values.
+    pub fn vars(&self) -> &[String] {
+        &self.vars
+    }
+
+    /// Value for `values` (one per `vars()` entry); non-finite results are `0`.
+    pub fn eval(&self, values: &[f64]) -> f64 {
+        let v = eval(&self.root, values);
+        if v.is_finite() {
+            v
+        } else {
+            0.0
+        }
+    }
+}
+
+fn truth(v: f64) -> bool {
+    v != 0.0 && !v.is_nan()
+}
+
+fn flag(b: bool) -> f64 {
+    if b {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+fn eval(node: &Node, values: &[f64]) -> f64 {
+    match node {
+        Node::Num(n) => *n,
+        Node::Var(i) => values.get(*i).copied().unwrap_or(0.0),
+        Node::Neg(a) => -eval(a, values),
+        Node::Not(a) => flag(!truth(eval(a, values))),
+        Node::Bin(BinOp::And, a, b) => flag(truth(eval(a, values)) && truth(eval(b, values))),
+        Node::Bin(BinOp::Or, a, b) => flag(truth(eval(a, values)) || truth(eval(b, values))),
+        Node::Bin(op, a, b) => {
+            let (a, b) = (eval(a, values), eval(b, values));
+            match op {
+                BinOp::Add => a + b,
+                BinOp::Sub => a - b,
+                BinOp::Mul => a * b,
+                BinOp::Div => a / b,
+                BinOp::Rem => a % b,
+                BinOp::Pow => a.powf(b),
+                BinOp::Lt => flag(a < b),
+                BinOp::Le => flag(a <= b),
+                BinOp::Gt => flag(a > b),
+                BinOp::Ge => flag(a >= b),
+                BinOp::Eq => flag(a == b),
+                BinOp::Ne => flag(a != b),
+                BinOp::And | BinOp::Or => unreachable!("short-circuit ops handled above"),
+            }
+        }
+        Node::Cond(c, a, b) => {
+            if truth(eval(c, values)) {
+                eval(a, values)
+            } else {
+                eval(b, values)
+            }
+        }
+        Node::Call(name, args) => {
+            let arg = |i: usize| eval(&args[i], values);
+            match *name {
+                "abs" => arg(0).abs(),
+                "sqrt" => arg(0).sqrt(),
+                "sin" => arg(0).sin(),
+                "cos" => arg(0).cos(),
+                "floor" => arg(0).floor(),
+                "round" => arg(0).round(),
+                "min" => args
+                    .iter()
+                    .map(|a| eval(a, values))
+                    .fold(f64::INFINITY, f64::min),
+                "max" => args
+                    .iter()
+                    .map(|a| eval(a, values))
+                    .fold(f64::NEG_INFINITY, f64::max),
+                "clamp" => {
+                    let (lo, hi) = (arg(1), arg(2));
+                    arg(0).max(lo).min(hi)
+                }
+                "lerp" => {
+                    let (a, b) = (arg(0), arg(1));
+                    a + (b - a) * arg(2)
+                }
+                "step" => flag(arg(1) >= arg(0)),
+                _ => unreachable!("function names are checked when parsing"),
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Num(f64),
+    Ident(String),
+    Op(&'static str),
+}
+
+impl fmt::Display for Tok {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Tok::Num(n) => write!(f, "number {n}"),
+            Tok::Ident(s) => write!(f, "`{s}`"),
+            Tok::Op(op) => write!(f, "`{op}`"),
+        }
+    }
+}
+
+/// Longest first, so `<=` is not read as `<` `=`.
+const OPERATORS: &[&str] = &[
+    "&&", "||", "<=", ">=", "==", "!=", "+", "-", "*", "/", "%", "^", "<", ">", "!", "?", ":", "(",
+    ")", ",",
+];
+
+fn tokenize(text: &str) -> Result<Vec<(Tok, usize)>, ExprError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit()
+            || (c == '.' && chars.get(i + 1).is_some_and(char::is_ascii_digit))
+        {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let s: String = chars[start..i].iter().collect();
+            let n = s.parse().map_err(|_| ExprError {
+                column,
+                message: format!("bad number `{s}`"),
+            })?;
+            out.push((Tok::Num(n), column));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            out.push((Tok::Ident(chars[start..i].iter().collect()), column));
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            let op = OPERATORS
+                .iter()
+                .find(|op| rest.starts_with(**op))
+                .ok_or_else(|| ExprError {
+                    column,
+                    message: format!("unexpected `{c}`"),
+                })?;
+            i += op.chars().count();
+            out.push((Tok::Op(op), column));
+        }
+    }
+    Ok(out)
+}
+
+struct Parser {
+    tokens: Vec<(Tok, usize)>,
+    pos: usize,
+    depth: usize,
+    vars: Vec<String>,
+    /// Column just past the text, for "expected ..." at the end.
+    end: usize,
+}
+
+impl Parser {
+    fn column(&self) -> usize {
+        self.tokens.get(self.pos).map_or(self.end, |(_, c)| *c)
+    }
+
+    fn error(&self, message: impl Into<String>) -> ExprError {
+        ExprError {
+            column: self.column(),
+            message: message.into(),
+        }
+    }
+
+    fn eat(&mut self, op: &str) -> bool {
+        if matches!(self.tokens.get(self.pos), Some((Tok::Op(o), _)) if *o == op) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, op: &str) -> Result<(), ExprError> {
+        if self.eat(op) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{op}`")))
+        }
+    }
+
+    fn expr(&mut self) -> Result<Node, ExprError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(self.error("nested too deeply"));
+        }
+        let cond = self.binary(0)?;
+        let node = if self.eat("?") {
+            let a = self.expr()?;
+            self.expect(":")?;
+            let b = self.expr()?;
+            Node::Cond(Box::new(cond), Box::new(a), Box::new(b))
+        } else {
+            cond
+        };
+        self.depth -= 1;
+        Ok(node)
+    }
+
+    /// Binary operators by precedence level, loosest first.
+    fn binary(&mut self, level: usize) -> Result<Node, ExprError> {
+        const LEVELS: &[&[(&str, BinOp)]] = &[
+            &[("||", BinOp::Or)],
+            &[("&&", BinOp::And)],
+            &[
+                ("<=", BinOp::Le),
+                (">=", BinOp::Ge),
+                ("==", BinOp::Eq),
+                ("!=", BinOp::Ne),
+                ("<", BinOp::Lt),
+                (">", BinOp::Gt),
+            ],
+            &[("+", BinOp::Add), ("-", BinOp::Sub)],
+            &[("*", BinOp::Mul), ("/", BinOp::Div), ("%", BinOp::Rem)],
+        ];
+        let Some(ops) = LEVELS.get(level) else {
+            return self.unary();
+        };
+        let mut left = self.binary(level + 1)?;
+        'outer: loop {
+            for (text, op) in *ops {
+                if self.eat(text) {
+                    let right = self.binary(level + 1)?;
+                    left = Node::Bin(*op, Box::new(left), Box::new(right));
+                    continue 'outer;
+                }
+            }
+            return Ok(left);
+        }
+    }
+
+    fn unary(&mut self) -> Result<Node, ExprError> {
+        if self.eat("-") {
+            return Ok(Node::Neg(Box::new(self.nested(Self::unary)?)));
+        }
+        if self.eat("!") {
+            return Ok(Node::Not(Box::new(self.nested(Self::unary)?)));
+        }
+        let base = self.atom()?;
+        if self.eat("^") {
+            let exp = self.nested(Self::unary)?;
+            return Ok(Node::Bin(BinOp::Pow, Box::new(base), Box::new(exp)));
+        }
+        Ok(base)
+    }
+
+    fn nested(&mut self, f: fn(&mut Self) -> Result<Node, ExprError>) -> Result<Node, ExprError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(self.error("nested too deeply"));
+        }
+        let node = f(self)?;
+        self.depth -= 1;
+        Ok(node)
+    }
+
+    fn atom(&mut self) -> Result<Node, ExprError> {
+        let column = self.column();
+        let Some((tok, _)) = self.tokens.get(self.pos).cloned() else {
+            return Err(self.error("expression ends early"));
+        };
+        self.pos += 1;
+        match tok {
+            Tok::Num(n) => Ok(Node::Num(n)),
+            Tok::Op("(") => {
+                let node = self.expr()?;
+                self.expect(")")?;
+                Ok(node)
+            }
+            Tok::Ident(name) if self.eat("(") => self.call(&name, column),
+            Tok::Ident(name) => {
+                let i = match self.vars.iter().position(|v| *v == name) {
+                    Some(i) => i,
+                    None => {
+                        self.vars.push(name);
+                        self.vars.len() - 1
+                    }
+                };
+                Ok(Node::Var(i))
+            }
+            Tok::Op(_) => Err(ExprError {
+                column,
+                message: format!("unexpected {tok}"),
+            }),
+        }
+    }
+
+    fn call(&mut self, name: &str, column: usize) -> Result<Node, ExprError> {
+        let Some(&(name, arity)) = FUNCTIONS.iter().find(|(f, _)| *f == name) else {
+            return Err(ExprError {
+                column,
+                message: format!("unknown function `{name}`"),
+            });
+        };
+        let mut args = Vec::new();
+        if !self.eat(")") {
+            loop {
+                args.push(self.expr()?);
+                if self.eat(")") {
+                    break;
+                }
+                self.expect(",")?;
+            }
+        }
+        let ok = match arity {
+            Some(n) => args.len() == n,
+            None => args.len() >= 2,
+        };
+        if !ok {
+            let wanted = arity.map_or_else(|| "2 or more".to_string(), |n| n.to_string());
+            return Err(ExprError {
+                column,
+                message: format!("`{name}` takes {wanted} arguments, got {}", args.len()),
+            });
+        }
+        Ok(Node::Call(name, args))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(text: &str, vars: &[(&str, f64)]) -> f64 {
+        let expr = Expr::parse(text).unwrap();
+        let values: Vec<f64> = expr
+            .vars()
+            .iter()
+            .map(|name| {
+                vars.iter()
+                    .find(|(n, _)| n == name)
+                    .map_or(0.0, |(_, v)| *v)
+            })
+            .collect();
+        expr.eval(&values)
+    }
+
+    #[test]
+    fn evaluates_with_usual_precedence() {
+        assert_eq!(value("1 + 2 * 3", &[]), 7.0);
+        assert_eq!(value("(1 + 2) * 3", &[]), 9.0);
+        assert_eq!(value("-2 ^ 2", &[]), -4.0);
+        assert_eq!(value("2 ^ 3 ^ 2", &[]), 512.0);
+        assert_eq!(value("10 - 4 - 3", &[]), 3.0);
+        assert_eq!(value("7 % 4", &[]), 3.0);
+        assert_eq!(value("1 < 2 && !(3 <= 2)", &[]), 1.0);
+        assert_eq!(value("on_ground ? 5 : 9", &[("on_ground", 0.0)]), 9.0);
+        assert_eq!(value("1 / 0", &[]), 0.0, "non-finite results are zero");
+    }
+
+    #[test]
+    fn functions_and_variables() {
+        let g = |g: f64| value("clamp((g_force - 1.5) * 40, 0, 120)", &[("g_force", g)]);
+        assert_eq!(g(1.0), 0.0);
+        assert_eq!(g(2.0), 20.0);
+        assert_eq!(g(9.0), 120.0);
+        assert_eq!(value("max(1, a, 3)", &[("a", 5.0)]), 5.0);
+        assert_eq!(value("min(a, 2)", &[("a", 5.0)]), 2.0);
+        assert_eq!(
+            value("step(10, x) * lerp(0, 100, .25)", &[("x", 12.0)]),
+            25.0
+        );
+
+        let expr = Expr::parse("a + b * a").unwrap();
+        assert_eq!(expr.vars(), ["a", "b"]);
+    }
+
+    #[test]
+    fn errors_point_at_the_problem() {
+        let err = |text: &str| Expr::parse(text).unwrap_err();
+        assert_eq!(err("1 +").column, 4);
+        assert_eq!(err("2 $ 3").column, 3);
+        assert!(err("clamp(1, 2)").message.contains("takes 3"));
+        assert!(err("exec(1)").message.contains("unknown function"));
+        assert!(err("(1").message.contains("expected `)`"));
+        assert!(err("1 2").message.contains("unexpected"));
+        assert!(err(&"(".repeat(100)).message.contains("nested"));
+        assert!(err(&"1+".repeat(300)).message.contains("longer"));
+    }
+}
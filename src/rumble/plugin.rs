@@ -0,0 +1,510 @@
+//! Native effect plugins: dynamic libraries in the `plugins/` folder that add to the rumble
+//! output through a small, versioned C ABI.
+//!
+//! A plugin exports `ursa_plugin_descriptor`, returning a [`PluginDescriptor`] that lives as long
+//! as the library. Each tick the host calls `tick` with a read-only [`PluginInput`] (flight
+//! variables, the subscribed extras and the plugin's section of the preset's `plugins:` map as
+//! YAML) and reads back a [`PluginOutput`]. A tick that returns non-zero, panics, or reports a
+//! non-finite intensity disables that plugin for the rest of the run; the others keep going.
+
+use std::collections::BTreeMap;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use serde_yaml::Value;
+
+use crate::shutdown::contain_panics;
+use crate::FlightVars;
+
+/// Bumped whenever a `#[repr(C)]` type below changes; plugins built for another version are
+/// not loaded.
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+/// Exported symbol of type [`EntryFn`].
+pub const PLUGIN_ENTRY_SYMBOL: &str = "ursa_plugin_descriptor";
+/// Most plugins loaded at once.
+pub const MAX_PLUGINS: usize = 16;
+
+/// A preset's `plugins:` map: settings for each plugin, by plugin name.
+pub type PluginSections = BTreeMap<String, Value>;
+
+pub type EntryFn = unsafe extern "C-unwind" fn() -> *const PluginDescriptor;
+/// Fills `output`; returns 0 on success. Anything else disables the plugin.
+pub type TickFn = unsafe extern "C-unwind" fn(*const PluginInput, *mut PluginOutput) -> i32;
+
+#[repr(C)]
+pub struct PluginDescriptor {
+    /// Must equal [`PLUGIN_ABI_VERSION`].
+    pub abi_version: u32,
+    /// NUL-terminated UTF-8; also the key of the plugin's section under `plugins:`.
+    pub name: *const c_char,
+    /// NUL-terminated UTF-8, shown in the UI; may be null.
+    pub version: *const c_char,
+    pub tick: TickFn,
+}
+
+/// `FlightVars` without the extras map; flags are `0` or `1`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PluginFlightVars {
+    pub sim_time_s: f64,
+    pub airspeed_indicated: f64,
+    pub bank_deg: f64,
+    pub flaps_pct: f64,
+    pub gear_handle: f64,
+    pub ground_speed_kt: f64,
+    pub wind_kt: f64,
+    pub wind_dir_deg: f64,
+    pub vertical_speed_fpm: f64,
+    pub eng_rpm: f64,
+    pub flaps_index: i32,
+    pub num_engines: u32,
+    pub on_ground: u8,
+    pub stalled: u8,
+    pub paused: u8,
+}
+
+impl From<&FlightVars> for PluginFlightVars {
+    fn from(fv: &FlightVars) -> Self {
+        Self {
+            sim_time_s: fv.sim_time_s,
+            airspeed_indicated: fv.airspeed_indicated,
+            bank_deg: fv.bank_deg,
+            flaps_pct: fv.flaps_pct,
+            gear_handle: fv.gear_handle,
+            ground_speed_kt: fv.ground_speed_kt,
+            wind_kt: fv.wind_kt,
+            wind_dir_deg: fv.wind_dir_deg,
+            vertical_speed_fpm: fv.vertical_speed_fpm,
+            eng_rpm: fv.eng_rpm,
+            flaps_index: fv.flaps_index,
+            num_engines: fv.num_engines,
+            on_ground: fv.on_ground.into(),
+            stalled: fv.stalled.into(),
+            paused: fv.paused.into(),
+        }
+    }
+}
+
+/// One subscribed simvar, by its preset `key`.
+#[repr(C)]
+pub struct PluginExtra {
+    pub key: *const c_char,
+    pub value: f64,
+}
+
+/// Valid for the duration of one `tick` call only.
+#[repr(C)]
+pub struct PluginInput {
+    pub abi_version: u32,
+    pub vars: PluginFlightVars,
+    pub extras: *const PluginExtra,
+    pub extras_len: usize,
+    /// The plugin's section of the preset's `plugins:` map as YAML; empty when there is none.
+    pub config: *const c_char,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PluginOutput {
+    /// Contribution on the slider scale (0–255); negative values count as 0.
+    pub intensity: f64,
+    /// Plugin-defined bits, shown in the UI while non-zero.
+    pub active: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum PluginError {
+    #[error("{0}")]
+    Load(String),
+    #[error("does not export `{PLUGIN_ENTRY_SYMBOL}`")]
+    MissingEntry,
+    #[error("returned no descriptor")]
+    NoDescriptor,
+    #[error("built for plugin ABI {found}; this version supports {PLUGIN_ABI_VERSION}")]
+    AbiMismatch { found: u32 },
+    #[error("has no valid name")]
+    BadName,
+    #[error("another plugin is already named `{0}`")]
+    DuplicateName(String),
+    #[error("more than {MAX_PLUGINS} plugins; skipped")]
+    TooMany,
+    #[error("returned error code {0}")]
+    Failed(i32),
+    #[error("panicked: {0}")]
+    Panicked(String),
+    #[error("returned an invalid intensity ({0})")]
+    BadOutput(f64),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PluginState {
+    Running,
+    /// Could not be loaded.
+    Rejected(PluginError),
+    /// Failed at runtime and no longer runs.
+    Disabled(PluginError),
+}
+
+/// What the UI shows for one file in `plugins/`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PluginStatus {
+    pub name: String,
+    pub version: String,
+    pub path: PathBuf,
+    pub state: PluginState,
+    /// `active` from the last tick.
+    pub active: u32,
+}
+
+/// A loaded plugin.
+pub struct Plugin {
+    status: PluginStatus,
+    tick: TickFn,
+    config: CString,
+    /// Keeps the library mapped while `tick` may be called.
+    _library: Option<Box<dyn std::any::Any + Send>>,
+}
+
+fn c_str(ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+    // SAFETY: non-null and NUL-terminated per the ABI contract.
+    unsafe { CStr::from_ptr(ptr) }
+        .to_str()
+        .ok()
+        .map(str::to_string)
+}
+
+impl Plugin {
+    /// Check `descriptor` and wrap it.
+    ///
+    /// # Safety
+    /// `descriptor` must be null or point to a `PluginDescriptor` that, with its strings and
+    /// `tick`, stays valid while `library` is alive.
+    pub unsafe fn from_descriptor(
+        descriptor: *const PluginDescriptor,
+        path: &Path,
+        library: Option<Box<dyn std::any::Any + Send>>,
+    ) -> Result<Self, PluginError> {
+        // SAFETY: guaranteed by the caller.
+        let desc = unsafe { descriptor.as_ref() }.ok_or(PluginError::NoDescriptor)?;
+        if desc.abi_version != PLUGIN_ABI_VERSION {
+            return Err(PluginError::AbiMismatch {
+                found: desc.abi_version,
+            });
+        }
+        let name = c_str(desc.name)
+            .map(|n| n.trim().to_string())
+            .filter(|n| !n.is_empty())
+            .ok_or(PluginError::BadName)?;
+        Ok(Self {
+            status: PluginStatus {
+                name,
+                version: c_str(desc.version).unwrap_or_default(),
+                path: path.to_path_buf(),
+                state: PluginState::Running,
+                active: 0,
+            },
+            tick: desc.tick,
+            config: CString::default(),
+            _library: library,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.status.name
+    }
+
+    fn run(&mut self, vars: PluginFlightVars, extras: &[PluginExtra]) -> Result<f64, PluginError> {
+        let input = PluginInput {
+            abi_version: PLUGIN_ABI_VERSION,
+            vars,
+            extras: extras.as_ptr(),
+            extras_len: extras.len(),
+            config: self.config.as_ptr(),
+        };
+        let mut output = PluginOutput::default();
+        let tick = self.tick;
+        // SAFETY: `input` and `output` outlive the call and `tick` came from a checked descriptor.
+        let code = contain_panics(|| {
+            catch_unwind(AssertUnwindSafe(|| unsafe { tick(&input, &mut output) }))
+        })
+        .map_err(|payload| {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown payload".to_string());
+            PluginError::Panicked(message)
+        })?;
+        if code != 0 {
+            return Err(PluginError::Failed(code));
+        }
+        if !output.intensity.is_finite() {
+            return Err(PluginError::BadOutput(output.intensity));
+        }
+        self.status.active = output.active;
+        Ok(output.intensity.clamp(0.0, 255.0))
+    }
+}
+
+#[derive(Default)]
+struct HostInner {
+    plugins: Vec<Plugin>,
+    rejected: Vec<PluginStatus>,
+    /// Extras keys as C strings, rebuilt when the subscribed set changes.
+    keys: Vec<(String, CString)>,
+    log: Vec<String>,
+}
+
+/// The loaded plugins, shared by the sim worker (which ticks them) and the UI (which lists them).
+#[derive(Clone, Default)]
+pub struct PluginHost {
+    inner: Arc<Mutex<HostInner>>,
+}
+
+impl PluginHost {
+    pub fn new(plugins: Vec<Plugin>, rejected: Vec<PluginStatus>) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(HostInner {
+                plugins,
+                rejected,
+                ..Default::default()
+            })),
+        }
+    }
+
+    /// Load every library in `dir`; a missing folder means no plugins.
+    #[cfg(feature = "app")]
+    pub fn load_dir(dir: &Path) -> Self {
+        let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+            .map(|entries| entries.flatten().map(|e| e.path()).collect())
+            .unwrap_or_default();
+        files.retain(|p| {
+            p.extension()
+                .is_some_and(|ext| ext == std::env::consts::DLL_EXTENSION)
+        });
+        files.sort();
+
+        let mut plugins: Vec<Plugin> = Vec::new();
+        let mut rejected = Vec::new();
+        for path in files {
+            let loaded = if plugins.len() >= MAX_PLUGINS {
+                Err(PluginError::TooMany)
+            } else {
+                load_library(&path).and_then(|p| {
+                    if plugins.iter().any(|q| q.name() == p.name()) {
+                        Err(PluginError::DuplicateName(p.name().to_string()))
+                    } else {
+                        Ok(p)
+                    }
+                })
+            };
+            match loaded {
+                Ok(plugin) => plugins.push(plugin),
+                Err(e) => rejected.push(PluginStatus {
+                    name: path
+                        .file_stem()
+                        .map(|s| s.to_string_lossy().into_owned())
+                        .unwrap_or_default(),
+                    version: String::new(),
+                    path,
+                    state: PluginState::Rejected(e),
+                    active: 0,
+                }),
+            }
+        }
+        Self::new(plugins, rejected)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.lock().plugins.is_empty()
+    }
+
+    /// Loaded plugins first, then the files that could not be loaded.
+    pub fn statuses(&self) -> Vec<PluginStatus> {
+        let inner = self.inner.lock();
+        inner
+            .plugins
+            .iter()
+            .map(|p| p.status.clone())
+            .chain(inner.rejected.iter().cloned())
+            .collect()
+    }
+
+    /// Hand each plugin its section of the preset's `plugins:` map.
+    pub fn configure(&self, sections: &PluginSections) {
+        for plugin in &mut self.inner.lock().plugins {
+            let yaml = sections
+                .get(plugin.name())
+                .and_then(|v| serde_yaml::to_string(v).ok())
+                .unwrap_or_default();
+            plugin.config = CString::new(yaml).unwrap_or_default();
+        }
+    }
+
+    /// Messages about plugins disabled since the last call.
+    pub fn take_log(&self) -> Vec<String> {
+        std::mem::take(&mut self.inner.lock().log)
+    }
+
+    /// Tick every running plugin; returns the summed contribution. `muted` plugins still run
+    /// (so their state keeps up) but add nothing.
+    pub fn step(&self, fv: &FlightVars, muted: bool) -> f64 {
+        let mut inner = self.inner.lock();
+        let HostInner {
+            plugins, keys, log, ..
+        } = &mut *inner;
+        if plugins.is_empty() {
+            return 0.0;
+        }
+        if keys.len() != fv.extras.len() || keys.iter().any(|(k, _)| !fv.extras.contains_key(k)) {
+            *keys = fv
+                .extras
+                .keys()
+                .filter_map(|k| Some((k.clone(), CString::new(k.as_str()).ok()?)))
+                .collect();
+        }
+        let extras: Vec<PluginExtra> = keys
+            .iter()
+            .map(|(k, c)| PluginExtra {
+                key: c.as_ptr(),
+                value: fv.extras[k],
+            })
+            .collect();
+        let vars = PluginFlightVars::from(fv);
+
+        let mut total = 0.0;
+        for plugin in plugins.iter_mut() {
+            if plugin.status.state != PluginState::Running {
+                continue;
+            }
+            match plugin.run(vars, &extras) {
+                Ok(term) if !muted => total += term,
+                Ok(_) => {}
+                Err(e) => {
+                    log.push(format!("Plugin `{}` disabled: {e}", plugin.name()));
+                    plugin.status.active = 0;
+                    plugin.status.state = PluginState::Disabled(e);
+                }
+            }
+        }
+        total
+    }
+}
+
+#[cfg(feature = "app")]
+fn load_library(path: &Path) -> Result<Plugin, PluginError> {
+    // SAFETY: loading runs the library's initialisers; plugins are code the user installed.
+    let library =
+        unsafe { libloading::Library::new(path) }.map_err(|e| PluginError::Load(e.to_string()))?;
+    // SAFETY: the symbol's type is fixed by the plugin ABI.
+    let entry = unsafe { library.get::<EntryFn>(PLUGIN_ENTRY_SYMBOL.as_bytes()) }
+        .map(|sym| *sym)
+        .map_err(|_| PluginError::MissingEntry)?;
+    let descriptor = contain_panics(|| catch_unwind(|| unsafe { entry() }))
+        .map_err(|_| PluginError::Panicked("in ursa_plugin_descriptor".to_string()))?;
+    // SAFETY: the descriptor lives as long as the library, which the plugin keeps.
+    unsafe { Plugin::from_descriptor(descriptor, path, Some(Box::new(library))) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe extern "C-unwind" fn echo_airspeed(
+        input: *const PluginInput,
+        output: *mut PluginOutput,
+    ) -> i32 {
+        let input = unsafe { &*input };
+        let config = unsafe { CStr::from_ptr(input.config) }.to_str().unwrap();
+        let scale: f64 = config
+            .trim()
+            .strip_prefix("scale: ")
+            .map_or(1.0, |s| s.parse().unwrap());
+        let extras = unsafe { std::slice::from_raw_parts(input.extras, input.extras_len) };
+        let g = extras
+            .iter()
+            .find(|e| unsafe { CStr::from_ptr(e.key) }.to_bytes() == b"g_force")
+            .map_or(0.0, |e| e.value);
+        unsafe {
+            *output = PluginOutput {
+                intensity: input.vars.airspeed_indicated * scale + g,
+                active: u32::from(input.vars.on_ground == 0),
+            };
+        }
+        0
+    }
+
+    unsafe extern "C-unwind" fn panics(_: *const PluginInput, _: *mut PluginOutput) -> i32 {
+        panic!("bad plugin");
+    }
+
+    fn plugin(name: &'static CStr, abi_version: u32, tick: TickFn) -> Result<Plugin, PluginError> {
+        let desc = PluginDescriptor {
+            abi_version,
+            name: name.as_ptr(),
+            version: c"1.0".as_ptr(),
+            tick,
+        };
+        unsafe { Plugin::from_descriptor(&desc, Path::new("test.dll"), None) }
+    }
+
+    fn flying(ias: f64) -> FlightVars {
+        let mut fv = FlightVars {
+            airspeed_indicated: ias,
+            ..Default::default()
+        };
+        fv.extras.insert("g_force".to_string(), 2.0);
+        fv
+    }
+
+    #[test]
+    fn descriptors_are_checked() {
+        assert_eq!(
+            plugin(c"echo", PLUGIN_ABI_VERSION + 1, echo_airspeed).err(),
+            Some(PluginError::AbiMismatch {
+                found: PLUGIN_ABI_VERSION + 1
+            })
+        );
+        assert_eq!(
+            plugin(c"  ", PLUGIN_ABI_VERSION, echo_airspeed).err(),
+            Some(PluginError::BadName)
+        );
+        let ok = plugin(c"echo", PLUGIN_ABI_VERSION, echo_airspeed).unwrap();
+        assert_eq!((ok.name(), ok.status.version.as_str()), ("echo", "1.0"));
+    }
+
+    #[test]
+    fn plugins_see_vars_extras_and_config_and_a_panic_disables_only_that_plugin() {
+        let host = PluginHost::new(
+            vec![
+                plugin(c"echo", PLUGIN_ABI_VERSION, echo_airspeed).unwrap(),
+                plugin(c"crashy", PLUGIN_ABI_VERSION, panics).unwrap(),
+            ],
+            Vec::new(),
+        );
+        host.configure(&BTreeMap::from([(
+            "echo".to_string(),
+            serde_yaml::from_str("scale: 0.5").unwrap(),
+        )]));
+
+        assert_eq!(host.step(&flying(100.0), false), 52.0);
+        let statuses = host.statuses();
+        assert_eq!(statuses[0].state, PluginState::Running);
+        assert_eq!(statuses[0].active, 1);
+        assert_eq!(
+            statuses[1].state,
+            PluginState::Disabled(PluginError::Panicked("bad plugin".to_string()))
+        );
+        assert_eq!(host.take_log().len(), 1);
+
+        assert_eq!(host.step(&flying(40.0), false), 22.0, "echo keeps running");
+        assert_eq!(host.step(&flying(40.0), true), 0.0, "muted");
+        assert!(host.take_log().is_empty());
+    }
+}
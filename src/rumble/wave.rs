@@ -0,0 +1,437 @@
+//! Waveforms and envelopes that give effects their texture.
+//!
+//! Every built-in effect describes its oscillation as a [`Waveform`]; a preset can override any
+//! part of it per effect under `textures:`. Custom effects pick a waveform and an [`Adsr`]
+//! envelope directly. All shapes are pure functions of time, so the same seed replays the same
+//! noise.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::bindings::Effect;
+
+/// Fastest waveform, in Hz; the output path sends far slower than this anyway.
+pub const MAX_HZ: f64 = 50.0;
+/// Ramp length of a trapezoid when none is given, as a fraction of the pulse.
+pub const DEFAULT_EDGE: f64 = 0.12;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Shape {
+    /// No modulation: full level throughout the pulse.
+    #[default]
+    Constant,
+    /// `0.5 + 0.5·sin`, starting mid-way up.
+    Sine,
+    /// One `sin` hump per cycle, starting and ending at zero.
+    HalfSine,
+    Square,
+    Triangle,
+    Saw,
+    /// Ramps up, holds, ramps down; see `edge`.
+    Trapezoid,
+    /// Random levels, one per cycle, smoothly joined.
+    Noise,
+}
+
+/// A periodic level between 0 and 1.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Waveform {
+    #[serde(default)]
+    pub shape: Shape,
+    #[serde(default)]
+    pub hz: f64,
+    /// Fraction of each cycle the shape plays in; the rest is silent.
+    #[serde(default = "full_duty", skip_serializing_if = "is_full_duty")]
+    pub duty: f64,
+    /// Trapezoid ramp length as a fraction of the pulse.
+    #[serde(default = "default_edge", skip_serializing_if = "is_default_edge")]
+    pub edge: f64,
+    /// Noise sequence; the same seed gives the same texture every run.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub seed: u64,
+}
+
+fn full_duty() -> f64 {
+    1.0
+}
+
+fn is_full_duty(duty: &f64) -> bool {
+    *duty == 1.0
+}
+
+fn default_edge() -> f64 {
+    DEFAULT_EDGE
+}
+
+fn is_default_edge(edge: &f64) -> bool {
+    *edge == DEFAULT_EDGE
+}
+
+fn is_zero(seed: &u64) -> bool {
+    *seed == 0
+}
+
+impl Default for Waveform {
+    fn default() -> Self {
+        Self::new(Shape::Constant, 0.0)
+    }
+}
+
+/// Why a waveform or envelope cannot be used.
+#[derive(Debug, Clone, Copy, PartialEq, thiserror::Error)]
+pub enum WaveIssue {
+    #[error("waveform hz must be above 0 and at most {MAX_HZ}")]
+    Rate,
+    #[error("waveform duty must be above 0 and at most 1")]
+    Duty,
+    #[error("waveform edge must be between 0 and 0.5")]
+    Edge,
+    #[error("envelope times must not be negative and sustain must be between 0 and 1")]
+    Envelope,
+}
+
+impl Waveform {
+    pub fn new(shape: Shape, hz: f64) -> Self {
+        Self {
+            shape,
+            hz,
+            duty: 1.0,
+            edge: DEFAULT_EDGE,
+            seed: 0,
+        }
+    }
+
+    /// Same shape repeating every `period` seconds.
+    pub fn with_period(shape: Shape, period: f64) -> Self {
+        Self::new(shape, 1.0 / period)
+    }
+
+    pub fn with_duty(mut self, duty: f64) -> Self {
+        self.duty = duty;
+        self
+    }
+
+    pub fn with_edge(mut self, edge: f64) -> Self {
+        self.edge = edge;
+        self
+    }
+
+    pub fn is_constant(&self) -> bool {
+        self.shape == Shape::Constant && self.duty == 1.0
+    }
+
+    pub fn check(&self) -> Result<(), WaveIssue> {
+        let hz_ok = self.hz > 0.0 && self.hz <= MAX_HZ;
+        if self.shape != Shape::Constant && !hz_ok {
+            return Err(WaveIssue::Rate);
+        }
+        if !(self.duty > 0.0 && self.duty <= 1.0) {
+            return Err(WaveIssue::Duty);
+        }
+        if !(0.0..=0.5).contains(&self.edge) {
+            return Err(WaveIssue::Edge);
+        }
+        Ok(())
+    }
+
+    /// Cycle number and position within the cycle (`0..1`) at time `t`.
+    fn cycle(&self, t: f64) -> (f64, f64) {
+        let x = t * self.hz;
+        let n = x.floor();
+        (n, if x.is_finite() { x - n } else { 0.0 })
+    }
+
+    /// Whether `t` falls in the playing part of the cycle.
+    pub fn in_pulse(&self, t: f64) -> bool {
+        self.cycle(t).1 < self.duty
+    }
+
+    /// Level in `0..=1` at time `t` (seconds on whichever clock the effect runs on).
+    pub fn sample(&self, t: f64) -> f64 {
+        let (n, phase) = self.cycle(t);
+        if phase >= self.duty {
+            return 0.0;
+        }
+        let p = (phase / self.duty).clamp(0.0, 1.0);
+        match self.shape {
+            Shape::Constant => 1.0,
+            Shape::Sine => 0.5 + 0.5 * (std::f64::consts::TAU * p).sin(),
+            Shape::HalfSine => (std::f64::consts::PI * p).sin(),
+            Shape::Square => {
+                if p < 0.5 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Shape::Triangle => 1.0 - (2.0 * p - 1.0).abs(),
+            Shape::Saw => p,
+            Shape::Trapezoid => {
+                let edge = self.edge.max(f64::EPSILON);
+                (p / edge).min((1.0 - p) / edge).clamp(0.0, 1.0)
+            }
+            Shape::Noise => {
+                let a = noise(self.seed, n as i64);
+                let b = noise(self.seed, n as i64 + 1);
+                let s = p * p * (3.0 - 2.0 * p);
+                a + (b - a) * s
+            }
+        }
+    }
+}
+
+/// Deterministic value in `0..1` for cycle `n` of sequence `seed` (splitmix64).
+fn noise(seed: u64, n: i64) -> f64 {
+    let mut z = seed
+        .wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        .wrapping_add(n as u64)
+        .wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    (z >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Preset override for one built-in effect's waveform; unset fields keep the effect's own.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Texture {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shape: Option<Shape>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hz: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duty: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub edge: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+impl Texture {
+    pub fn apply(&self, base: Waveform) -> Waveform {
+        Waveform {
+            shape: self.shape.unwrap_or(base.shape),
+            hz: self.hz.unwrap_or(base.hz),
+            duty: self.duty.unwrap_or(base.duty),
+            edge: self.edge.unwrap_or(base.edge),
+            seed: self.seed.unwrap_or(base.seed),
+        }
+    }
+
+    /// Checked against a stand-in base, since the effect's own rate may vary.
+    pub fn check(&self) -> Result<(), WaveIssue> {
+        self.apply(Waveform::new(Shape::Sine, 1.0)).check()
+    }
+}
+
+/// A preset's `textures:` map.
+pub type Textures = BTreeMap<Effect, Texture>;
+
+/// `base` for `effect` with the preset's override applied. Each effect gets its own default
+/// noise seed so two noisy effects do not move in step.
+pub fn textured(textures: &Textures, effect: Effect, base: Waveform) -> Waveform {
+    let seed = Effect::ALL.iter().position(|e| *e == effect).unwrap_or(0) as u64 + 1;
+    let base = Waveform { seed, ..base };
+    textures.get(&effect).map_or(base, |t| t.apply(base))
+}
+
+/// Attack, decay, sustain and release. Attack and release times are for a full-scale change, so
+/// a release from half level takes half as long; decay falls from full level to `sustain`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Adsr {
+    #[serde(default)]
+    pub attack_s: f64,
+    #[serde(default)]
+    pub decay_s: f64,
+    /// Level held while the gate stays on, `0..=1`.
+    #[serde(default = "full_duty", skip_serializing_if = "is_full_duty")]
+    pub sustain: f64,
+    #[serde(default)]
+    pub release_s: f64,
+}
+
+impl Default for Adsr {
+    fn default() -> Self {
+        Self {
+            attack_s: 0.0,
+            decay_s: 0.0,
+            sustain: 1.0,
+            release_s: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Stage {
+    #[default]
+    Idle,
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+}
+
+/// Where an [`Adsr`] envelope is for one effect.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EnvelopeState {
+    stage: Stage,
+    level: f64,
+}
+
+impl EnvelopeState {
+    pub fn level(&self) -> f64 {
+        self.level
+    }
+}
+
+impl Adsr {
+    pub fn is_instant(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn check(&self) -> Result<(), WaveIssue> {
+        let times_ok = [self.attack_s, self.decay_s, self.release_s]
+            .iter()
+            .all(|t| *t >= 0.0);
+        if !times_ok || !(0.0..=1.0).contains(&self.sustain) {
+            return Err(WaveIssue::Envelope);
+        }
+        Ok(())
+    }
+
+    /// Advance `state` by `dt` seconds with the gate on or off; returns the new level.
+    pub fn step(&self, state: &mut EnvelopeState, gate: bool, dt: f64) -> f64 {
+        let rate = |time: f64| {
+            if time <= 0.0 {
+                f64::INFINITY
+            } else {
+                dt / time
+            }
+        };
+        if gate {
+            if matches!(state.stage, Stage::Idle | Stage::Release) {
+                state.stage = Stage::Attack;
+            }
+            if state.stage == Stage::Attack {
+                state.level = (state.level + rate(self.attack_s)).min(1.0);
+                if state.level >= 1.0 {
+                    state.stage = Stage::Decay;
+                }
+            } else if state.stage == Stage::Decay {
+                let fall = (1.0 - self.sustain) * rate(self.decay_s);
+                state.level = (state.level - fall).max(self.sustain);
+            }
+            if state.stage == Stage::Decay && state.level <= self.sustain {
+                state.level = self.sustain;
+                state.stage = Stage::Sustain;
+            }
+        } else if state.stage != Stage::Idle {
+            state.stage = Stage::Release;
+            state.level = (state.level - rate(self.release_s)).max(0.0);
+            if state.level <= 0.0 {
+                state.stage = Stage::Idle;
+            }
+        }
+        state.level
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shapes_follow_their_cycle_and_duty() {
+        let half = Waveform::with_period(Shape::HalfSine, 1.0).with_duty(0.4);
+        assert!((half.sample(0.2) - 1.0).abs() < 1e-9, "peak mid-pulse");
+        assert_eq!(half.sample(0.6), 0.0);
+        assert!(half.in_pulse(1.1) && !half.in_pulse(1.5));
+
+        let sine = Waveform::new(Shape::Sine, 8.0);
+        assert!((sine.sample(0.0) - 0.5).abs() < 1e-9);
+        assert!((sine.sample(1.0 / 32.0) - 1.0).abs() < 1e-9);
+
+        let trap = Waveform::new(Shape::Trapezoid, 1.0);
+        assert!((trap.sample(0.06) - 0.5).abs() < 1e-9);
+        assert_eq!(trap.sample(0.5), 1.0);
+        assert_eq!(Waveform::new(Shape::Square, 2.0).sample(0.3), 0.0);
+        assert_eq!(Waveform::default().sample(12.3), 1.0);
+    }
+
+    #[test]
+    fn noise_is_smooth_and_repeats_for_the_same_seed() {
+        let a = Waveform {
+            seed: 7,
+            ..Waveform::new(Shape::Noise, 10.0)
+        };
+        let b = Waveform { seed: 8, ..a };
+        let samples = |w: &Waveform| {
+            (0..200)
+                .map(|i| w.sample(i as f64 * 0.005))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(samples(&a), samples(&a.clone()));
+        assert_ne!(samples(&a), samples(&b));
+        let s = samples(&a);
+        assert!(s.iter().all(|v| (0.0..1.0).contains(v)));
+        assert!(s.windows(2).all(|w| (w[0] - w[1]).abs() < 0.1), "filtered");
+    }
+
+    #[test]
+    fn adsr_walks_through_its_stages() {
+        let env = Adsr {
+            attack_s: 0.2,
+            decay_s: 0.2,
+            sustain: 0.5,
+            release_s: 0.5,
+        };
+        let mut state = EnvelopeState::default();
+        let mut level = |gate| env.step(&mut state, gate, 0.1);
+        assert!((level(true) - 0.5).abs() < 1e-9);
+        assert!((level(true) - 1.0).abs() < 1e-9);
+        assert!((level(true) - 0.75).abs() < 1e-9);
+        assert!((level(true) - 0.5).abs() < 1e-9);
+        assert!((level(true) - 0.5).abs() < 1e-9, "sustain");
+        assert!((level(false) - 0.3).abs() < 1e-9);
+        assert!((level(false) - 0.1).abs() < 1e-9);
+        assert_eq!(level(false), 0.0);
+
+        let instant = Adsr::default();
+        let mut state = EnvelopeState::default();
+        assert_eq!(instant.step(&mut state, true, 0.0), 1.0);
+        assert_eq!(instant.step(&mut state, false, 0.0), 0.0);
+    }
+
+    #[test]
+    fn textures_override_only_what_they_set() {
+        let base = Waveform::with_period(Shape::HalfSine, 0.5).with_duty(0.3);
+        let textures = Textures::from([(
+            Effect::Spoilers,
+            Texture {
+                shape: Some(Shape::Noise),
+                ..Default::default()
+            },
+        )]);
+        let spoilers = textured(&textures, Effect::Spoilers, base);
+        assert_eq!(
+            (spoilers.shape, spoilers.hz, spoilers.duty),
+            (Shape::Noise, 2.0, 0.3)
+        );
+        assert_eq!(
+            textured(&textures, Effect::Bank, base).shape,
+            Shape::HalfSine
+        );
+        assert_ne!(spoilers.seed, textured(&textures, Effect::Bank, base).seed);
+        assert_eq!(
+            Texture {
+                duty: Some(0.0),
+                ..Default::default()
+            }
+            .check(),
+            Err(WaveIssue::Duty)
+        );
+    }
+}
@@ -0,0 +1,238 @@
+//! Exit coordination: however the process goes down (window closed, tray Quit, Ctrl+C or a
+//! closing console, a panic on any thread), the HID worker writes a final zero frame to every
+//! vibe interface and the worker threads are joined with a time limit.
+
+use std::cell::Cell;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{bounded, Sender};
+use parking_lot::Mutex;
+
+use crate::{HidCmd, LogBuffer, UiCmd};
+
+pub const HID_THREAD: &str = "hid";
+pub const SIM_THREAD: &str = "sim";
+pub const BUTTONS_THREAD: &str = "buttons";
+/// How long to wait for the HID worker to confirm the zero frame.
+pub const MOTOR_STOP_TIMEOUT: Duration = Duration::from_millis(500);
+/// How long the worker threads get, together, to finish after a stop.
+pub const JOIN_TIMEOUT: Duration = Duration::from_secs(2);
+const JOIN_POLL: Duration = Duration::from_millis(10);
+
+thread_local! {
+    static CONTAINED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Run `f` with panics treated as handled by the caller (which must `catch_unwind` them), so
+/// the panic hook does not shut the app down. For plugin calls.
+pub fn contain_panics<R>(f: impl FnOnce() -> R) -> R {
+    let outer = CONTAINED.with(|c| c.replace(true));
+    let result = f();
+    CONTAINED.with(|c| c.set(outer));
+    result
+}
+
+pub type ShutdownShared = Arc<Shutdown>;
+
+pub struct Shutdown {
+    requested: AtomicBool,
+    /// `Some` once a motor stop was attempted: whether the HID worker confirmed it.
+    motor_stop: Mutex<Option<bool>>,
+    workers: Mutex<Vec<JoinHandle<()>>>,
+    tx_hid: Sender<HidCmd>,
+    tx_ui: Sender<UiCmd>,
+    logs: LogBuffer,
+}
+
+impl Shutdown {
+    pub fn new(tx_hid: Sender<HidCmd>, tx_ui: Sender<UiCmd>, logs: LogBuffer) -> ShutdownShared {
+        Arc::new(Self {
+            requested: AtomicBool::new(false),
+            motor_stop: Mutex::new(None),
+            workers: Mutex::new(Vec::new()),
+            tx_hid,
+            tx_ui,
+            logs,
+        })
+    }
+
+    /// Workers poll this and return once it is set.
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+
+    /// Ask the workers to return and the window to close. Only the first call logs.
+    pub fn request(&self, reason: &str) {
+        if !self.requested.swap(true, Ordering::SeqCst) {
+            self.logs.push(format!("Shutdown: {reason}"));
+            let _ = self.tx_ui.send(UiCmd::Quit);
+        }
+    }
+
+    /// Have the HID worker zero every vibe interface and exit, waiting up to
+    /// `MOTOR_STOP_TIMEOUT`. Later calls return the first outcome.
+    pub fn stop_motor(&self) -> bool {
+        let Some(mut state) = self.motor_stop.try_lock_for(MOTOR_STOP_TIMEOUT) else {
+            return false;
+        };
+        if let Some(done) = *state {
+            return done;
+        }
+        let (tx, rx) = bounded(1);
+        let done = self.tx_hid.send(HidCmd::Shutdown(tx)).is_ok()
+            && rx.recv_timeout(MOTOR_STOP_TIMEOUT).is_ok();
+        if !done {
+            self.logs.push(format!(
+                "Shutdown: HID worker did not confirm the motor stop within {} ms",
+                MOTOR_STOP_TIMEOUT.as_millis()
+            ));
+        }
+        *state = Some(done);
+        done
+    }
+
+    /// Start a named worker thread that `finish` joins.
+    pub fn spawn(&self, name: &str, f: impl FnOnce() + Send + 'static) {
+        match thread::Builder::new().name(name.to_string()).spawn(f) {
+            Ok(handle) => self.workers.lock().push(handle),
+            Err(e) => self
+                .logs
+                .push(format!("Shutdown: could not start the {name} thread: {e}")),
+        }
+    }
+
+    /// Request the exit, stop the motor, then join the workers for up to `JOIN_TIMEOUT`;
+    /// any still running are left to end with the process.
+    pub fn finish(&self, reason: &str) {
+        self.request(reason);
+        self.stop_motor();
+        let deadline = Instant::now() + JOIN_TIMEOUT;
+        let workers = std::mem::take(&mut *self.workers.lock());
+        for handle in workers {
+            while !handle.is_finished() && Instant::now() < deadline {
+                thread::sleep(JOIN_POLL);
+            }
+            let name = handle.thread().name().unwrap_or("worker").to_string();
+            if !handle.is_finished() {
+                self.logs.push(format!(
+                    "Shutdown: {name} thread still running after {} s; not waiting",
+                    JOIN_TIMEOUT.as_secs()
+                ));
+            } else if handle.join().is_err() {
+                self.logs
+                    .push(format!("Shutdown: {name} thread had panicked"));
+            }
+        }
+    }
+
+    /// `finish` when the guard drops, including while unwinding out of `main`.
+    pub fn guard(self: &Arc<Self>) -> ShutdownGuard {
+        ShutdownGuard(self.clone())
+    }
+
+    /// On a panic in any thread (outside `contain_panics`): log it, stop the motor and close
+    /// the app. The previous hook still prints the report.
+    pub fn install_panic_hook(self: &Arc<Self>) {
+        let shutdown = self.clone();
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if !CONTAINED.with(Cell::get) {
+                let current = thread::current();
+                let name = current.name().unwrap_or("unnamed");
+                shutdown
+                    .logs
+                    .push(format!("Panic in thread '{name}': {info}"));
+                // The HID worker cannot answer while it panics; unwinding drops its session,
+                // which zeroes the motors itself.
+                if name != HID_THREAD {
+                    shutdown.stop_motor();
+                }
+                shutdown.request("a thread panicked");
+            }
+            previous(info);
+        }));
+    }
+}
+
+pub struct ShutdownGuard(ShutdownShared);
+
+impl Drop for ShutdownGuard {
+    fn drop(&mut self) {
+        self.0.finish("app exiting");
+    }
+}
+
+#[cfg(windows)]
+static CTRL_TARGET: once_cell::sync::OnceCell<ShutdownShared> = once_cell::sync::OnceCell::new();
+
+/// Close the app on Ctrl+C / Ctrl+Break, and stop the motor before Windows ends the process
+/// when the console closes. Only debug builds have a console; logoff and shutdown reach the
+/// tray window as `WM_ENDSESSION` instead.
+#[cfg(windows)]
+pub fn install_ctrl_handler(shutdown: &ShutdownShared) -> windows::core::Result<()> {
+    use windows::Win32::System::Console::SetConsoleCtrlHandler;
+
+    let _ = CTRL_TARGET.set(shutdown.clone());
+    // SAFETY: the handler only touches `CTRL_TARGET`, which lives for the whole process.
+    unsafe { SetConsoleCtrlHandler(Some(on_console_ctrl), true) }
+}
+
+#[cfg(windows)]
+unsafe extern "system" fn on_console_ctrl(event: u32) -> windows::Win32::Foundation::BOOL {
+    use windows::Win32::Foundation::{FALSE, TRUE};
+    use windows::Win32::System::Console::{CTRL_BREAK_EVENT, CTRL_C_EVENT};
+
+    let Some(shutdown) = CTRL_TARGET.get() else {
+        return FALSE;
+    };
+    if event == CTRL_C_EVENT || event == CTRL_BREAK_EVENT {
+        shutdown.request("Ctrl+C");
+    } else {
+        // Windows ends the process as soon as this returns.
+        shutdown.request("console closed");
+        shutdown.stop_motor();
+    }
+    TRUE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossbeam_channel::unbounded;
+
+    #[test]
+    fn finish_stops_the_motor_once_and_joins_the_workers() {
+        let (tx_hid, rx_hid) = unbounded();
+        let (tx_ui, rx_ui) = unbounded();
+        let logs = LogBuffer::default();
+        let shutdown = Shutdown::new(tx_hid, tx_ui, logs.clone());
+
+        shutdown.spawn(HID_THREAD, move || {
+            while let Ok(cmd) = rx_hid.recv() {
+                if let HidCmd::Shutdown(reply) = cmd {
+                    let _ = reply.send(2);
+                    return;
+                }
+            }
+        });
+        let worker = shutdown.clone();
+        shutdown.spawn(SIM_THREAD, move || {
+            while !worker.is_requested() {
+                thread::sleep(Duration::from_millis(1));
+            }
+        });
+
+        shutdown.finish("test");
+        assert!(shutdown.is_requested());
+        assert!(matches!(rx_ui.try_recv(), Ok(UiCmd::Quit)));
+        assert!(shutdown.workers.lock().is_empty());
+        assert!(shutdown.stop_motor(), "first outcome is kept");
+        shutdown.request("again");
+        assert_eq!(logs.snapshot().len(), 1, "{:?}", logs.snapshot());
+    }
+}
@@ -1,3 +1,5 @@
+use std::time::{Duration, Instant};
+
 pub mod parse;
 
 #[cfg(all(windows, feature = "app"))]
@@ -11,3 +13,45 @@ mod stub;
 
 #[cfg(any(not(windows), not(feature = "app")))]
 pub use stub::sim_worker;
+
+/// Flight data older than this is dropped from `last_vars` and the effect indicators cleared.
+pub const VARS_STALE_AFTER: Duration = Duration::from_millis(1500);
+
+/// When `last_vars` was last refreshed, so the sim worker can drop it once frames stop coming.
+#[derive(Debug, Default)]
+pub struct VarsWatch {
+    fresh_at: Option<Instant>,
+}
+
+impl VarsWatch {
+    pub fn refresh(&mut self, now: Instant) {
+        self.fresh_at = Some(now);
+    }
+
+    /// True once per stall: the first call at least `VARS_STALE_AFTER` after the last refresh.
+    pub fn went_stale(&mut self, now: Instant) -> bool {
+        let stale = self
+            .fresh_at
+            .is_some_and(|t| now.saturating_duration_since(t) >= VARS_STALE_AFTER);
+        if stale {
+            self.fresh_at = None;
+        }
+        stale
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vars_go_stale_once_per_stall() {
+        let t0 = Instant::now();
+        let mut watch = VarsWatch::default();
+        assert!(!watch.went_stale(t0 + VARS_STALE_AFTER), "nothing seen yet");
+        watch.refresh(t0);
+        assert!(!watch.went_stale(t0 + VARS_STALE_AFTER / 2));
+        assert!(watch.went_stale(t0 + VARS_STALE_AFTER));
+        assert!(!watch.went_stale(t0 + VARS_STALE_AFTER * 2));
+    }
+}
@@ -3,7 +3,11 @@ use std::sync::{atomic::AtomicBool, Arc};
 use crossbeam_channel::Sender;
 use parking_lot::Mutex;
 
-use crate::{preset::PresetShared, EffectsShared, FlightVars, HidCmd, LogBuffer, SimStatus};
+use crate::{
+    bindings::ControlsShared, hid::input::InputShared, preset::PresetShared,
+    rumble::plugin::PluginHost, shutdown::ShutdownShared, EffectsShared, FlightVars, HidCmd,
+    LogBuffer, SimStatus,
+};
 
 #[allow(clippy::too_many_arguments)]
 pub fn sim_worker(
@@ -15,6 +19,10 @@ pub fn sim_worker(
     _hold: Arc<AtomicBool>,
     _status: Arc<Mutex<SimStatus>>,
     _aircraft_title: Arc<Mutex<String>>,
+    _stick_input: InputShared,
+    _controls: ControlsShared,
+    _plugins: PluginHost,
+    _shutdown: ShutdownShared,
 ) {
     // Non-Windows stub: SimConnect is unavailable.
 }
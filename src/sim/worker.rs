@@ -11,13 +11,18 @@ use crossbeam_channel::Sender;
 use libloading::Library;
 use parking_lot::Mutex;
 
+use crate::bindings::ControlsShared;
+use crate::hid::input::InputShared;
 use crate::preset::{
     is_engine_extra_key, PresetShared, SimVarLayout, SimVarProfile, CORE_SIMVARS, CORE_SIMVAR_COUNT,
 };
+use crate::rumble::plugin::PluginHost;
 use crate::rumble::RumbleEngine;
+use crate::shutdown::ShutdownShared;
 use crate::sim::parse::{
     finalize_flight_vars, flight_status, merge_extras, parse_extra_elems, parse_main_elems,
 };
+use crate::sim::{VarsWatch, VARS_STALE_AFTER};
 use crate::{EffectsShared, FlightVars, HidCmd, LogBuffer, SimStatus};
 
 type DWord = u32;
@@ -207,6 +212,10 @@ pub fn sim_worker(
     hold: Arc<AtomicBool>,
     status: Arc<Mutex<SimStatus>>,
     aircraft_title: Arc<Mutex<String>>,
+    stick_input: InputShared,
+    controls: ControlsShared,
+    plugins: PluginHost,
+    shutdown: ShutdownShared,
 ) {
     logs.push("SimConnect: worker started");
 
@@ -222,7 +231,7 @@ pub fn sim_worker(
     };
 
     unsafe {
-        loop {
+        while !shutdown.is_requested() {
             let mut h_sc: Handle = std::ptr::null_mut();
             let name = std::ffi::CString::new("UrsaMinorFFB").unwrap();
             let hr = (fns.open)(
@@ -240,8 +249,10 @@ pub fn sim_worker(
             }
             *status.lock() = SimStatus::Connected;
             *aircraft_title.lock() = String::new();
+            preset.set_aircraft_title("");
 
             let mut rumble_engine = RumbleEngine::new();
+            rumble_engine.set_plugins(plugins.clone());
             let session_simvars = preset.simvar_profile();
             let core_field_count = CORE_SIMVAR_COUNT;
 
@@ -482,12 +493,30 @@ pub fn sim_worker(
             let mut last_logged_intensity: u8 = 255;
             let mut main_frame_count: u64 = 0;
             let mut last_frame_diag = Instant::now();
+            let mut vars_watch = VarsWatch::default();
+            let session_extra_keys_all = session_simvars.layout().extra_keys();
+            let mut custom_effects_rev = 0;
 
-            loop {
+            while !shutdown.is_requested() {
                 if preset.simvar_profile() != session_simvars {
                     logs.push("SimConnect: preset simvars changed, reconnecting".to_string());
                     break;
                 }
+                let rev = preset.current_rev();
+                if rev != custom_effects_rev {
+                    custom_effects_rev = rev;
+                    if let Err(e) = rumble_engine
+                        .set_custom_effects(&preset.custom_effects(), &session_extra_keys_all)
+                    {
+                        logs.push(format!("Rumble: custom effects disabled: {e}"));
+                    }
+                    rumble_engine.set_textures(preset.textures());
+                    rumble_engine.set_patterns(&preset.patterns());
+                    plugins.configure(&preset.plugin_sections());
+                }
+                for line in plugins.take_log() {
+                    logs.push(line);
+                }
 
                 let mut p_recv: *mut SimRecv = std::ptr::null_mut();
                 let mut cb: DWord = 0;
@@ -530,6 +559,7 @@ pub fn sim_worker(
                                     let s_ptr = data_ptr as *const c_char;
                                     let title =
                                         CStr::from_ptr(s_ptr).to_string_lossy().into_owned();
+                                    preset.set_aircraft_title(&title);
                                     *aircraft_title.lock() = title;
                                 }
                                 continue;
@@ -621,6 +651,8 @@ pub fn sim_worker(
                                         finalize_flight_vars(&mut fv);
                                         let cfg_now = preset.rumble_config();
                                         *status.lock() = flight_status(&fv);
+                                        rumble_engine.set_stick_input(*stick_input.lock());
+                                        rumble_engine.set_output_controls(controls.lock().clone());
                                         let out = rumble_engine.step(
                                             &fv,
                                             &cfg_now,
@@ -629,7 +661,11 @@ pub fn sim_worker(
                                         );
                                         effects.apply_snapshot(&out.effects);
                                         *last_vars.lock() = Some(fv);
+                                        vars_watch.refresh(Instant::now());
                                         let _ = tx_hid.send(HidCmd::SendIntensity(out.intensity));
+                                        for play in rumble_engine.take_pattern_plays() {
+                                            let _ = tx_hid.send(HidCmd::PlayPattern(play));
+                                        }
                                     }
                                     continue;
                                 }
@@ -647,6 +683,8 @@ pub fn sim_worker(
                                         finalize_flight_vars(&mut fv);
                                         let cfg_now = preset.rumble_config();
                                         *status.lock() = flight_status(&fv);
+                                        rumble_engine.set_stick_input(*stick_input.lock());
+                                        rumble_engine.set_output_controls(controls.lock().clone());
                                         let out = rumble_engine.step(
                                             &fv,
                                             &cfg_now,
@@ -655,7 +693,11 @@ pub fn sim_worker(
                                         );
                                         effects.apply_snapshot(&out.effects);
                                         *last_vars.lock() = Some(fv);
+                                        vars_watch.refresh(Instant::now());
                                         let _ = tx_hid.send(HidCmd::SendIntensity(out.intensity));
+                                        for play in rumble_engine.take_pattern_plays() {
+                                            let _ = tx_hid.send(HidCmd::PlayPattern(play));
+                                        }
                                     }
                                     continue;
                                 }
@@ -679,6 +721,9 @@ pub fn sim_worker(
 
                                 *status.lock() = flight_status(&fv);
 
+                                rumble_engine.set_stick_input(*stick_input.lock());
+                                rumble_engine.set_output_controls(controls.lock().clone());
+
                                 let out = rumble_engine.step(
                                     &fv,
                                     &cfg_now,
@@ -704,7 +749,11 @@ pub fn sim_worker(
                                     last_rumble_log = Instant::now();
                                 }
                                 *last_vars.lock() = Some(fv);
+                                vars_watch.refresh(Instant::now());
                                 let _ = tx_hid.send(HidCmd::SendIntensity(out.intensity));
+                                for play in rumble_engine.take_pattern_plays() {
+                                    let _ = tx_hid.send(HidCmd::PlayPattern(play));
+                                }
                             }
                         }
                         SIMCONNECT_RECV_ID_EXCEPTION => {}
@@ -714,6 +763,16 @@ pub fn sim_worker(
                     thread::sleep(Duration::from_millis(10));
                 }
 
+                if vars_watch.went_stale(Instant::now()) {
+                    logs.push(format!(
+                        "Sim: no flight data for {} ms → clearing effects",
+                        VARS_STALE_AFTER.as_millis()
+                    ));
+                    *last_vars.lock() = None;
+                    effects.clear_all();
+                    let _ = tx_hid.send(HidCmd::SendIntensity(0));
+                }
+
                 if last_frame_diag.elapsed() >= Duration::from_secs(5) {
                     if main_frame_count > 0 {
                         if let Some(fv) = last_vars.lock().as_ref() {
@@ -791,9 +850,13 @@ pub fn sim_worker(
             let _ = (fns.close)(h_sc);
             *status.lock() = SimStatus::Disconnected;
             *aircraft_title.lock() = String::new();
+            preset.set_aircraft_title("");
             *last_vars.lock() = None;
             let _ = tx_hid.send(HidCmd::SendIntensity(0));
-            thread::sleep(Duration::from_millis(600));
+            if !shutdown.is_requested() {
+                thread::sleep(Duration::from_millis(600));
+            }
         }
     }
+    logs.push("SimConnect: worker stopped");
 }
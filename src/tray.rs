@@ -22,10 +22,11 @@ use windows::Win32::UI::WindowsAndMessaging::{
     RegisterClassW, SetForegroundWindow, ShowWindow, TrackPopupMenu, TranslateMessage, CS_HREDRAW,
     CS_VREDRAW, CW_USEDEFAULT, IDC_ARROW, IMAGE_ICON, LR_DEFAULTCOLOR, LR_SHARED, MENU_ITEM_FLAGS,
     MSG, SHOW_WINDOW_CMD, SW_RESTORE, TPM_BOTTOMALIGN, TPM_LEFTALIGN, TPM_RETURNCMD,
-    TPM_RIGHTBUTTON, TRACK_POPUP_MENU_FLAGS, WM_COMMAND, WM_CONTEXTMENU, WM_DESTROY,
+    TPM_RIGHTBUTTON, TRACK_POPUP_MENU_FLAGS, WM_COMMAND, WM_CONTEXTMENU, WM_DESTROY, WM_ENDSESSION,
     WM_LBUTTONDBLCLK, WM_LBUTTONUP, WM_RBUTTONUP, WM_USER, WNDCLASSW, WS_OVERLAPPED,
 };
 
+use crate::shutdown::ShutdownShared;
 use crate::UiCmd;
 
 const ID_TRAY_STOP_OR_RESUME: u32 = 1002;
@@ -44,12 +45,13 @@ struct TrayState {
     ctx: egui::Context,
     nid: NOTIFYICONDATAW,
     is_held: bool, // drives Stop/Resume label
+    shutdown: ShutdownShared,
 }
 
 static TRAY_STATE: OnceLock<Mutex<Box<TrayState>>> = OnceLock::new();
 
 /// Restore + focus main window by its title, then notify egui to focus too.
-fn bring_main_to_front() {
+pub fn bring_main_to_front() {
     unsafe {
         let title_w = wide(MAIN_WINDOW_TITLE);
         let main_hwnd = FindWindowW(None, PCWSTR(title_w.as_ptr()));
@@ -70,7 +72,7 @@ fn bring_main_to_front() {
 unsafe extern "system" fn wnd_proc(
     hwnd: HWND,
     msg: u32,
-    _wparam: WPARAM,
+    wparam: WPARAM,
     lparam: LPARAM,
 ) -> LRESULT {
     match msg {
@@ -191,6 +193,19 @@ unsafe extern "system" fn wnd_proc(
             LRESULT(0)
         }
 
+        // Release builds have no console, so logoff and shutdown arrive here rather than at the
+        // console handler. Windows may end the process as soon as this returns.
+        WM_ENDSESSION => {
+            if wparam.0 != 0 {
+                if let Some(lock) = TRAY_STATE.get() {
+                    let shutdown = lock.lock().unwrap().shutdown.clone();
+                    shutdown.request("session ending");
+                    shutdown.stop_motor();
+                }
+            }
+            LRESULT(0)
+        }
+
         WM_COMMAND => LRESULT(0),
 
         _ => DefWindowProcW(hwnd, msg, WPARAM(0), lparam),
@@ -213,7 +228,12 @@ fn load_app_icon(hinst: HINSTANCE) -> windows::Win32::UI::WindowsAndMessaging::H
     }
 }
 
-pub fn spawn_tray_with_ctx(tx_ui: Sender<UiCmd>, ctx: egui::Context, app_version: &'static str) {
+pub fn spawn_tray_with_ctx(
+    tx_ui: Sender<UiCmd>,
+    ctx: egui::Context,
+    shutdown: ShutdownShared,
+    app_version: &'static str,
+) {
     std::thread::spawn(move || unsafe {
         let hinst = HINSTANCE(GetModuleHandleW(None).unwrap().0);
 
@@ -265,6 +285,7 @@ pub fn spawn_tray_with_ctx(tx_ui: Sender<UiCmd>, ctx: egui::Context, app_version
             ctx,
             nid,
             is_held: false,
+            shutdown,
         });
         let _ = TRAY_STATE.set(Mutex::new(state));
 
@@ -277,6 +298,20 @@ pub fn spawn_tray_with_ctx(tx_ui: Sender<UiCmd>, ctx: egui::Context, app_version
 }
 
 /// Update tray’s idea of whether output is held (drives label “Stop/Resume”).
+/// Restore the main window, then ask egui to close it: a minimized window runs no frames, so a
+/// bare `ViewportCommand::Close` would wait until the user restores it. False before the tray
+/// (and so the egui context) exists.
+pub fn close_main_window() -> bool {
+    bring_main_to_front();
+    let Some(lock) = TRAY_STATE.get() else {
+        return false;
+    };
+    let st = lock.lock().unwrap();
+    st.ctx.send_viewport_cmd(ViewportCommand::Close);
+    st.ctx.request_repaint();
+    true
+}
+
 pub fn notify_held(held: bool) {
     if let Some(lock) = TRAY_STATE.get() {
         let mut st = lock.lock().unwrap();
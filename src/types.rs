@@ -1,8 +1,11 @@
 use std::collections::HashMap;
 use std::sync::{
-    atomic::{AtomicBool, Ordering},
+    atomic::{AtomicBool, AtomicU32, AtomicU8, Ordering},
     Arc,
 };
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
 
 #[derive(Debug, Clone, Default, PartialEq)]
 pub struct FlightVars {
@@ -45,7 +48,7 @@ pub struct RumbleConfig {
     pub flaps_bump_duration_s: f64,
     pub flaps_bump_eps_pct: f64,
     pub gear_bump_duration_s: f64,
-    #[serde(default = "default_spoilers", alias = "ground_spoilers")]
+    #[serde(default = "default_spoilers")]
     pub spoilers: f32,
     #[serde(default = "default_engine_vibe")]
     pub engine_vibe: f32,
@@ -59,6 +62,12 @@ pub struct RumbleConfig {
     pub eng_rpm_idle: f32,
     #[serde(default = "default_eng_rpm_max")]
     pub eng_rpm_max: f32,
+    /// Silence output while the stick reports no hand on it.
+    #[serde(default)]
+    pub hands_off_cut: bool,
+    /// Extra transient strength (percent) at full stick deflection.
+    #[serde(default)]
+    pub deflection_gain: f32,
 }
 
 fn default_eng_rpm_spool_min() -> f32 {
@@ -116,6 +125,8 @@ impl Default for RumbleConfig {
             eng_rpm_startup_max: default_eng_rpm_startup_max(),
             eng_rpm_idle: default_eng_rpm_idle(),
             eng_rpm_max: default_eng_rpm_max(),
+            hands_off_cut: false,
+            deflection_gain: 0.0,
         }
     }
 }
@@ -134,15 +145,41 @@ pub struct EffectsSnapshot {
     pub spoilers_boost_active: bool,
     pub turb_thump_active: bool,
     pub engine_vibe_active: bool,
+    /// Bit `i` set while the preset's custom effect `i` contributes.
+    pub custom_active: u32,
 }
 
 #[derive(Debug)]
 pub enum HidCmd {
     SendIntensity(u8),
     SendRaw(Vec<u8>),
+    /// Protocol lab: write one report to a single open interface.
+    SendRawTo {
+        path: String,
+        bytes: Vec<u8>,
+    },
+    /// Protocol lab: read an input report, or the feature report `report_id`.
+    ReadReport {
+        path: String,
+        kind: crate::hid::lab::ReportKind,
+        report_id: u8,
+        len: usize,
+    },
     StopAll,
     ReopenDevices,
     SetHold(bool),
+    /// Short fixed-strength pulse confirming a stick-button action; plays through hold.
+    AckPulse,
+    /// Target vibe report rate in Hz; each device's `max_send_hz` still caps it.
+    SetSendRate(u32),
+    /// How long the last `SendIntensity` may stand before the watchdog fades it out.
+    SetWatchdogTimeout(std::time::Duration),
+    /// Rolling-window limits on sustained motor output.
+    SetDutyLimits(crate::hid::duty::DutyLimits),
+    /// Zero every vibe interface and end the worker; replies with how many took the frame.
+    Shutdown(crossbeam_channel::Sender<usize>),
+    /// Play a haptic pattern on top of the continuous intensity, sequenced by the HID worker.
+    PlayPattern(crate::hid::pattern::PatternPlay),
     SetSidestickVariant(crate::hid::protocol::SidestickVariant),
 }
 
@@ -160,6 +197,7 @@ pub struct EffectsState {
     pub spoilers_boost_active: AtomicBool,
     pub turb_thump_active: AtomicBool,
     pub engine_vibe_active: AtomicBool,
+    pub custom_active: AtomicU32,
 }
 
 pub type EffectsShared = Arc<EffectsState>;
@@ -188,6 +226,8 @@ impl EffectsState {
             .store(snap.turb_thump_active, Ordering::Relaxed);
         self.engine_vibe_active
             .store(snap.engine_vibe_active, Ordering::Relaxed);
+        self.custom_active
+            .store(snap.custom_active, Ordering::Relaxed);
     }
 
     pub fn clear_all(&self) {
@@ -195,6 +235,129 @@ impl EffectsState {
     }
 }
 
+/// Write statistics for one open HID interface, published by the HID worker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceHealth {
+    pub path: String,
+    pub pid: u16,
+    pub model: String,
+    pub connected_at: Instant,
+    pub writes_ok: u64,
+    pub writes_failed: u64,
+    pub last_error: Option<String>,
+    /// Smoothed time one vibe write takes.
+    pub write_latency: Duration,
+    /// Slowest vibe write since the interface was opened.
+    pub write_latency_max: Duration,
+    /// Smoothed gap between vibe writes; zero until two have gone out.
+    pub send_interval: Duration,
+}
+
+impl DeviceHealth {
+    pub fn uptime(&self) -> Duration {
+        self.connected_at.elapsed()
+    }
+
+    /// Vibe writes per second actually reaching the device.
+    pub fn send_hz(&self) -> f64 {
+        if self.send_interval.is_zero() {
+            0.0
+        } else {
+            1.0 / self.send_interval.as_secs_f64()
+        }
+    }
+
+    /// Share of writes that failed, in percent.
+    pub fn failure_pct(&self) -> f64 {
+        let total = self.writes_ok + self.writes_failed;
+        if total == 0 {
+            0.0
+        } else {
+            self.writes_failed as f64 * 100.0 / total as f64
+        }
+    }
+
+    /// One line of rate, latency and failure figures, for the status tooltip and the log.
+    pub fn metrics_summary(&self) -> String {
+        format!(
+            "{:.0} Hz, write {:.2} ms avg / {:.2} ms max, {:.1}% failed",
+            self.send_hz(),
+            self.write_latency.as_secs_f64() * 1000.0,
+            self.write_latency_max.as_secs_f64() * 1000.0,
+            self.failure_pct()
+        )
+    }
+}
+
+/// Sidestick connection state shared between the HID worker and the UI.
+#[derive(Default)]
+pub struct ControllerState {
+    connected: AtomicBool,
+    output_stalled: AtomicBool,
+    motor_limited: AtomicBool,
+    /// Share of the requested intensity the duty limiter lets through while `motor_limited`.
+    motor_limit_pct: AtomicU8,
+    devices: Mutex<Vec<DeviceHealth>>,
+}
+
+pub type ControllerShared = Arc<ControllerState>;
+
+impl ControllerState {
+    /// At least one vibe-capable interface is open.
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    pub fn devices(&self) -> Vec<DeviceHealth> {
+        self.devices.lock().clone()
+    }
+
+    pub fn publish(&self, connected: bool, devices: Vec<DeviceHealth>) {
+        *self.devices.lock() = devices;
+        self.connected.store(connected, Ordering::Relaxed);
+    }
+
+    /// The output watchdog faded the output because no fresh intensity arrived.
+    pub fn is_output_stalled(&self) -> bool {
+        self.output_stalled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_output_stalled(&self, stalled: bool) {
+        self.output_stalled.store(stalled, Ordering::Relaxed);
+    }
+
+    /// Percent of the requested intensity let through while the motor duty limiter is active.
+    pub fn motor_limit_pct(&self) -> Option<u8> {
+        self.motor_limited
+            .load(Ordering::Relaxed)
+            .then(|| self.motor_limit_pct.load(Ordering::Relaxed))
+    }
+
+    pub fn set_motor_limit_pct(&self, pct: Option<u8>) {
+        self.motor_limit_pct
+            .store(pct.unwrap_or(100), Ordering::Relaxed);
+        self.motor_limited.store(pct.is_some(), Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HidEvent {
+    Connected {
+        path: String,
+        model: String,
+    },
+    Disconnected {
+        path: String,
+        model: String,
+        reason: String,
+    },
+    /// Rising edge of a stick button, as a bit index into the button field.
+    ButtonPressed {
+        path: String,
+        button: u8,
+    },
+}
+
 #[derive(Debug, Clone)]
 pub enum UiCmd {
     Show,
@@ -203,6 +366,8 @@ pub enum UiCmd {
     Stop,
     Resume,
     Quit,
+    /// A file in the presets folder was edited on disk.
+    FileChanged(crate::preset::FileTarget),
     #[cfg(all(windows, feature = "app"))]
     UpdateAvailable(crate::updater::ReleaseInfo),
 }
@@ -1,9 +1,27 @@
 use egui::{Color32, RichText, Vec2};
 
+mod ab_compare;
+mod bindings_panel;
+mod file_errors;
+mod lab_panel;
+mod preset_manager;
+
 use crate::{
-    preset::{Preset, PresetKind, PresetShared, PresetStore},
-    tray, updater, EffectsShared, FlightVars, HidCmd, LogBuffer, SidestickVariant, SimStatus,
-    UiCmd,
+    bindings::{Action, BindingsShared, ButtonOutcome, ControlsShared},
+    hid::{
+        duty::DutyLimits,
+        input::InputShared,
+        lab::LabShared,
+        session::{MAX_SEND_HZ, MIN_SEND_HZ},
+    },
+    preset::{
+        load_aircraft_overrides, slugify, AbSlot, AppSettings, FileTarget, Preset, PresetError,
+        PresetId, PresetShared, PresetStore, SimVarDef,
+    },
+    rumble::custom::CustomEffectDef,
+    rumble::plugin::{PluginHost, PluginState, PluginStatus},
+    tray, updater, ControllerShared, DeviceHealth, EffectsShared, FlightVars, HidCmd, HidEvent,
+    LogBuffer, RumbleConfig, SidestickVariant, SimStatus, UiCmd,
 };
 use crossbeam_channel::{Receiver, Sender, TryRecvError};
 use parking_lot::Mutex;
@@ -107,13 +125,29 @@ fn status_badge(ui: &mut egui::Ui, status: &SimStatus) {
     });
 }
 
-fn controller_badge_dot(ui: &mut egui::Ui, connected: bool) {
+fn device_health_line(d: &DeviceHealth) -> String {
+    let mut line = format!(
+        "{} (PID=0x{:04X}) up {}s, writes ok={} failed={}, {}",
+        d.model,
+        d.pid,
+        d.uptime().as_secs(),
+        d.writes_ok,
+        d.writes_failed,
+        d.metrics_summary()
+    );
+    if let Some(e) = &d.last_error {
+        line.push_str(&format!(", last error: {e}"));
+    }
+    line
+}
+
+fn controller_badge_dot(ui: &mut egui::Ui, connected: bool, devices: &[DeviceHealth]) {
     let (color, filled) = if connected {
         (Color32::from_rgb(30, 180, 90), true)
     } else {
         (Color32::from_rgb(200, 60, 60), false)
     };
-    ui.horizontal(|ui| {
+    let badge = ui.horizontal(|ui| {
         circle_indicator_colored(ui, color, filled);
         ui.colored_label(
             color,
@@ -124,10 +158,21 @@ fn controller_badge_dot(ui: &mut egui::Ui, connected: bool) {
             },
         );
     });
+    let hover = if devices.is_empty() {
+        "No sidestick interfaces open.".to_string()
+    } else {
+        devices
+            .iter()
+            .map(device_health_line)
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    badge.response.on_hover_text(hover);
 }
 
 pub struct UiState {
-    pub controller_connected: Arc<AtomicBool>,
+    pub controller: ControllerShared,
+    pub rx_hid_events: Receiver<HidEvent>,
 
     pub status: Arc<Mutex<SimStatus>>,
     pub aircraft_title: Arc<Mutex<String>>,
@@ -135,11 +180,16 @@ pub struct UiState {
     pub config: Arc<PresetShared>,
     pub preset_store: PresetStore,
     pub saved_baseline: Preset,
+    /// What "Reset" restores for the current preset; refreshed when the preset changes.
+    reset_target: RumbleConfig,
     pub toast: Option<Toast>,
     pub show_reset_confirm: bool,
     pub update_prompt: Option<updater::ReleaseInfo>,
     pub show_live_aircraft_data: bool,
     pub sidestick_variant: SidestickVariant,
+    pub send_hz: u32,
+    pub watchdog_ms: u32,
+    pub duty_limits: DutyLimits,
     pub effects: EffectsShared,
 
     pub tx_hid: Sender<HidCmd>,
@@ -151,6 +201,21 @@ pub struct UiState {
     pub rx_ui: Receiver<UiCmd>,
     pub tx_ui: Sender<UiCmd>,
 
+    pub lab: LabShared,
+    pub stick_input: InputShared,
+    lab_panel: lab_panel::LabPanel,
+
+    pub controls: ControlsShared,
+    pub button_bindings: BindingsShared,
+    /// Tells the button dispatcher to leave presses alone while a binding is being learned.
+    pub button_learning: Arc<AtomicBool>,
+    pub rx_buttons: Receiver<ButtonOutcome>,
+    pub plugins: PluginHost,
+    bindings_panel: bindings_panel::BindingsPanel,
+    preset_manager: preset_manager::PresetManager,
+    ab: Option<ab_compare::AbSession>,
+    file_errors: file_errors::FileErrors,
+
     viewport_sync: ViewportSync,
 }
 
@@ -163,7 +228,8 @@ struct ViewportSync {
 impl UiState {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
-        controller_connected: Arc<AtomicBool>,
+        controller: ControllerShared,
+        rx_hid_events: Receiver<HidEvent>,
         status: Arc<Mutex<SimStatus>>,
         aircraft_title: Arc<Mutex<String>>,
         config: Arc<PresetShared>,
@@ -171,6 +237,9 @@ impl UiState {
         saved_baseline: Preset,
         show_live_aircraft_data: bool,
         sidestick_variant: SidestickVariant,
+        send_hz: u32,
+        watchdog_ms: u32,
+        duty_limits: DutyLimits,
         effects: EffectsShared,
         tx_hid: Sender<HidCmd>,
         logs: LogBuffer,
@@ -178,19 +247,32 @@ impl UiState {
         hold: Arc<AtomicBool>,
         rx_ui: Receiver<UiCmd>,
         tx_ui: Sender<UiCmd>,
+        lab: LabShared,
+        stick_input: InputShared,
+        controls: ControlsShared,
+        button_bindings: BindingsShared,
+        button_learning: Arc<AtomicBool>,
+        rx_buttons: Receiver<ButtonOutcome>,
+        plugins: PluginHost,
     ) -> Self {
+        let reset_target = preset_store.reset_target(&saved_baseline);
         Self {
-            controller_connected,
+            controller,
+            rx_hid_events,
             status,
             aircraft_title,
             config,
             preset_store,
             saved_baseline,
+            reset_target,
             toast: None,
             show_reset_confirm: false,
             update_prompt: None,
             show_live_aircraft_data,
             sidestick_variant,
+            send_hz,
+            watchdog_ms,
+            duty_limits,
             effects,
             tx_hid,
             logs,
@@ -198,10 +280,28 @@ impl UiState {
             hold,
             rx_ui,
             tx_ui,
+            lab,
+            stick_input,
+            lab_panel: lab_panel::LabPanel::default(),
+            controls,
+            button_bindings,
+            button_learning,
+            rx_buttons,
+            plugins,
+            bindings_panel: bindings_panel::BindingsPanel::default(),
+            preset_manager: preset_manager::PresetManager::default(),
+            ab: None,
+            file_errors: file_errors::FileErrors::default(),
             viewport_sync: ViewportSync::default(),
         }
     }
 
+    /// Show a file that failed to load; the app keeps running on defaults until it is fixed.
+    pub fn report_file_error(&mut self, target: FileTarget, error: PresetError) {
+        self.logs.push(error.to_string());
+        self.file_errors.report(target, error);
+    }
+
     fn live_data_fields(v: Option<&FlightVars>, aircraft: &str) -> Vec<(&'static str, String)> {
         let mut fields = Vec::new();
         if !aircraft.is_empty() {
@@ -263,7 +363,19 @@ impl UiState {
         fields
     }
 
-    fn live_data_grid(ui: &mut egui::Ui, fields: &[(&'static str, String)]) {
+    /// Values of the simvars the preset declares, labelled by key.
+    fn declared_simvar_fields(v: Option<&FlightVars>, defs: &[SimVarDef]) -> Vec<(String, String)> {
+        defs.iter()
+            .map(|def| {
+                let value = v
+                    .and_then(|v| v.extras.get(&def.key))
+                    .map_or_else(|| "—".to_string(), |x| format!("{x:.2}"));
+                (format!("{} ({})", def.key, def.unit), value)
+            })
+            .collect()
+    }
+
+    fn live_data_grid(ui: &mut egui::Ui, fields: &[(String, String)]) {
         let mid = fields.len().div_ceil(2);
         let (left, right) = fields.split_at(mid);
 
@@ -292,9 +404,8 @@ impl UiState {
     }
 
     fn preset_can_reset(&self) -> bool {
-        let kind = self.config.kind();
-        let default = kind.built_in_default();
-        self.config.get() != default || self.saved_baseline != default
+        self.config.get().rumble != self.reset_target
+            || self.saved_baseline.rumble != self.reset_target
     }
 
     fn show_toast(&mut self, message: impl Into<String>, error: bool) {
@@ -358,9 +469,9 @@ impl UiState {
             return;
         }
         self.show_live_aircraft_data = visible;
-        let mut settings = self.preset_store.load_settings();
-        settings.show_live_aircraft_data = visible;
-        let _ = self.preset_store.save_settings(&settings);
+        let _ = self
+            .preset_store
+            .update_settings(|s| s.show_live_aircraft_data = visible);
         self.viewport_sync.synced_height = 0.0;
     }
 
@@ -471,6 +582,56 @@ impl UiState {
         });
     }
 
+    /// Custom effects come from the preset file, so their row only shows activity.
+    fn custom_effect_row(ui: &mut egui::Ui, effect: &CustomEffectDef, active: bool) {
+        ui.horizontal(|ui| {
+            ui.label(RichText::new(&effect.name).strong())
+                .on_hover_text(format!("Custom effect: {}", effect.intensity));
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                let (color, filled) = if active {
+                    (Color32::WHITE, true)
+                } else {
+                    (Color32::from_gray(90), false)
+                };
+                circle_indicator_colored(ui, color, filled);
+            });
+        });
+    }
+
+    /// Plugins are configured in the preset file; their row shows state and activity.
+    fn plugin_row(ui: &mut egui::Ui, plugin: &PluginStatus) {
+        ui.horizontal(|ui| {
+            let title = if plugin.version.is_empty() {
+                plugin.name.clone()
+            } else {
+                format!("{} {}", plugin.name, plugin.version)
+            };
+            ui.label(RichText::new(title).strong())
+                .on_hover_text(format!("Plugin: {}", plugin.path.display()));
+            ui.with_layout(
+                egui::Layout::right_to_left(egui::Align::Center),
+                |ui| match &plugin.state {
+                    PluginState::Running => {
+                        let (color, filled) = if plugin.active != 0 {
+                            (Color32::WHITE, true)
+                        } else {
+                            (Color32::from_gray(90), false)
+                        };
+                        circle_indicator_colored(ui, color, filled);
+                    }
+                    PluginState::Rejected(e) => {
+                        ui.colored_label(Color32::from_rgb(220, 120, 80), "not loaded")
+                            .on_hover_text(e.to_string());
+                    }
+                    PluginState::Disabled(e) => {
+                        ui.colored_label(Color32::from_rgb(220, 120, 80), "disabled")
+                            .on_hover_text(e.to_string());
+                    }
+                },
+            );
+        });
+    }
+
     fn taxi_bound_row(
         ui: &mut egui::Ui,
         name: &str,
@@ -536,12 +697,249 @@ impl UiState {
         });
     }
 
-    fn select_preset(&mut self, kind: PresetKind) {
-        let preset = self.preset_store.load(kind);
+    fn select_preset(&mut self, id: &PresetId) {
+        let preset = match self.preset_store.load_id(id) {
+            Ok(preset) => preset,
+            Err(e) => {
+                self.report_file_error(FileTarget::Preset(id.clone()), e);
+                return;
+            }
+        };
+        self.file_errors.clear(&FileTarget::Preset(id.clone()));
+        let _ = self.preset_store.save_active(preset.id.clone());
         self.config.set(preset.clone());
+        self.reset_target = self.preset_store.reset_target(&preset);
         self.saved_baseline = preset;
         self.toast = None;
-        let _ = self.preset_store.save_active(kind);
+    }
+
+    fn apply_preset_request(
+        &mut self,
+        ctx: &egui::Context,
+        request: preset_manager::PresetRequest,
+    ) {
+        use preset_manager::PresetRequest;
+
+        let current = self.config.get();
+        let result = match request {
+            PresetRequest::Create { name, extends } => {
+                let parent = self.preset_store.load_id(&extends);
+                parent
+                    .map_err(std::io::Error::from)
+                    .and_then(|parent| self.preset_store.create_user(&name, extends, parent.rumble))
+                    .map(|p| {
+                        self.select_preset(&p.id);
+                        format!("Created {}.", p.name)
+                    })
+            }
+            PresetRequest::Duplicate { name } => {
+                self.preset_store.duplicate(&current, &name).map(|p| {
+                    self.select_preset(&p.id);
+                    format!("Created {}.", p.name)
+                })
+            }
+            PresetRequest::Rename { name } => {
+                self.preset_store.rename_user(&current.id, &name).map(|p| {
+                    // Keep unsaved slider edits; only the identity changes.
+                    self.config.rename(p.id.clone(), p.name.clone());
+                    self.saved_baseline.id = p.id;
+                    self.saved_baseline.name = p.name.clone();
+                    format!("Renamed to {}.", p.name)
+                })
+            }
+            PresetRequest::Delete => self.preset_store.delete_user(&current.id).map(|fallback| {
+                self.select_preset(&fallback);
+                format!("Deleted {}.", current.name)
+            }),
+            PresetRequest::Restore(backup) => self
+                .preset_store
+                .restore_backup(&current.id, &backup)
+                .map_err(std::io::Error::from)
+                .map(|p| {
+                    self.select_preset(&p.id);
+                    format!(
+                        "Restored {} from {}.",
+                        p.name,
+                        backup.saved_at.format("%Y-%m-%d %H:%M")
+                    )
+                }),
+            PresetRequest::Export {
+                author,
+                aircraft,
+                include_overrides,
+            } => {
+                let package = self.preset_store.export_package(
+                    &current,
+                    &author,
+                    aircraft,
+                    include_overrides,
+                );
+                let dir = self.export_dir().join("exports");
+                let path = dir.join(format!("{}.yml", slugify(&current.name)));
+                package
+                    .to_yaml()
+                    .and_then(|text| {
+                        std::fs::create_dir_all(&dir)?;
+                        std::fs::write(&path, &text)?;
+                        ctx.output_mut(|o| o.copied_text = text);
+                        Ok(())
+                    })
+                    .map(|()| {
+                        format!(
+                            "Copied {} to the clipboard and saved {}.",
+                            current.name,
+                            path.display()
+                        )
+                    })
+            }
+            PresetRequest::Import {
+                package,
+                name,
+                include_overrides,
+            } => self
+                .preset_store
+                .import_package(&package, &name, include_overrides)
+                .map(|p| {
+                    self.select_preset(&p.id);
+                    format!("Imported {}.", p.name)
+                }),
+        };
+        match result {
+            Ok(message) => {
+                self.preset_manager.finish(Ok(()));
+                self.show_toast(message, false);
+            }
+            Err(e) => self.preset_manager.finish(Err(e.to_string())),
+        }
+    }
+
+    /// Folder beside `presets/` for exported files.
+    fn export_dir(&self) -> std::path::PathBuf {
+        let dir = self.preset_store.dir();
+        dir.parent().unwrap_or(dir).to_path_buf()
+    }
+
+    fn set_hold(&mut self, hold: bool) {
+        self.hold.store(hold, Ordering::Relaxed);
+        let _ = self.tx_hid.send(HidCmd::SetHold(hold));
+        tray::notify_held(hold);
+    }
+
+    fn save_button_settings(&self) {
+        let gain = self.controls.lock().gain_pct;
+        let _ = self.preset_store.update_settings(|s| {
+            s.master_gain_pct = gain;
+            s.button_bindings = self.button_bindings.lock().clone();
+        });
+    }
+
+    /// Bound actions already ran in the button dispatcher; a press only matters here while the
+    /// bindings panel is learning a button.
+    fn on_stick_button(&mut self, button: u8) {
+        let learned = self
+            .bindings_panel
+            .capture(button, &mut self.button_bindings.lock());
+        if learned {
+            self.save_button_settings();
+        }
+    }
+
+    fn on_button_outcome(&mut self, outcome: ButtonOutcome) {
+        match outcome {
+            ButtonOutcome::Held(held) => {
+                self.show_toast(
+                    if held {
+                        "Rumble held."
+                    } else {
+                        "Rumble resumed."
+                    },
+                    false,
+                );
+            }
+            ButtonOutcome::Gain(gain) => {
+                self.show_toast(format!("Master gain {gain}%."), false);
+            }
+            ButtonOutcome::Muted(effect, muted) => {
+                let state = if muted { "muted" } else { "unmuted" };
+                self.show_toast(format!("{} {state}.", effect.label()), false);
+            }
+            ButtonOutcome::Deferred(action) => self.run_action(action),
+        }
+    }
+
+    /// Actions that work on the editor's state, so they wait for the window to draw.
+    fn run_action(&mut self, action: Action) {
+        match action {
+            Action::ToggleHold | Action::GainUp | Action::GainDown | Action::Mute(_) => {}
+            Action::CyclePreset => {
+                let next = self.preset_store.next_preset(&self.config.id());
+                self.select_preset(&next);
+                self.show_toast(format!("{} preset.", self.config.name()), false);
+            }
+            Action::ToggleAb => {
+                if self.ab.is_none() {
+                    if !self.preset_needs_save() {
+                        self.show_toast("Nothing to compare: no unsaved changes.", false);
+                        return;
+                    }
+                    self.apply_ab_request(ab_compare::AbRequest::SavedVsEdited);
+                }
+                if let Some(slot) = self.toggle_ab() {
+                    let label = self.ab.as_ref().map_or("", |s| s.label(slot)).to_string();
+                    self.show_toast(format!("Playing {slot:?}: {label}."), false);
+                }
+            }
+        }
+    }
+
+    fn apply_ab_request(&mut self, request: ab_compare::AbRequest) {
+        use ab_compare::AbRequest;
+
+        match request {
+            AbRequest::SavedVsEdited => {
+                let saved = self.saved_baseline.clone();
+                self.start_ab(saved, ["Edited".to_string(), "Saved".to_string()]);
+            }
+            AbRequest::With(id) => match self.preset_store.load_id(&id) {
+                Ok(other) => {
+                    let labels = [self.config.name(), other.name.clone()];
+                    self.start_ab(other, labels);
+                }
+                Err(e) => self.report_file_error(FileTarget::Preset(id), e),
+            },
+            AbRequest::Toggle => {
+                self.toggle_ab();
+            }
+            AbRequest::End(keep) => self.end_ab(keep),
+        }
+    }
+
+    /// Compare the running preset (A) with `b`, whose values are taken as saved.
+    fn start_ab(&mut self, b: Preset, labels: [String; 2]) {
+        self.config.ab_start(b.clone(), b.rumble.clone());
+        self.ab = Some(ab_compare::AbSession {
+            labels,
+            parked_reset: self.preset_store.reset_target(&b),
+            parked_baseline: b,
+        });
+    }
+
+    /// Swap sides, along with the saved baseline and reset target the UI keeps for each.
+    fn toggle_ab(&mut self) -> Option<AbSlot> {
+        let session = self.ab.as_mut()?;
+        let slot = self.config.ab_toggle()?;
+        std::mem::swap(&mut self.saved_baseline, &mut session.parked_baseline);
+        std::mem::swap(&mut self.reset_target, &mut session.parked_reset);
+        Some(slot)
+    }
+
+    fn end_ab(&mut self, keep: AbSlot) {
+        if self.config.ab_slot().is_some_and(|s| s != keep) {
+            self.toggle_ab();
+        }
+        self.config.ab_end(keep);
+        self.ab = None;
+        let _ = self.preset_store.save_active(self.config.id());
     }
 
     fn select_sidestick_variant(&mut self, variant: SidestickVariant) {
@@ -549,18 +947,28 @@ impl UiState {
             return;
         }
         self.sidestick_variant = variant;
-        let mut settings = self.preset_store.load_settings();
-        settings.sidestick_variant = variant;
-        let _ = self.preset_store.save_settings(&settings);
+        let _ = self
+            .preset_store
+            .update_settings(|s| s.sidestick_variant = variant);
         let _ = self.tx_hid.send(HidCmd::SetSidestickVariant(variant));
     }
 
+    fn set_send_rate(&mut self, hz: u32) {
+        if self.send_hz == hz {
+            return;
+        }
+        self.send_hz = hz;
+        let _ = self.preset_store.update_settings(|s| s.hid_send_hz = hz);
+        let _ = self.tx_hid.send(HidCmd::SetSendRate(hz));
+    }
+
     fn save_current_preset(&mut self) {
         let preset = self.config.get();
         match self.preset_store.save(&preset) {
             Ok(()) => {
+                self.config.mark_saved();
                 self.saved_baseline = preset.clone();
-                self.show_toast(format!("Saved {} preset.", preset.kind.label()), false);
+                self.show_toast(format!("Saved {} preset.", preset.name), false);
             }
             Err(e) => {
                 self.show_toast(format!("Save failed: {e}"), true);
@@ -569,11 +977,139 @@ impl UiState {
     }
 
     fn confirm_reset_preset(&mut self) {
-        let kind = self.config.kind();
-        let preset = self.preset_store.reset_to_built_in(kind);
+        let id = self.config.id();
+        let preset = match self.preset_store.reset(&id) {
+            Ok(preset) => preset,
+            Err(e) => {
+                self.report_file_error(FileTarget::Preset(id), e);
+                return;
+            }
+        };
         self.config.set(preset.clone());
+        self.show_toast(format!("Reset {} to defaults.", preset.name), false);
         self.saved_baseline = preset;
-        self.show_toast(format!("Reset {} to defaults.", kind.label()), false);
+    }
+
+    fn apply_settings(&mut self, settings: AppSettings) {
+        self.set_live_aircraft_data_visible(settings.show_live_aircraft_data);
+        self.select_sidestick_variant(settings.sidestick_variant);
+        self.set_send_rate(settings.hid_send_hz);
+        if self.watchdog_ms != settings.output_watchdog_ms {
+            self.watchdog_ms = settings.output_watchdog_ms;
+            let _ = self
+                .tx_hid
+                .send(HidCmd::SetWatchdogTimeout(Duration::from_millis(
+                    u64::from(self.watchdog_ms),
+                )));
+        }
+        if self.duty_limits != settings.motor_duty {
+            self.duty_limits = settings.motor_duty;
+            let _ = self.tx_hid.send(HidCmd::SetDutyLimits(self.duty_limits));
+        }
+        self.controls.lock().gain_pct = settings.master_gain_pct;
+        *self.button_bindings.lock() = settings.button_bindings;
+    }
+
+    fn reload_file(&mut self, target: FileTarget) {
+        match &target {
+            FileTarget::Settings => match self.preset_store.load_settings() {
+                Ok(settings) => {
+                    self.file_errors.clear(&target);
+                    self.apply_settings(settings);
+                    self.show_toast("Settings reloaded.", false);
+                }
+                Err(e) => self.report_file_error(target, e),
+            },
+            FileTarget::Preset(id) => {
+                let id = id.clone();
+                self.select_preset(&id);
+            }
+            FileTarget::Aircraft => {
+                match load_aircraft_overrides(&self.preset_store.aircraft_path()) {
+                    Ok(table) => {
+                        self.file_errors.clear(&target);
+                        self.config.set_aircraft_overrides(table);
+                        let title = self.aircraft_title.lock().clone();
+                        self.config.set_aircraft_title(&title);
+                        self.show_toast("Aircraft overrides reloaded.", false);
+                    }
+                    Err(e) => self.report_file_error(target, e),
+                }
+            }
+        }
+    }
+
+    /// Something in the presets folder changed on disk (possibly our own write).
+    fn on_file_changed(&mut self, target: FileTarget) {
+        match target {
+            FileTarget::Preset(_) => self.reload_active_preset(),
+            FileTarget::Settings => match self.preset_store.load_settings() {
+                Ok(settings) => {
+                    self.file_errors.clear(&FileTarget::Settings);
+                    if self.settings_differ(&settings) {
+                        self.apply_settings(settings);
+                        self.logs.push("settings.yml changed on disk; reloaded");
+                    }
+                }
+                Err(e) => self.report_file_error(FileTarget::Settings, e),
+            },
+            FileTarget::Aircraft => self.reload_file(FileTarget::Aircraft),
+        }
+    }
+
+    fn settings_differ(&self, settings: &AppSettings) -> bool {
+        settings.show_live_aircraft_data != self.show_live_aircraft_data
+            || settings.sidestick_variant != self.sidestick_variant
+            || settings.hid_send_hz != self.send_hz
+            || settings.output_watchdog_ms != self.watchdog_ms
+            || settings.motor_duty != self.duty_limits
+            || settings.master_gain_pct != self.controls.lock().gain_pct
+            || settings.button_bindings != *self.button_bindings.lock()
+    }
+
+    /// Pick up disk edits to the active preset or one it extends. Unchanged files keep unsaved
+    /// slider edits; a file that no longer loads keeps the last good values running.
+    fn reload_active_preset(&mut self) {
+        let id = self.config.id();
+        match self.preset_store.load_id(&id) {
+            Ok(preset) => {
+                self.file_errors.clear(&FileTarget::Preset(id));
+                if preset != self.saved_baseline {
+                    self.logs
+                        .push(format!("{} changed on disk; reloaded", preset.name));
+                    self.config.set(preset.clone());
+                    self.reset_target = self.preset_store.reset_target(&preset);
+                    self.saved_baseline = preset;
+                    self.show_toast("Preset reloaded from disk.", false);
+                }
+            }
+            Err(e) => {
+                self.logs
+                    .push("Edit rejected; keeping the last good preset");
+                self.report_file_error(FileTarget::Preset(id), e);
+            }
+        }
+    }
+
+    fn apply_file_action(&mut self, action: file_errors::FileAction) {
+        use file_errors::FileAction;
+
+        match action {
+            FileAction::Open(path) => Self::open_path_in_shell(&path),
+            FileAction::Reload(target) => self.reload_file(target),
+            FileAction::Revert(target, path) => match self.preset_store.revert_file(&path) {
+                Ok(backup) => {
+                    self.logs.push(format!(
+                        "Reverted {}; previous contents kept as {}",
+                        path.display(),
+                        backup.display()
+                    ));
+                    self.reload_file(target);
+                }
+                Err(e) => self.show_toast(format!("Revert failed: {e}"), true),
+            },
+            FileAction::Dismiss(target) => self.file_errors.clear(&target),
+        }
     }
 
     fn open_presets_folder(&self) {
@@ -663,13 +1199,43 @@ impl eframe::App for UiState {
                         status_badge(ui, &st);
                         ui.separator();
 
-                        let controller_ok = self.controller_connected.load(Ordering::Relaxed);
-                        controller_badge_dot(ui, controller_ok);
+                        controller_badge_dot(
+                            ui,
+                            self.controller.is_connected(),
+                            &self.controller.devices(),
+                        );
+                        if self.controller.is_output_stalled() {
+                            ui.colored_label(Color32::from_rgb(220, 180, 40), "⚠ Output stopped")
+                                .on_hover_text(
+                                    "No fresh intensity from the sim thread; the output \
+                                     watchdog faded the stick to zero",
+                                );
+                        }
+                        if let Some(pct) = self.controller.motor_limit_pct() {
+                            let l = self.duty_limits;
+                            ui.colored_label(
+                                Color32::from_rgb(220, 180, 40),
+                                format!("Motor limited to {pct}%"),
+                            )
+                            .on_hover_text(format!(
+                                "Sustained output averaged over {}% in the last {} s; \
+                                 the duty limiter scales it down to protect the motor \
+                                 (motor_duty in settings.yml)",
+                                l.onset_pct, l.window_s
+                            ));
+                        }
 
                         let ac = self.aircraft_title.lock().clone();
                         if !ac.is_empty() {
                             ui.separator();
-                            ui.label(RichText::new(ac).italics());
+                            let label = ui.label(RichText::new(ac).italics());
+                            if let Some(o) = self.config.aircraft_override() {
+                                label.on_hover_text(format!(
+                                    "aircraft.yml override \"{}\": {} setting(s)",
+                                    o.title_contains,
+                                    o.rumble.len()
+                                ));
+                            }
                         }
 
                         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
@@ -677,14 +1243,10 @@ impl eframe::App for UiState {
                             let holding = self.hold.load(Ordering::Relaxed);
                             if !holding {
                                 if ui.button("⛔ Stop").clicked() {
-                                    self.hold.store(true, Ordering::Relaxed);
-                                    let _ = self.tx_hid.send(HidCmd::SetHold(true));
-                                    tray::notify_held(true);
+                                    self.set_hold(true);
                                 }
                             } else if ui.button("▶ Resume").clicked() {
-                                self.hold.store(false, Ordering::Relaxed);
-                                let _ = self.tx_hid.send(HidCmd::SetHold(false));
-                                tray::notify_held(false);
+                                self.set_hold(false);
                             }
 
                             ui.separator();
@@ -710,6 +1272,29 @@ impl eframe::App for UiState {
                                                 }
                                             });
                                         ui.label(RichText::new("Sidestick").strong());
+                                        ui.separator();
+                                        let mut hz = self.send_hz;
+                                        let rate = ui
+                                            .add(
+                                                egui::DragValue::new(&mut hz)
+                                                    .clamp_range(MIN_SEND_HZ..=MAX_SEND_HZ)
+                                                    .suffix(" Hz"),
+                                            )
+                                            .on_hover_text(
+                                                "How often intensity is sent to the stick; \
+                                                 each device's limit still applies",
+                                            );
+                                        if rate.changed() {
+                                            self.send_hz = hz;
+                                            let _ = self.tx_hid.send(HidCmd::SetSendRate(hz));
+                                        }
+                                        // Saved once the drag or edit ends.
+                                        if rate.drag_stopped() || rate.lost_focus() {
+                                            let _ = self
+                                                .preset_store
+                                                .update_settings(|s| s.hid_send_hz = hz);
+                                        }
+                                        ui.label(RichText::new("Update rate").strong());
                                     },
                                 );
                             });
@@ -725,6 +1310,10 @@ impl eframe::App for UiState {
             });
 
         self.dismiss_expired_toast();
+        // Loading or resetting a preset ends a comparison.
+        if self.config.ab_slot().is_none() {
+            self.ab = None;
+        }
 
         {
             let mut panel_frame = egui::Frame::central_panel(&ctx.style());
@@ -738,16 +1327,34 @@ impl eframe::App for UiState {
                 ui.set_min_width(panel_w);
                 ui.horizontal(|ui| {
                     ui.label(RichText::new("Preset").strong());
-                    let current = self.config.kind();
+                    let current = self.config.get();
                     egui::ComboBox::from_id_source("preset_kind")
-                        .selected_text(current.label())
+                        .selected_text(&current.name)
                         .show_ui(ui, |ui| {
-                            for kind in PresetKind::ALL {
-                                if ui.selectable_label(current == kind, kind.label()).clicked() {
-                                    self.select_preset(kind);
+                            for p in self.preset_store.all_presets() {
+                                let item = ui.selectable_label(current.id == p.id, &p.name);
+                                let item = if matches!(p.id, PresetId::User(_)) {
+                                    item.on_hover_text(format!("{} family", p.family.label()))
+                                } else {
+                                    item
+                                };
+                                if item.clicked() {
+                                    self.select_preset(&p.id);
                                 }
                             }
                         });
+                    let aircraft = self.aircraft_title.lock().clone();
+                    self.preset_manager.menu(ui, &current, &aircraft, || {
+                        self.preset_store.backups(&current.id)
+                    });
+                    if self.ab.is_none() {
+                        let has_edits = self.preset_needs_save();
+                        if let Some(request) = ab_compare::menu(ui, has_edits, &current.id, || {
+                            self.preset_store.all_presets()
+                        }) {
+                            self.apply_ab_request(request);
+                        }
+                    }
 
                     ui.with_layout(
                         egui::Layout::right_to_left(egui::Align::Center),
@@ -756,6 +1363,9 @@ impl eframe::App for UiState {
                             {
                                 self.open_presets_folder();
                             }
+                            if ui.button("🎮").on_hover_text("Stick button bindings").clicked() {
+                                self.bindings_panel.open = !self.bindings_panel.open;
+                            }
                             let save_enabled = self.preset_needs_save();
                             if ui
                                 .add_enabled(save_enabled, egui::Button::new("Save"))
@@ -774,6 +1384,17 @@ impl eframe::App for UiState {
                     );
                 });
 
+                if let (Some(session), Some(playing), Some(parked)) =
+                    (&self.ab, self.config.ab_slot(), self.config.ab_parked())
+                {
+                    ui.add_space(4.0);
+                    let request =
+                        ab_compare::bar(ui, session, playing, &self.config.get(), &parked);
+                    if let Some(request) = request {
+                        self.apply_ab_request(request);
+                    }
+                }
+
                 ui.add_space(8.0);
                 ui.heading("Rumble Effects");
                 ui.add_space(6.0);
@@ -887,12 +1508,41 @@ impl eframe::App for UiState {
                         &mut _changed,
                     );
 
+                    let stick = *self.stick_input.lock();
+                    UiState::effect_row(
+                        ui,
+                        "Stick deflection",
+                        &mut cfg.deflection_gain,
+                        0.0..=100.0,
+                        stick.is_some_and(|s| s.deflection() > 0.05),
+                        &mut _changed,
+                    );
+                    _changed |= ui
+                        .add_enabled(
+                            stick.is_some(),
+                            egui::Checkbox::new(
+                                &mut cfg.hands_off_cut,
+                                "Cut rumble when hand is off the stick",
+                            ),
+                        )
+                        .on_disabled_hover_text("No stick input reports received yet")
+                        .changed();
+
                     if _changed {
                         self.toast = None;
                     }
                     kind
                 });
 
+                let custom = self.config.custom_effects();
+                let custom_active = self.effects.custom_active.load(Ordering::Relaxed);
+                for (i, effect) in custom.iter().enumerate() {
+                    UiState::custom_effect_row(ui, effect, custom_active & (1 << i) != 0);
+                }
+                for plugin in self.plugins.statuses() {
+                    UiState::plugin_row(ui, &plugin);
+                }
+
                 ui.add_space(8.0);
                 ui.separator();
                 ui.add_space(6.0);
@@ -926,7 +1576,21 @@ impl eframe::App for UiState {
                     ui.add_space(ui.spacing().item_spacing.y);
                     let ac = self.aircraft_title.lock().clone();
                     let v = self.last_vars.lock().clone();
-                    let fields = Self::live_data_fields(v.as_ref(), &ac);
+                    let mut fields: Vec<(String, String)> = Self::live_data_fields(v.as_ref(), &ac)
+                        .into_iter()
+                        .map(|(key, value)| (key.to_string(), value))
+                        .collect();
+                    if let Some(stick) = *self.stick_input.lock() {
+                        fields.push((
+                            "Stick X / Y".to_string(),
+                            format!("{:+.2} / {:+.2}", stick.x, stick.y),
+                        ));
+                        fields.push(("Hand on stick".to_string(), stick.hand_on.to_string()));
+                    }
+                    fields.extend(Self::declared_simvar_fields(
+                        v.as_ref(),
+                        &self.config.declared_simvars(),
+                    ));
                     Self::live_data_grid(ui, &fields);
                 }
                 });
@@ -944,8 +1608,17 @@ impl eframe::App for UiState {
                         .resizable(false)
                         .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
                         .show(ctx, |ui| {
-                            ui.label("Reset this preset to factory defaults?");
-                            ui.label("This action cannot be undone. Your saved preset file will be deleted.");
+                            let preset = self.config.get();
+                            if matches!(preset.id, PresetId::User(_)) {
+                                ui.label(format!(
+                                    "Reset \"{}\" to match its base preset?",
+                                    preset.name
+                                ));
+                                ui.label("This action cannot be undone. The preset file will be overwritten.");
+                            } else {
+                                ui.label("Reset this preset to factory defaults?");
+                                ui.label("This action cannot be undone. Your saved preset file will be deleted.");
+                            }
                             ui.horizontal(|ui| {
                                 if ui.button("Cancel").clicked() {
                                     self.show_reset_confirm = false;
@@ -986,6 +1659,35 @@ impl eframe::App for UiState {
             });
         }
 
+        let export_dir = self.export_dir();
+        self.lab_panel.show(
+            ctx,
+            &self.controller.devices(),
+            &self.lab,
+            &self.tx_hid,
+            &export_dir,
+        );
+
+        if let Some(action) = self.file_errors.show(ctx) {
+            self.apply_file_action(action);
+        }
+
+        if let Some(request) =
+            self.preset_manager
+                .show(ctx, || self.preset_store.all_presets(), &self.config.get())
+        {
+            self.apply_preset_request(ctx, request);
+        }
+
+        let changes =
+            self.bindings_panel
+                .show(ctx, &mut self.button_bindings.lock(), &self.controls);
+        if changes.bindings || changes.gain {
+            self.save_button_settings();
+        }
+        self.button_learning
+            .store(self.bindings_panel.is_learning(), Ordering::Relaxed);
+
         self.sync_viewport_to_content(ctx);
 
         self.draw_toast(ctx);
@@ -994,6 +1696,27 @@ impl eframe::App for UiState {
             ctx.request_repaint_after(remaining.min(Duration::from_millis(50)));
         }
 
+        for note in self.preset_store.take_notes() {
+            self.logs.push(note);
+        }
+
+        while let Ok(event) = self.rx_hid_events.try_recv() {
+            match event {
+                HidEvent::Connected { model, .. } => {
+                    self.show_toast(format!("{model} connected."), false);
+                }
+                HidEvent::Disconnected { model, reason, .. } => {
+                    self.show_toast(format!("{model} disconnected ({reason})."), true);
+                }
+                HidEvent::ButtonPressed { button, .. } => self.on_stick_button(button),
+            }
+            ctx.request_repaint();
+        }
+        while let Ok(outcome) = self.rx_buttons.try_recv() {
+            self.on_button_outcome(outcome);
+            ctx.request_repaint();
+        }
+
         loop {
             match self.rx_ui.try_recv() {
                 Ok(cmd) => match cmd {
@@ -1004,19 +1727,15 @@ impl eframe::App for UiState {
                     }
                     UiCmd::Hide => {}
                     UiCmd::Toggle => {}
-                    UiCmd::Stop => {
-                        self.hold.store(true, Ordering::Relaxed);
-                        let _ = self.tx_hid.send(HidCmd::SetHold(true));
-                        tray::notify_held(true);
-                    }
-                    UiCmd::Resume => {
-                        self.hold.store(false, Ordering::Relaxed);
-                        let _ = self.tx_hid.send(HidCmd::SetHold(false));
-                        tray::notify_held(false);
-                    }
+                    UiCmd::Stop => self.set_hold(true),
+                    UiCmd::Resume => self.set_hold(false),
                     UiCmd::Quit => {
                         ctx.send_viewport_cmd(egui::ViewportCommand::Close);
                     }
+                    UiCmd::FileChanged(target) => {
+                        self.on_file_changed(target);
+                        ctx.request_repaint();
+                    }
                     UiCmd::UpdateAvailable(info) => {
                         self.update_prompt = Some(info);
                         ctx.request_repaint();
@@ -0,0 +1,124 @@
+//! A/B comparison: flip the running preset between two versions to feel the difference.
+
+use egui::RichText;
+
+use crate::preset::{changes, AbSlot, Preset, PresetId, PresetSummary};
+use crate::RumbleConfig;
+
+/// UI side of a running comparison. `PresetShared` holds the two presets; this holds what
+/// `UiState` tracks per preset for the side that is not playing.
+pub struct AbSession {
+    pub labels: [String; 2],
+    pub parked_baseline: Preset,
+    pub parked_reset: RumbleConfig,
+}
+
+impl AbSession {
+    pub fn label(&self, slot: AbSlot) -> &str {
+        match slot {
+            AbSlot::A => &self.labels[0],
+            AbSlot::B => &self.labels[1],
+        }
+    }
+}
+
+pub enum AbRequest {
+    /// A: current slider values, B: the saved file.
+    SavedVsEdited,
+    With(PresetId),
+    Toggle,
+    End(AbSlot),
+}
+
+/// "A/B" menu next to the preset picker, for starting a comparison.
+pub fn menu(
+    ui: &mut egui::Ui,
+    has_edits: bool,
+    current: &PresetId,
+    presets: impl FnOnce() -> Vec<PresetSummary>,
+) -> Option<AbRequest> {
+    let mut request = None;
+    ui.menu_button("A/B", |ui| {
+        if ui
+            .add_enabled(has_edits, egui::Button::new("Edited vs. saved"))
+            .on_disabled_hover_text("No unsaved changes")
+            .clicked()
+        {
+            request = Some(AbRequest::SavedVsEdited);
+            ui.close_menu();
+        }
+        ui.separator();
+        ui.label(RichText::new("Compare with").weak());
+        for p in presets().into_iter().filter(|p| &p.id != current) {
+            if ui.button(&p.name).clicked() {
+                request = Some(AbRequest::With(p.id));
+                ui.close_menu();
+            }
+        }
+    })
+    .response
+    .on_hover_text("Compare two versions on the same maneuver");
+    request
+}
+
+/// Bar shown while comparing: which side plays, the differences, and how to finish.
+pub fn bar(
+    ui: &mut egui::Ui,
+    session: &AbSession,
+    playing: AbSlot,
+    playing_preset: &Preset,
+    parked_preset: &Preset,
+) -> Option<AbRequest> {
+    let mut request = None;
+    let (a, b) = match playing {
+        AbSlot::A => (playing_preset, parked_preset),
+        AbSlot::B => (parked_preset, playing_preset),
+    };
+    ui.horizontal(|ui| {
+        ui.label(RichText::new("A/B").strong());
+        for slot in [AbSlot::A, AbSlot::B] {
+            let name = format!("{slot:?}: {}", session.label(slot));
+            if ui.selectable_label(playing == slot, name).clicked() && playing != slot {
+                request = Some(AbRequest::Toggle);
+            }
+        }
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            for slot in [AbSlot::B, AbSlot::A] {
+                if ui
+                    .button(format!("Keep {slot:?}"))
+                    .on_hover_text(format!("End the comparison with {slot:?} playing"))
+                    .clicked()
+                {
+                    request = Some(AbRequest::End(slot));
+                }
+            }
+        });
+    });
+
+    let diff = changes(&a.rumble, &b.rumble);
+    let title = match diff.len() {
+        0 => "No differences".to_string(),
+        1 => "1 difference".to_string(),
+        n => format!("{n} differences"),
+    };
+    egui::CollapsingHeader::new(title)
+        .id_source("ab_differences")
+        .show(ui, |ui| {
+            egui::Grid::new("ab_diff_grid")
+                .striped(true)
+                .num_columns(3)
+                .show(ui, |ui| {
+                    ui.label("");
+                    ui.label(RichText::new("A").strong());
+                    ui.label(RichText::new("B").strong());
+                    ui.end_row();
+                    for change in &diff {
+                        ui.label(change.label);
+                        ui.monospace(change.from_text());
+                        ui.monospace(change.to_text());
+                        ui.end_row();
+                    }
+                });
+        });
+    request
+}
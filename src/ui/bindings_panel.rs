@@ -0,0 +1,163 @@
+//! Window for mapping stick buttons to actions, plus master gain and muted effects.
+
+use egui::RichText;
+
+use crate::bindings::{Action, ButtonBinding, ControlsShared, Effect, MAX_GAIN_PCT};
+
+#[derive(Default)]
+pub struct BindingsPanel {
+    pub open: bool,
+    /// Row waiting for the next stick button press.
+    learning: Option<usize>,
+    last_pressed: Option<u8>,
+}
+
+/// What the user changed this frame, so the caller knows what to persist.
+#[derive(Default)]
+pub struct PanelChanges {
+    pub bindings: bool,
+    pub gain: bool,
+}
+
+impl BindingsPanel {
+    /// Note a stick button press; returns true if it was taken to fill a learning row.
+    pub fn capture(&mut self, button: u8, bindings: &mut [ButtonBinding]) -> bool {
+        self.last_pressed = Some(button);
+        if !self.open {
+            self.learning = None;
+            return false;
+        }
+        match self.learning.take().and_then(|i| bindings.get_mut(i)) {
+            Some(binding) => {
+                binding.button = button;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether the next stick button press will be taken by a learning row.
+    pub fn is_learning(&self) -> bool {
+        self.open && self.learning.is_some()
+    }
+
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        bindings: &mut Vec<ButtonBinding>,
+        controls: &ControlsShared,
+    ) -> PanelChanges {
+        let mut changes = PanelChanges::default();
+        if !self.open {
+            return changes;
+        }
+
+        let mut open = self.open;
+        egui::Window::new("Stick buttons")
+            .open(&mut open)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                ui.label(match self.last_pressed {
+                    Some(b) => format!("Last pressed: button {b}"),
+                    None => "Press a stick button to see its number.".to_string(),
+                });
+                ui.separator();
+
+                let mut remove = None;
+                egui::Grid::new("button_bindings")
+                    .num_columns(4)
+                    .show(ui, |ui| {
+                        for (i, binding) in bindings.iter_mut().enumerate() {
+                            ui.horizontal(|ui| {
+                                changes.bindings |= ui
+                                    .add(
+                                        egui::DragValue::new(&mut binding.button)
+                                            .clamp_range(0..=63)
+                                            .prefix("Button "),
+                                    )
+                                    .changed();
+                                let learning = self.learning == Some(i);
+                                if ui
+                                    .selectable_label(
+                                        learning,
+                                        if learning { "Press…" } else { "Learn" },
+                                    )
+                                    .on_hover_text("Assign the next button pressed on the stick")
+                                    .clicked()
+                                {
+                                    self.learning = if learning { None } else { Some(i) };
+                                }
+                            });
+
+                            egui::ComboBox::from_id_source(("binding_action", i))
+                                .selected_text(binding.action.label())
+                                .show_ui(ui, |ui| {
+                                    for action in Action::ALL {
+                                        if ui
+                                            .selectable_label(
+                                                binding.action.same_kind(action),
+                                                action.label(),
+                                            )
+                                            .clicked()
+                                            && !binding.action.same_kind(action)
+                                        {
+                                            binding.action = action;
+                                            changes.bindings = true;
+                                        }
+                                    }
+                                });
+
+                            if let Action::Mute(effect) = &mut binding.action {
+                                egui::ComboBox::from_id_source(("binding_effect", i))
+                                    .selected_text(effect.label())
+                                    .show_ui(ui, |ui| {
+                                        for e in Effect::ALL {
+                                            changes.bindings |=
+                                                ui.selectable_value(effect, e, e.label()).changed();
+                                        }
+                                    });
+                            } else {
+                                ui.label("");
+                            }
+
+                            if ui.button("🗑").on_hover_text("Remove binding").clicked() {
+                                remove = Some(i);
+                            }
+                            ui.end_row();
+                        }
+                    });
+                if let Some(i) = remove {
+                    bindings.remove(i);
+                    self.learning = None;
+                    changes.bindings = true;
+                }
+                if ui.button("Add binding").clicked() {
+                    bindings.push(ButtonBinding {
+                        button: self.last_pressed.unwrap_or(0),
+                        action: Action::ToggleHold,
+                    });
+                    changes.bindings = true;
+                }
+
+                ui.separator();
+                let mut c = controls.lock();
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new("Master gain").strong());
+                    changes.gain |= ui
+                        .add(egui::Slider::new(&mut c.gain_pct, 0..=MAX_GAIN_PCT).suffix("%"))
+                        .changed();
+                });
+                ui.label(RichText::new("Muted effects").strong());
+                ui.horizontal_wrapped(|ui| {
+                    for effect in Effect::ALL {
+                        let mut muted = c.is_muted(effect);
+                        if ui.checkbox(&mut muted, effect.label()).changed() {
+                            c.toggle_mute(effect);
+                        }
+                    }
+                });
+            });
+        self.open = open;
+        changes
+    }
+}
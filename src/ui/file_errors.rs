@@ -0,0 +1,81 @@
+//! Window listing preset and settings files that failed to load, with ways to fix or revert them.
+
+use std::path::PathBuf;
+
+use egui::{Color32, RichText};
+
+use crate::preset::{FileTarget, PresetError};
+
+pub enum FileAction {
+    Open(PathBuf),
+    Reload(FileTarget),
+    Revert(FileTarget, PathBuf),
+    Dismiss(FileTarget),
+}
+
+#[derive(Default)]
+pub struct FileErrors {
+    errors: Vec<(FileTarget, PresetError)>,
+}
+
+impl FileErrors {
+    /// Add or replace the error for `target`.
+    pub fn report(&mut self, target: FileTarget, error: PresetError) {
+        self.clear(&target);
+        self.errors.push((target, error));
+    }
+
+    pub fn clear(&mut self, target: &FileTarget) {
+        self.errors.retain(|(t, _)| t != target);
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<FileAction> {
+        if self.errors.is_empty() {
+            return None;
+        }
+        let mut action = None;
+        egui::Window::new("Preset file problems")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label("These files could not be used. Nothing has been overwritten.");
+                for (target, error) in &self.errors {
+                    ui.separator();
+                    ui.label(
+                        RichText::new(error.to_string()).color(Color32::from_rgb(200, 60, 60)),
+                    );
+                    ui.horizontal(|ui| {
+                        if ui
+                            .button("Open file")
+                            .on_hover_text("Edit the file, then press Reload")
+                            .clicked()
+                        {
+                            action = Some(FileAction::Open(error.file().to_path_buf()));
+                        }
+                        if ui.button("Reload").clicked() {
+                            action = Some(FileAction::Reload(target.clone()));
+                        }
+                        if ui
+                            .button("Revert")
+                            .on_hover_text("Keep the file as .invalid.bak and go back to defaults")
+                            .clicked()
+                        {
+                            action = Some(FileAction::Revert(
+                                target.clone(),
+                                error.file().to_path_buf(),
+                            ));
+                        }
+                        if ui
+                            .button("Dismiss")
+                            .on_hover_text("Keep using defaults for now")
+                            .clicked()
+                        {
+                            action = Some(FileAction::Dismiss(target.clone()));
+                        }
+                    });
+                }
+            });
+        action
+    }
+}
@@ -0,0 +1,233 @@
+//! Developer window for the HID protocol lab (toggle with Ctrl+Shift+L).
+
+use crossbeam_channel::Sender;
+use egui::RichText;
+
+use crate::hid::lab::{format_hex, parse_hex, LabFrame, LabShared, ReportKind};
+use crate::{DeviceHealth, HidCmd};
+
+const LOG_ROWS_SHOWN: usize = 200;
+
+pub struct LabPanel {
+    pub open: bool,
+    target: String,
+    raw_mode: bool,
+    raw_hex: String,
+    report_id: String,
+    channel: String,
+    opcode: String,
+    payload: String,
+    len: u16,
+    read_kind: ReportKind,
+    feature_id: String,
+    read_len: usize,
+    status: Option<String>,
+}
+
+impl Default for LabPanel {
+    fn default() -> Self {
+        let vibe = LabFrame::simapp_vibe(0x07, 0x00);
+        Self {
+            open: std::env::var_os("URSA_HID_LAB").is_some(),
+            target: String::new(),
+            raw_mode: false,
+            raw_hex: String::new(),
+            report_id: format!("{:02X}", vibe.report_id),
+            channel: format!("{:02X}", vibe.channel),
+            opcode: format_hex(&vibe.opcode),
+            payload: format_hex(&vibe.payload),
+            len: vibe.len,
+            read_kind: ReportKind::Input,
+            feature_id: "00".to_string(),
+            read_len: 64,
+            status: None,
+        }
+    }
+}
+
+fn single_byte(text: &str, what: &str) -> Result<u8, String> {
+    match parse_hex(text).map_err(|e| e.to_string())?.as_slice() {
+        [b] => Ok(*b),
+        _ => Err(format!("{what} must be one byte")),
+    }
+}
+
+impl LabPanel {
+    fn frame_bytes(&self) -> Result<Vec<u8>, String> {
+        if self.raw_mode {
+            return parse_hex(&self.raw_hex).map_err(|e| e.to_string());
+        }
+        Ok(LabFrame {
+            report_id: single_byte(&self.report_id, "Report ID")?,
+            channel: single_byte(&self.channel, "Channel")?,
+            opcode: parse_hex(&self.opcode).map_err(|e| e.to_string())?,
+            payload: parse_hex(&self.payload).map_err(|e| e.to_string())?,
+            len: self.len,
+        }
+        .build())
+    }
+
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        devices: &[DeviceHealth],
+        lab: &LabShared,
+        tx_hid: &Sender<HidCmd>,
+        export_dir: &std::path::Path,
+    ) {
+        if ctx.input(|i| i.modifiers.ctrl && i.modifiers.shift && i.key_pressed(egui::Key::L)) {
+            self.open = !self.open;
+        }
+        if !self.open {
+            return;
+        }
+
+        if self.target.is_empty() || !devices.iter().any(|d| d.path == self.target) {
+            self.target = devices.first().map(|d| d.path.clone()).unwrap_or_default();
+        }
+
+        let mut open = self.open;
+        egui::Window::new("HID protocol lab")
+            .open(&mut open)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Interface");
+                    egui::ComboBox::from_id_source("lab_target")
+                        .selected_text(
+                            devices
+                                .iter()
+                                .find(|d| d.path == self.target)
+                                .map(|d| format!("{} (PID=0x{:04X})", d.model, d.pid))
+                                .unwrap_or_else(|| "none open".to_string()),
+                        )
+                        .show_ui(ui, |ui| {
+                            for d in devices {
+                                ui.selectable_value(
+                                    &mut self.target,
+                                    d.path.clone(),
+                                    format!("{} (PID=0x{:04X})", d.model, d.pid),
+                                )
+                                .on_hover_text(&d.path);
+                            }
+                        });
+                });
+
+                ui.separator();
+                ui.checkbox(&mut self.raw_mode, "Raw bytes");
+                if self.raw_mode {
+                    ui.text_edit_singleline(&mut self.raw_hex);
+                } else {
+                    egui::Grid::new("lab_frame").num_columns(2).show(ui, |ui| {
+                        ui.label("Report ID");
+                        ui.text_edit_singleline(&mut self.report_id);
+                        ui.end_row();
+                        ui.label("Channel");
+                        ui.text_edit_singleline(&mut self.channel);
+                        ui.end_row();
+                        ui.label("Opcode");
+                        ui.text_edit_singleline(&mut self.opcode);
+                        ui.end_row();
+                        ui.label("Payload");
+                        ui.text_edit_singleline(&mut self.payload);
+                        ui.end_row();
+                        ui.label("Length");
+                        ui.add(egui::DragValue::new(&mut self.len).clamp_range(0..=64));
+                        ui.end_row();
+                    });
+                }
+
+                let frame = self.frame_bytes();
+                match &frame {
+                    Ok(bytes) => {
+                        ui.monospace(format_hex(bytes));
+                    }
+                    Err(e) => {
+                        ui.colored_label(egui::Color32::from_rgb(200, 60, 60), e);
+                    }
+                }
+                ui.add_enabled_ui(frame.is_ok() && !self.target.is_empty(), |ui| {
+                    if ui.button("Send").clicked() {
+                        if let Ok(bytes) = frame {
+                            let _ = tx_hid.send(HidCmd::SendRawTo {
+                                path: self.target.clone(),
+                                bytes,
+                            });
+                        }
+                    }
+                });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut self.read_kind, ReportKind::Input, "Input");
+                    ui.radio_value(&mut self.read_kind, ReportKind::Feature, "Feature ID");
+                    ui.add_enabled(
+                        self.read_kind == ReportKind::Feature,
+                        egui::TextEdit::singleline(&mut self.feature_id).desired_width(28.0),
+                    );
+                    ui.label("Length");
+                    ui.add(egui::DragValue::new(&mut self.read_len).clamp_range(1..=256));
+                    let can_read = !self.target.is_empty();
+                    if ui
+                        .add_enabled(can_read, egui::Button::new("Read"))
+                        .clicked()
+                    {
+                        match single_byte(&self.feature_id, "Feature ID") {
+                            Ok(report_id) => {
+                                let _ = tx_hid.send(HidCmd::ReadReport {
+                                    path: self.target.clone(),
+                                    kind: self.read_kind,
+                                    report_id,
+                                    len: self.read_len,
+                                });
+                                self.status = None;
+                            }
+                            Err(e) => self.status = Some(e),
+                        }
+                    }
+                });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new("Session log").strong());
+                    if ui.button("Clear").clicked() {
+                        lab.lock().clear();
+                    }
+                    if ui.button("Export").clicked() {
+                        let name = format!(
+                            "hid-lab-{}.txt",
+                            chrono::Local::now().format("%Y%m%d-%H%M%S")
+                        );
+                        let path = export_dir.join(name);
+                        self.status = Some(match lab.lock().export_to(&path) {
+                            Ok(()) => format!("Exported → {}", path.display()),
+                            Err(e) => format!("Export failed: {e}"),
+                        });
+                    }
+                });
+                if let Some(status) = &self.status {
+                    ui.label(status);
+                }
+
+                let lines: Vec<String> = {
+                    let log = lab.lock();
+                    let entries = log.entries();
+                    entries
+                        .iter()
+                        .skip(entries.len().saturating_sub(LOG_ROWS_SHOWN))
+                        .map(|e| e.line())
+                        .collect()
+                };
+                egui::ScrollArea::vertical()
+                    .max_height(220.0)
+                    .stick_to_bottom(true)
+                    .show(ui, |ui| {
+                        for line in lines {
+                            ui.monospace(line);
+                        }
+                    });
+            });
+        self.open = open;
+        ctx.request_repaint_after(std::time::Duration::from_millis(200));
+    }
+}
@@ -0,0 +1,448 @@
+//! Menu and dialogs for creating, duplicating, renaming and deleting user presets,
+//! restoring an earlier saved version, and sharing presets as packages.
+
+use crate::preset::{Backup, Preset, PresetId, PresetPackage, PresetSummary};
+
+enum Dialog {
+    New {
+        name: String,
+        extends: PresetId,
+    },
+    Duplicate {
+        name: String,
+    },
+    Rename {
+        name: String,
+    },
+    Delete {
+        name: String,
+    },
+    Restore {
+        backups: Vec<Backup>,
+        selected: usize,
+    },
+    Export {
+        author: String,
+        /// One aircraft title per line.
+        aircraft: String,
+        include_overrides: bool,
+    },
+    Import {
+        text: String,
+        parsed: Option<Result<Box<PresetPackage>, String>>,
+        name: String,
+        include_overrides: bool,
+    },
+}
+
+impl Dialog {
+    fn import() -> Self {
+        Dialog::Import {
+            text: String::new(),
+            parsed: None,
+            name: String::new(),
+            include_overrides: true,
+        }
+    }
+}
+
+/// Confirmed dialog; the caller applies it to the store and reports back with `finish`.
+pub enum PresetRequest {
+    Create {
+        name: String,
+        extends: PresetId,
+    },
+    Duplicate {
+        name: String,
+    },
+    Rename {
+        name: String,
+    },
+    Delete,
+    Restore(Backup),
+    Export {
+        author: String,
+        aircraft: Vec<String>,
+        include_overrides: bool,
+    },
+    Import {
+        package: Box<PresetPackage>,
+        name: String,
+        include_overrides: bool,
+    },
+}
+
+#[derive(Default)]
+pub struct PresetManager {
+    dialog: Option<Dialog>,
+    error: Option<String>,
+}
+
+impl PresetManager {
+    /// "⋯" menu next to the preset picker. `backups` lists earlier versions of `current`;
+    /// `aircraft` is the loaded aircraft's title, suggested as the target of an export.
+    pub fn menu(
+        &mut self,
+        ui: &mut egui::Ui,
+        current: &Preset,
+        aircraft: &str,
+        backups: impl FnOnce() -> Vec<Backup>,
+    ) {
+        let is_user = matches!(current.id, PresetId::User(_));
+        ui.menu_button("⋯", |ui| {
+            if ui.button("New preset…").clicked() {
+                self.open(Dialog::New {
+                    name: String::new(),
+                    extends: current.id.clone(),
+                });
+                ui.close_menu();
+            }
+            if ui.button("Duplicate…").clicked() {
+                self.open(Dialog::Duplicate {
+                    name: format!("{} copy", current.name),
+                });
+                ui.close_menu();
+            }
+            if ui
+                .add_enabled(is_user, egui::Button::new("Rename…"))
+                .clicked()
+            {
+                self.open(Dialog::Rename {
+                    name: current.name.clone(),
+                });
+                ui.close_menu();
+            }
+            if ui
+                .add_enabled(is_user, egui::Button::new("Delete…"))
+                .clicked()
+            {
+                self.open(Dialog::Delete {
+                    name: current.name.clone(),
+                });
+                ui.close_menu();
+            }
+            ui.separator();
+            if ui.button("Restore earlier version…").clicked() {
+                self.open(Dialog::Restore {
+                    backups: backups(),
+                    selected: 0,
+                });
+                ui.close_menu();
+            }
+            ui.separator();
+            if ui.button("Export…").clicked() {
+                self.open(Dialog::Export {
+                    author: String::new(),
+                    aircraft: aircraft.to_string(),
+                    include_overrides: false,
+                });
+                ui.close_menu();
+            }
+            if ui.button("Import…").clicked() {
+                self.open(Dialog::import());
+                ui.close_menu();
+            }
+        })
+        .response
+        .on_hover_text("Manage presets");
+    }
+
+    fn open(&mut self, dialog: Dialog) {
+        self.dialog = Some(dialog);
+        self.error = None;
+    }
+
+    /// Close on success, or keep the dialog open showing `error`.
+    pub fn finish(&mut self, result: Result<(), String>) {
+        match result {
+            Ok(()) => {
+                self.dialog = None;
+                self.error = None;
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    /// `presets` lists the possible parents for a new preset, read only while that dialog is
+    /// open; imports are previewed against `current`.
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        presets: impl FnOnce() -> Vec<PresetSummary>,
+        current: &Preset,
+    ) -> Option<PresetRequest> {
+        let dialog = self.dialog.as_mut()?;
+        let title = match dialog {
+            Dialog::New { .. } => "New preset",
+            Dialog::Duplicate { .. } => "Duplicate preset",
+            Dialog::Rename { .. } => "Rename preset",
+            Dialog::Delete { .. } => "Delete preset",
+            Dialog::Restore { .. } => "Restore earlier version",
+            Dialog::Export { .. } => "Export preset",
+            Dialog::Import { .. } => "Import preset",
+        };
+
+        let mut request = None;
+        let mut cancel = false;
+        egui::Window::new(title)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                match dialog {
+                    Dialog::New { name, extends } => {
+                        let presets = presets();
+                        ui.horizontal(|ui| {
+                            ui.label("Name");
+                            ui.text_edit_singleline(name).request_focus();
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Based on");
+                            let selected = presets
+                                .iter()
+                                .find(|p| &p.id == extends)
+                                .map_or_else(|| extends.to_string(), |p| p.name.clone());
+                            egui::ComboBox::from_id_source("new_preset_extends")
+                                .selected_text(selected)
+                                .show_ui(ui, |ui| {
+                                    for p in &presets {
+                                        ui.selectable_value(extends, p.id.clone(), &p.name);
+                                    }
+                                });
+                        });
+                        ui.label(
+                            egui::RichText::new(
+                                "Only changed sliders are stored; the rest follow the base preset.",
+                            )
+                            .weak(),
+                        );
+                    }
+                    Dialog::Duplicate { name } | Dialog::Rename { name } => {
+                        ui.horizontal(|ui| {
+                            ui.label("Name");
+                            ui.text_edit_singleline(name).request_focus();
+                        });
+                    }
+                    Dialog::Delete { name } => {
+                        ui.label(format!("Delete \"{name}\"?"));
+                        ui.label("This action cannot be undone. The preset file will be deleted.");
+                    }
+                    Dialog::Restore { backups, selected } => {
+                        if backups.is_empty() {
+                            ui.label("No earlier versions saved yet.");
+                        }
+                        for (i, backup) in backups.iter().enumerate() {
+                            let when = backup.saved_at.format("%Y-%m-%d %H:%M:%S").to_string();
+                            ui.radio_value(selected, i, when);
+                        }
+                        ui.label(
+                            egui::RichText::new(
+                                "Times are when each version was replaced. Unsaved changes are discarded; \
+                                 the version on disk now is kept in the history.",
+                            )
+                            .weak(),
+                        );
+                    }
+                    Dialog::Export {
+                        author,
+                        aircraft,
+                        include_overrides,
+                    } => {
+                        ui.label(format!(
+                            "\"{}\" with its current slider values.",
+                            current.name
+                        ));
+                        ui.horizontal(|ui| {
+                            ui.label("Author");
+                            ui.text_edit_singleline(author);
+                        });
+                        ui.label("Made for (one aircraft title per line)");
+                        ui.add(egui::TextEdit::multiline(aircraft).desired_rows(2));
+                        ui.checkbox(include_overrides, "Include matching aircraft overrides");
+                        ui.label(
+                            egui::RichText::new(
+                                "The package is copied to the clipboard and saved in the exports folder.",
+                            )
+                            .weak(),
+                        );
+                    }
+                    Dialog::Import {
+                        text,
+                        parsed,
+                        name,
+                        include_overrides,
+                    } => {
+                        ui.label("Paste a shared preset, or drop its file on the window.");
+                        let dropped = ctx.input(|i| {
+                            i.raw
+                                .dropped_files
+                                .first()
+                                .and_then(|f| f.path.as_ref())
+                                .and_then(|p| std::fs::read_to_string(p).ok())
+                        });
+                        if let Some(contents) = dropped {
+                            *text = contents;
+                            *parsed = None;
+                        }
+                        egui::ScrollArea::vertical()
+                            .id_source("import_text")
+                            .max_height(120.0)
+                            .show(ui, |ui| {
+                                let edit = ui.add(
+                                    egui::TextEdit::multiline(text)
+                                        .code_editor()
+                                        .desired_rows(6)
+                                        .desired_width(f32::INFINITY),
+                                );
+                                if edit.changed() {
+                                    *parsed = None;
+                                }
+                            });
+                        if parsed.is_none() && !text.trim().is_empty() {
+                            let result = PresetPackage::parse(text)
+                                .map(Box::new)
+                                .map_err(|e| e.to_string());
+                            if let Ok(package) = &result {
+                                *name = package.name.clone();
+                            }
+                            *parsed = Some(result);
+                        }
+                        match parsed {
+                            Some(Ok(package)) => {
+                                import_preview(ui, package, current, name, include_overrides)
+                            }
+                            Some(Err(e)) => {
+                                ui.colored_label(egui::Color32::from_rgb(200, 60, 60), e.as_str());
+                            }
+                            None => {}
+                        }
+                    }
+                }
+                if let Some(e) = &self.error {
+                    ui.colored_label(egui::Color32::from_rgb(200, 60, 60), e);
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                    let confirm = match dialog {
+                        Dialog::Delete { .. } => "Delete",
+                        Dialog::Restore { .. } => "Restore",
+                        Dialog::Export { .. } => "Export",
+                        Dialog::Import { .. } => "Import",
+                        _ => "OK",
+                    };
+                    // Enter adds a line in the multi-line fields of these two.
+                    let enter = ui.input(|i| i.key_pressed(egui::Key::Enter))
+                        && !matches!(dialog, Dialog::Export { .. } | Dialog::Import { .. });
+                    let available = match dialog {
+                        Dialog::Restore { backups, .. } => !backups.is_empty(),
+                        Dialog::Import { parsed, .. } => matches!(parsed, Some(Ok(_))),
+                        _ => true,
+                    };
+                    let clicked = ui
+                        .add_enabled(available, egui::Button::new(confirm))
+                        .clicked();
+                    if available && (clicked || enter) {
+                        request = Some(match dialog {
+                            Dialog::New { name, extends } => PresetRequest::Create {
+                                name: name.clone(),
+                                extends: extends.clone(),
+                            },
+                            Dialog::Duplicate { name } => {
+                                PresetRequest::Duplicate { name: name.clone() }
+                            }
+                            Dialog::Rename { name } => PresetRequest::Rename { name: name.clone() },
+                            Dialog::Delete { .. } => PresetRequest::Delete,
+                            Dialog::Restore { backups, selected } => {
+                                PresetRequest::Restore(backups[*selected].clone())
+                            }
+                            Dialog::Export {
+                                author,
+                                aircraft,
+                                include_overrides,
+                            } => PresetRequest::Export {
+                                author: author.clone(),
+                                aircraft: aircraft
+                                    .lines()
+                                    .map(str::trim)
+                                    .filter(|l| !l.is_empty())
+                                    .map(str::to_string)
+                                    .collect(),
+                                include_overrides: *include_overrides,
+                            },
+                            Dialog::Import {
+                                parsed: Some(Ok(package)),
+                                name,
+                                include_overrides,
+                                ..
+                            } => PresetRequest::Import {
+                                package: package.clone(),
+                                name: name.clone(),
+                                include_overrides: *include_overrides,
+                            },
+                            Dialog::Import { .. } => unreachable!("import is enabled once parsed"),
+                        });
+                    }
+                });
+            });
+
+        if cancel {
+            self.dialog = None;
+            self.error = None;
+        }
+        request
+    }
+}
+
+/// Package details and what importing it would change.
+fn import_preview(
+    ui: &mut egui::Ui,
+    package: &PresetPackage,
+    current: &Preset,
+    name: &mut String,
+    include_overrides: &mut bool,
+) {
+    ui.separator();
+    let by = if package.author.is_empty() {
+        String::new()
+    } else {
+        format!(" by {}", package.author)
+    };
+    ui.label(format!(
+        "{}{by}, {} family (app {})",
+        package.name,
+        package.family.label(),
+        package.app_version
+    ));
+    if !package.aircraft.is_empty() {
+        ui.label(format!("Made for: {}", package.aircraft.join(", ")));
+    }
+    ui.horizontal(|ui| {
+        ui.label("Import as");
+        ui.text_edit_singleline(name);
+    });
+    let changes = package.preview(&current.rumble);
+    if changes.is_empty() {
+        ui.label(format!("Same values as \"{}\".", current.name));
+    } else {
+        ui.label(format!("Differences from \"{}\":", current.name));
+        egui::ScrollArea::vertical()
+            .id_source("import_changes")
+            .max_height(160.0)
+            .show(ui, |ui| {
+                for change in &changes {
+                    ui.monospace(change.to_string());
+                }
+            });
+    }
+    if !package.aircraft_overrides.is_empty() {
+        ui.checkbox(
+            include_overrides,
+            format!(
+                "Add its {} aircraft override(s) to aircraft.yml",
+                package.aircraft_overrides.len()
+            ),
+        );
+    }
+}
@@ -0,0 +1,861 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crossbeam_channel::unbounded;
+
+use ursa_minor_ffb::hid::clock::{Clock, ManualClock};
+use ursa_minor_ffb::hid::duty::DutyLimits;
+use ursa_minor_ffb::hid::input::{InputShared, HANDS_OFF_AFTER};
+use ursa_minor_ffb::hid::lab::{self, Direction, LabShared, ReportKind};
+use ursa_minor_ffb::hid::mock::{MockBackend, MockHid};
+use ursa_minor_ffb::hid::pattern::{Pattern, PatternPlay};
+use ursa_minor_ffb::hid::protocol::{
+    SidestickVariant, WW_PID_URSA_MINOR_AIRBUS_L, WW_PID_URSA_MINOR_AIRBUS_R, WW_VID,
+};
+use ursa_minor_ffb::hid::registry::DeviceRegistry;
+use ursa_minor_ffb::hid::session::{
+    HidSession, ACK_PULSE_DURATION, ACK_PULSE_INTENSITY, DEFAULT_SEND_HZ, DEFAULT_WATCHDOG_TIMEOUT,
+    IDLE_RESCAN_INTERVAL, KEEPALIVE_INTERVAL, MAX_CONSECUTIVE_WRITE_FAILURES,
+    PATTERN_SEND_INTERVAL, RECONNECT_BACKOFF_MAX, RECONNECT_BACKOFF_MIN, WATCHDOG_FADE,
+};
+use ursa_minor_ffb::hid::transport::HidDeviceInfo;
+use ursa_minor_ffb::{ControllerShared, HidCmd, HidEvent, LogBuffer};
+
+/// Send cadence at the default rate.
+const SEND_INTERVAL: Duration = Duration::from_millis(1000 / DEFAULT_SEND_HZ as u64);
+
+struct Rig {
+    clock: ManualClock,
+    hid: MockHid,
+    controller: ControllerShared,
+    session: HidSession<MockBackend, ManualClock>,
+}
+
+fn rig() -> Rig {
+    let clock = ManualClock::new();
+    let hid = MockHid::new(clock.clone());
+    hid.plug(
+        MockHid::sidestick_info("stick-l", WW_PID_URSA_MINOR_AIRBUS_L),
+        Some((14, 0x02)),
+    );
+    let controller = ControllerShared::default();
+    let session = HidSession::new(
+        hid.backend(),
+        clock.clone(),
+        controller.clone(),
+        LogBuffer::default(),
+    );
+    Rig {
+        clock,
+        hid,
+        controller,
+        session,
+    }
+}
+
+fn intensities(hid: &MockHid) -> Vec<u8> {
+    hid.take_frames().iter().map(|f| f.bytes[8]).collect()
+}
+
+#[test]
+fn first_tick_opens_device_and_sends_zero() {
+    let mut r = rig();
+    r.session.tick();
+
+    assert!(r.controller.is_connected());
+    assert_eq!(r.session.device_count(), 1);
+    assert_eq!(intensities(&r.hid), vec![0]);
+}
+
+#[test]
+fn sends_only_on_change_at_send_interval() {
+    let mut r = rig();
+    r.session.tick();
+    r.hid.take_frames();
+
+    r.session.handle(HidCmd::SendIntensity(40));
+    r.session.tick();
+    assert!(r.hid.frames().is_empty(), "must wait for SEND_INTERVAL");
+
+    r.clock.advance(SEND_INTERVAL);
+    r.session.tick();
+    r.clock.advance(SEND_INTERVAL);
+    r.session.tick();
+
+    let frames = r.hid.take_frames();
+    assert_eq!(frames.len(), 1, "unchanged intensity is not resent");
+    assert_eq!(frames[0].bytes[8], 40);
+    assert_eq!(frames[0].at, SEND_INTERVAL);
+}
+
+#[test]
+fn hold_zeroes_immediately_and_masks_intensity() {
+    let mut r = rig();
+    r.session.handle(HidCmd::SendIntensity(90));
+    r.session.tick();
+    r.hid.take_frames();
+
+    r.session.handle(HidCmd::SetHold(true));
+    assert_eq!(intensities(&r.hid), vec![0]);
+    assert!(r.session.is_held());
+
+    r.session.handle(HidCmd::SendIntensity(120));
+    r.clock.advance(SEND_INTERVAL);
+    r.session.tick();
+    assert!(r.hid.frames().is_empty());
+
+    r.session.handle(HidCmd::SetHold(false));
+    r.clock.advance(SEND_INTERVAL);
+    r.session.tick();
+    assert_eq!(intensities(&r.hid), vec![120]);
+}
+
+#[test]
+fn variant_switch_resends_with_new_channel() {
+    let mut r = rig();
+    r.session.handle(HidCmd::SendIntensity(30));
+    r.session.tick();
+    assert_eq!(r.hid.take_frames()[0].bytes[1], 0x07);
+
+    r.session
+        .handle(HidCmd::SetSidestickVariant(SidestickVariant::Fighter));
+    r.clock.advance(SEND_INTERVAL);
+    r.session.tick();
+
+    let frames = r.hid.take_frames();
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].bytes[1], 0x09);
+    assert_eq!(frames[0].bytes[8], 30);
+}
+
+#[test]
+fn stop_all_forces_zero_without_waiting() {
+    let mut r = rig();
+    r.session.handle(HidCmd::SendIntensity(70));
+    r.session.tick();
+    r.hid.take_frames();
+
+    r.session.handle(HidCmd::StopAll);
+    r.session.tick();
+    assert_eq!(intensities(&r.hid), vec![0]);
+}
+
+#[test]
+fn device_change_event_triggers_immediate_rescan() {
+    let mut r = rig();
+    r.session.tick();
+    assert_eq!(r.hid.enumerations(), 1);
+
+    r.clock.advance(Duration::from_millis(500));
+    r.session.tick();
+    assert_eq!(r.hid.enumerations(), 1, "no polling while connected");
+
+    r.hid.plug(
+        MockHid::sidestick_info("stick-r", WW_PID_URSA_MINOR_AIRBUS_R),
+        None,
+    );
+    r.session.tick();
+    assert_eq!(r.session.device_count(), 2);
+
+    r.hid.unplug("stick-l");
+    r.hid.unplug("stick-r");
+    r.session.tick();
+    assert_eq!(r.session.device_count(), 0);
+    assert!(!r.controller.is_connected());
+}
+
+#[test]
+fn idle_rescan_covers_backends_without_notifications() {
+    let mut r = rig();
+    r.hid.set_hotplug_events(false);
+    r.session.tick();
+
+    r.hid.plug(
+        MockHid::sidestick_info("stick-r", WW_PID_URSA_MINOR_AIRBUS_R),
+        None,
+    );
+    r.clock
+        .advance(IDLE_RESCAN_INTERVAL - Duration::from_millis(1));
+    r.session.tick();
+    assert_eq!(r.session.device_count(), 1);
+
+    r.clock.advance(Duration::from_millis(1));
+    r.session.tick();
+    assert_eq!(r.session.device_count(), 2);
+}
+
+#[test]
+fn reconnect_attempts_back_off_exponentially() {
+    let clock = ManualClock::new();
+    let hid = MockHid::new(clock.clone());
+    hid.set_hotplug_events(false);
+    let mut session = HidSession::new(
+        hid.backend(),
+        clock.clone(),
+        ControllerShared::default(),
+        LogBuffer::default(),
+    );
+
+    let mut gaps = Vec::new();
+    session.tick();
+    for _ in 0..8 {
+        let wait = session.next_scan().unwrap() - clock.now();
+        gaps.push(wait);
+        clock.advance(wait);
+        session.tick();
+    }
+    assert_eq!(gaps[0], RECONNECT_BACKOFF_MIN);
+    assert_eq!(gaps[1], RECONNECT_BACKOFF_MIN * 2);
+    assert_eq!(*gaps.last().unwrap(), RECONNECT_BACKOFF_MAX);
+    assert_eq!(hid.enumerations(), 9);
+
+    hid.plug(
+        MockHid::sidestick_info("stick-l", WW_PID_URSA_MINOR_AIRBUS_L),
+        None,
+    );
+    session.handle(HidCmd::ReopenDevices);
+    assert_eq!(session.device_count(), 1);
+}
+
+#[test]
+fn failing_handle_is_dropped_and_reopened() {
+    let mut r = rig();
+    let (tx, rx) = unbounded();
+    r.session.set_event_sink(tx);
+    r.session.tick();
+    r.hid.take_frames();
+    assert!(matches!(rx.try_recv(), Ok(HidEvent::Connected { .. })));
+
+    r.hid.set_write_failure("stick-l", true);
+    for level in 1..=MAX_CONSECUTIVE_WRITE_FAILURES as u8 {
+        r.session.handle(HidCmd::SendIntensity(level * 10));
+        r.clock.advance(SEND_INTERVAL);
+        r.session.tick();
+    }
+    assert_eq!(r.session.device_count(), 0);
+    assert!(!r.controller.is_connected());
+    match rx.try_recv() {
+        Ok(HidEvent::Disconnected { path, reason, .. }) => {
+            assert_eq!(path, "stick-l");
+            assert_eq!(reason, "mock write failure");
+        }
+        other => panic!("expected Disconnected, got {other:?}"),
+    }
+
+    r.hid.set_write_failure("stick-l", false);
+    r.clock.advance(RECONNECT_BACKOFF_MIN);
+    r.session.tick();
+    assert_eq!(r.session.device_count(), 1);
+    assert!(matches!(rx.try_recv(), Ok(HidEvent::Connected { .. })));
+    assert_eq!(
+        intensities(&r.hid),
+        vec![MAX_CONSECUTIVE_WRITE_FAILURES as u8 * 10],
+        "current intensity is resent to the new handle"
+    );
+}
+
+#[test]
+fn connection_events_come_once_per_physical_device() {
+    let mut r = rig();
+    let (tx, rx) = unbounded();
+    r.session.set_event_sink(tx);
+    r.hid.plug(
+        HidDeviceInfo {
+            path: "stick-l-if1".into(),
+            interface_number: 1,
+            usage_page: 0xFF00,
+            usage: 0x0001,
+            ..MockHid::sidestick_info("stick-l", WW_PID_URSA_MINOR_AIRBUS_L)
+        },
+        Some((14, 0x02)),
+    );
+    r.session.tick();
+    assert_eq!(r.session.device_count(), 2, "both interfaces are open");
+    let connected: Vec<HidEvent> = rx.try_iter().collect();
+    assert!(
+        matches!(connected.as_slice(), [HidEvent::Connected { .. }]),
+        "{connected:?}"
+    );
+
+    r.hid.unplug("stick-l-if1");
+    r.session.tick();
+    assert_eq!(r.session.device_count(), 1);
+    assert!(rx.try_recv().is_err(), "the stick is still there");
+
+    r.hid.unplug("stick-l");
+    r.session.tick();
+    assert!(matches!(rx.try_recv(), Ok(HidEvent::Disconnected { .. })));
+    assert!(rx.try_recv().is_err());
+}
+
+#[test]
+fn health_counts_writes_per_device() {
+    let mut r = rig();
+    r.session.tick();
+    r.hid.set_write_failure("stick-l", true);
+    r.session.handle(HidCmd::SendIntensity(50));
+    r.clock.advance(SEND_INTERVAL);
+    r.session.tick();
+
+    let devices = r.controller.devices();
+    assert_eq!(devices.len(), 1);
+    assert_eq!(devices[0].path, "stick-l");
+    assert_eq!(devices[0].writes_ok, 1);
+    assert_eq!(devices[0].writes_failed, 1);
+    assert_eq!(devices[0].last_error.as_deref(), Some("mock write failure"));
+}
+
+#[test]
+fn raw_frames_reach_every_open_interface() {
+    let mut r = rig();
+    r.session.tick();
+    r.hid.take_frames();
+
+    r.session.handle(HidCmd::SendRaw(vec![0x02, 0xAA, 0xBB]));
+    let frames = r.hid.take_frames();
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].bytes, vec![0x02, 0xAA, 0xBB]);
+}
+
+#[test]
+fn registry_override_drives_frames_for_new_device() {
+    let clock = ManualClock::new();
+    let hid = MockHid::new(clock.clone());
+    hid.plug(
+        HidDeviceInfo {
+            path: "orion".into(),
+            vendor_id: WW_VID,
+            product_id: 0xBE01,
+            interface_number: 2,
+            usage_page: 0xFF00,
+            usage: 0x0001,
+            serial: "orion-1".into(),
+        },
+        Some((6, 0x01)),
+    );
+    let registry = DeviceRegistry::bundled()
+        .with_override_yaml(
+            r#"
+frame_templates:
+  orion:
+    min_len: 6
+    body: [channel, 0x10, intensity]
+devices:
+  - { vid: 0x4098, pid: 0xBE01, model: "ORION 2 BASE", channel: 0x21,
+      vibe_usage_page: 0xFF00, vibe_usage: 0x0001, frame: orion }
+"#,
+        )
+        .unwrap();
+
+    let controller = ControllerShared::default();
+    let mut session = HidSession::new(
+        hid.backend(),
+        clock.clone(),
+        controller.clone(),
+        LogBuffer::default(),
+    );
+    session.set_registry(Arc::new(registry));
+    session.handle(HidCmd::SendIntensity(77));
+    session.tick();
+
+    assert!(controller.is_connected());
+    assert_eq!(controller.devices()[0].model, "ORION 2 BASE");
+    assert_eq!(
+        hid.take_frames()[0].bytes,
+        vec![0x01, 0x21, 0x10, 77, 0x00, 0x00]
+    );
+}
+
+#[test]
+fn lab_commands_target_one_interface_and_log_traffic() {
+    let mut r = rig();
+    r.hid.plug(
+        MockHid::sidestick_info("stick-r", WW_PID_URSA_MINOR_AIRBUS_R),
+        None,
+    );
+    let lab = LabShared::default();
+    r.session.set_lab_log(lab.clone());
+    r.session.tick();
+    r.hid.take_frames();
+
+    r.session.handle(HidCmd::SendRawTo {
+        path: "stick-r".into(),
+        bytes: vec![0x02, 0x08, 0x42],
+    });
+    let frames = r.hid.take_frames();
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].path, "stick-r");
+
+    r.hid.push_input_report("stick-r", vec![0x01, 0x80, 0x7F]);
+    r.session.handle(HidCmd::ReadReport {
+        path: "stick-r".into(),
+        kind: ReportKind::Input,
+        report_id: 0,
+        len: 8,
+    });
+    r.session.handle(HidCmd::SendRawTo {
+        path: "gone".into(),
+        bytes: vec![0x00],
+    });
+
+    let log = lab.lock();
+    let entries = log.entries();
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0].direction, Direction::Sent);
+    assert_eq!(entries[1].direction, Direction::Received(ReportKind::Input));
+    assert_eq!(entries[1].bytes, vec![0x01, 0x80, 0x7F]);
+    assert_eq!(entries[2].error.as_deref(), Some("interface not open"));
+}
+
+#[test]
+fn lab_input_read_waits_without_blocking_the_session() {
+    let mut r = rig();
+    let lab = LabShared::default();
+    r.session.set_lab_log(lab.clone());
+    r.session.tick();
+    r.hid.take_frames();
+
+    let read = || HidCmd::ReadReport {
+        path: "stick-l".into(),
+        kind: ReportKind::Input,
+        report_id: 0,
+        len: 8,
+    };
+    r.session.handle(read());
+    let asked = r.clock.now();
+    r.session.tick();
+    assert!(lab.lock().entries().is_empty(), "still waiting");
+    assert_eq!(r.clock.now(), asked, "the read did not block");
+
+    r.hid.push_input_report("stick-l", vec![0x01, 0x02]);
+    r.session.handle(HidCmd::SendIntensity(40));
+    r.clock.advance(SEND_INTERVAL);
+    r.session.tick();
+    assert_eq!(intensities(&r.hid), vec![40]);
+    assert_eq!(lab.lock().entries()[0].bytes, vec![0x01, 0x02]);
+
+    r.session.handle(read());
+    r.clock.advance(lab::DEFAULT_READ_TIMEOUT);
+    r.session.tick();
+    let log = lab.lock();
+    assert_eq!(log.entries().len(), 2);
+    assert_eq!(
+        log.entries()[1].error.as_deref(),
+        Some("no input report within 250 ms")
+    );
+}
+
+/// The expected URSA MINOR input layout, which the bundled registry leaves off until verified.
+fn with_input_layout(r: &mut Rig) {
+    let registry = DeviceRegistry::bundled()
+        .with_override_yaml(
+            r#"
+devices:
+  - { vid: 0x4098, pid: 0xBC27, model: "URSA MINOR {variant} L", hand: left,
+      channel: { airbus: 0x07, fighter: 0x09, space: 0x0B },
+      vibe_usage_page: 0x0001, vibe_usage: 0x0004, frame: ursa_simapp,
+      input: { report_id: 0x01,
+               x: { offset: 1, min: 0, max: 65535 },
+               y: { offset: 3, min: 0, max: 65535 },
+               buttons: { offset: 13, count: 32 } } }
+"#,
+        )
+        .unwrap();
+    r.session.set_registry(Arc::new(registry));
+}
+
+fn ursa_input_report(x: u16, y: u16, buttons: u32) -> Vec<u8> {
+    let mut r = vec![0u8; 17];
+    r[0] = 0x01;
+    r[1..3].copy_from_slice(&x.to_le_bytes());
+    r[3..5].copy_from_slice(&y.to_le_bytes());
+    r[13..17].copy_from_slice(&buttons.to_le_bytes());
+    r
+}
+
+#[test]
+fn input_reports_publish_stick_state_and_hands_off() {
+    let mut r = rig();
+    with_input_layout(&mut r);
+    let input = InputShared::default();
+    r.session.set_input_sink(input.clone());
+    r.session.tick();
+    assert_eq!(*input.lock(), None, "no reports yet");
+
+    r.hid
+        .push_input_report("stick-l", ursa_input_report(65535, 32768, 0b10));
+    r.clock.advance(SEND_INTERVAL);
+    r.session.tick();
+    let s = input.lock().unwrap();
+    assert!((s.x - 1.0).abs() < 1e-3);
+    assert!(s.y.abs() < 1e-3);
+    assert!(s.is_pressed(1));
+    assert!(s.hand_on);
+
+    r.hid
+        .push_input_report("stick-l", ursa_input_report(32768, 32768, 0));
+    r.clock.advance(SEND_INTERVAL);
+    r.session.tick();
+    assert!(input.lock().unwrap().hand_on);
+
+    r.clock.advance(HANDS_OFF_AFTER);
+    r.session.tick();
+    assert!(!input.lock().unwrap().hand_on, "centered and still");
+}
+
+#[test]
+fn a_tick_drains_every_queued_input_report() {
+    let mut r = rig();
+    with_input_layout(&mut r);
+    let input = InputShared::default();
+    r.session.set_input_sink(input.clone());
+    r.session.tick();
+
+    for x in 0..40u16 {
+        r.hid
+            .push_input_report("stick-l", ursa_input_report(x * 1000, 32768, 0));
+    }
+    r.session.tick();
+    let x = input.lock().unwrap().x;
+    assert!((x - (39_000.0 / 65535.0 * 2.0 - 1.0)).abs() < 1e-3, "{x}");
+    r.hid
+        .push_input_report("stick-l", ursa_input_report(65535, 32768, 0));
+    r.session.tick();
+    assert!(
+        (input.lock().unwrap().x - 1.0).abs() < 1e-3,
+        "nothing left behind"
+    );
+}
+
+#[test]
+fn button_presses_emit_rising_edges_once() {
+    let mut r = rig();
+    with_input_layout(&mut r);
+    let (tx, rx) = unbounded();
+    r.session.set_event_sink(tx);
+    r.session.set_input_sink(InputShared::default());
+    r.session.tick();
+    let _ = rx.try_iter().count();
+
+    for buttons in [0b0100, 0b0100, 0b0101, 0] {
+        r.hid
+            .push_input_report("stick-l", ursa_input_report(32768, 32768, buttons));
+        r.clock.advance(SEND_INTERVAL);
+        r.session.tick();
+    }
+
+    let pressed: Vec<u8> = rx
+        .try_iter()
+        .filter_map(|e| match e {
+            HidEvent::ButtonPressed { path, button } => {
+                assert_eq!(path, "stick-l");
+                Some(button)
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(pressed, vec![2, 0]);
+}
+
+#[test]
+fn ack_pulse_plays_through_hold_then_restores_output() {
+    let mut r = rig();
+    r.session.tick();
+    r.session.handle(HidCmd::SetHold(true));
+    r.hid.take_frames();
+
+    r.session.handle(HidCmd::AckPulse);
+    r.session.tick();
+    assert_eq!(intensities(&r.hid), vec![ACK_PULSE_INTENSITY]);
+
+    r.clock.advance(ACK_PULSE_DURATION);
+    r.session.tick();
+    assert_eq!(intensities(&r.hid), vec![0], "back to held output");
+}
+
+fn clunk(max_output: u8) -> HidCmd {
+    let pattern = Pattern::parse(
+        "keyframes: [{at_ms: 0, level: 1}, {at_ms: 20, level: 0.5, curve: step}, {at_ms: 40, level: 0}]",
+    )
+    .unwrap();
+    HidCmd::PlayPattern(PatternPlay {
+        pattern: Arc::new(pattern),
+        peak: 100,
+        max_output,
+    })
+}
+
+#[test]
+fn patterns_play_at_their_own_rate_over_the_continuous_intensity() {
+    let mut r = rig();
+    r.session.handle(HidCmd::SendIntensity(20));
+    r.session.tick();
+    r.hid.take_frames();
+
+    r.session.handle(clunk(255));
+    assert!(r.session.is_playing_pattern());
+    for _ in 0..5 {
+        r.session.tick();
+        r.clock.advance(PATTERN_SEND_INTERVAL);
+    }
+    r.session.tick();
+    assert_eq!(intensities(&r.hid), vec![120, 70, 45, 20]);
+    assert!(!r.session.is_playing_pattern());
+
+    r.session.handle(clunk(60));
+    r.session.tick();
+    assert_eq!(
+        intensities(&r.hid),
+        vec![60],
+        "capped at the preset's max_output"
+    );
+}
+
+#[test]
+fn hold_and_stop_all_cancel_patterns() {
+    let mut r = rig();
+    r.session.tick();
+    r.hid.take_frames();
+
+    r.session.handle(clunk(255));
+    r.session.handle(HidCmd::StopAll);
+    r.session.tick();
+    assert!(!r.session.is_playing_pattern());
+    assert!(r.hid.take_frames().is_empty(), "zero was already sent");
+
+    r.session.handle(HidCmd::SetHold(true));
+    r.session.handle(clunk(255));
+    r.clock.advance(SEND_INTERVAL);
+    r.session.tick();
+    assert!(!r.session.is_playing_pattern());
+    assert_eq!(intensities(&r.hid), vec![0]);
+}
+
+#[test]
+fn send_rate_follows_the_setting_up_to_the_device_limit() {
+    let mut r = rig();
+    r.session.tick();
+    assert_eq!(r.session.send_hz(), DEFAULT_SEND_HZ);
+    r.session.handle(HidCmd::SetSendRate(100));
+    r.hid.take_frames();
+
+    r.session.handle(HidCmd::SendIntensity(10));
+    r.clock.advance(Duration::from_millis(10));
+    r.session.tick();
+    assert_eq!(intensities(&r.hid), vec![10], "100 Hz cadence");
+
+    let registry = DeviceRegistry::bundled()
+        .with_override_yaml(
+            r#"
+devices:
+  - { vid: 0x4098, pid: 0xBC27, model: "SLOW STICK", channel: 0x07,
+      vibe_usage_page: 0x0001, vibe_usage: 0x0004, frame: ursa_simapp, max_send_hz: 25 }
+"#,
+        )
+        .unwrap();
+    r.session.set_registry(Arc::new(registry));
+    assert_eq!(r.session.send_hz(), 25);
+    r.session.handle(HidCmd::SendIntensity(20));
+    r.clock.advance(Duration::from_millis(20));
+    r.session.tick();
+    assert!(r.hid.take_frames().is_empty(), "capped at 25 Hz");
+    r.clock.advance(Duration::from_millis(20));
+    r.session.tick();
+    assert_eq!(intensities(&r.hid), vec![20]);
+}
+
+#[test]
+fn large_changes_skip_the_cadence_and_unchanged_output_keeps_alive() {
+    let mut r = rig();
+    r.session.tick();
+    r.hid.take_frames();
+
+    r.clock.advance(Duration::from_millis(10));
+    r.session.handle(HidCmd::SendIntensity(5));
+    r.session.tick();
+    assert!(r.hid.take_frames().is_empty(), "small change waits");
+    r.session.handle(HidCmd::SendIntensity(200));
+    r.session.tick();
+    assert_eq!(intensities(&r.hid), vec![200], "jump goes out at once");
+
+    // The sim keeps streaming the same value.
+    let mut elapsed = Duration::ZERO;
+    while elapsed < KEEPALIVE_INTERVAL {
+        assert!(r.hid.take_frames().is_empty(), "unchanged");
+        r.clock.advance(SEND_INTERVAL);
+        elapsed += SEND_INTERVAL;
+        r.session.handle(HidCmd::SendIntensity(200));
+        r.session.tick();
+    }
+    assert_eq!(intensities(&r.hid), vec![200], "keep-alive");
+}
+
+#[test]
+fn health_reports_write_latency_and_send_rate() {
+    let mut r = rig();
+    r.hid
+        .set_write_latency("stick-l", Duration::from_micros(1500));
+    r.session.tick();
+    for level in 1..=5 {
+        r.clock.advance(SEND_INTERVAL);
+        r.session.handle(HidCmd::SendIntensity(level));
+        r.session.tick();
+    }
+
+    let health = &r.controller.devices()[0];
+    assert_eq!(health.write_latency, Duration::from_micros(1500));
+    assert_eq!(health.write_latency_max, Duration::from_micros(1500));
+    let gap = SEND_INTERVAL + Duration::from_micros(1500);
+    assert_eq!(health.send_interval, gap);
+    assert_eq!(health.failure_pct(), 0.0);
+    assert!(health
+        .metrics_summary()
+        .starts_with("47 Hz, write 1.50 ms avg"));
+}
+
+#[test]
+fn watchdog_fades_a_stale_intensity_and_flags_the_stall() {
+    let mut r = rig();
+    r.session.handle(HidCmd::SendIntensity(160));
+    r.session.tick();
+    r.hid.take_frames();
+
+    r.clock.advance(DEFAULT_WATCHDOG_TIMEOUT);
+    r.session.tick();
+    assert!(r.hid.take_frames().is_empty(), "still within the timeout");
+    assert!(!r.controller.is_output_stalled());
+
+    r.clock.advance(WATCHDOG_FADE / 2);
+    r.session.tick();
+    assert_eq!(intensities(&r.hid), vec![80], "half way down");
+    assert!(r.controller.is_output_stalled());
+    r.clock.advance(WATCHDOG_FADE / 2);
+    r.session.tick();
+    assert_eq!(intensities(&r.hid), vec![0]);
+
+    r.session.handle(HidCmd::SendIntensity(90));
+    r.clock.advance(SEND_INTERVAL);
+    r.session.tick();
+    assert_eq!(
+        intensities(&r.hid),
+        vec![90],
+        "fresh intensity resumes output"
+    );
+    assert!(!r.controller.is_output_stalled());
+
+    r.session
+        .handle(HidCmd::SetWatchdogTimeout(Duration::from_secs(2)));
+    r.clock.advance(Duration::from_secs(1));
+    r.session.tick();
+    assert_eq!(intensities(&r.hid), vec![90], "keep-alive, not faded");
+}
+
+/// Stream `level` for `secs` seconds at the default cadence; returns the frames sent.
+fn stream(r: &mut Rig, level: u8, secs: u64) -> Vec<u8> {
+    let mut sent = Vec::new();
+    for _ in 0..secs * u64::from(DEFAULT_SEND_HZ) {
+        r.session.handle(HidCmd::SendIntensity(level));
+        r.session.tick();
+        sent.extend(intensities(&r.hid));
+        r.clock.advance(SEND_INTERVAL);
+    }
+    sent
+}
+
+#[test]
+fn duty_limiter_scales_sustained_output_and_lets_transients_through() {
+    let mut r = rig();
+    r.session.handle(HidCmd::SetDutyLimits(DutyLimits {
+        window_s: 10,
+        ..Default::default()
+    }));
+    r.session.tick();
+    r.hid.take_frames();
+
+    assert_eq!(stream(&mut r, 255, 1), vec![255], "a short burst passes");
+    assert_eq!(r.controller.motor_limit_pct(), None);
+
+    let sent = stream(&mut r, 255, 9);
+    let last = *sent.last().unwrap();
+    let pct = r.controller.motor_limit_pct().expect("limiting");
+    assert!(last < 200, "sustained output scaled down to {last}");
+    assert!(u32::from(last).abs_diff(255 * u32::from(pct) / 100) <= 3);
+
+    stream(&mut r, 0, 10);
+    assert_eq!(
+        r.controller.motor_limit_pct(),
+        None,
+        "released after a rest"
+    );
+    assert_eq!(stream(&mut r, 255, 1), vec![255]);
+}
+
+#[test]
+fn shutdown_zeroes_the_motor_and_ends_the_run_loop() {
+    let mut r = rig();
+    r.session.handle(HidCmd::SendIntensity(200));
+    r.session.tick();
+    r.hid.take_frames();
+
+    let (tx, rx) = unbounded();
+    let (reply, stopped) = unbounded();
+    tx.send(HidCmd::Shutdown(reply)).unwrap();
+    tx.send(HidCmd::SendIntensity(150)).unwrap();
+    r.session.run(&rx);
+    assert_eq!(stopped.try_recv(), Ok(1));
+    assert_eq!(
+        intensities(&r.hid),
+        vec![0],
+        "returned without another frame"
+    );
+
+    r.session.tick();
+    drop(r.session);
+    assert!(r.hid.take_frames().is_empty(), "nothing after the stop");
+}
+
+#[test]
+fn dropping_a_running_session_zeroes_the_motor() {
+    let mut r = rig();
+    r.session.handle(HidCmd::SendIntensity(180));
+    r.session.tick();
+    r.hid.take_frames();
+
+    drop(r.session);
+    assert_eq!(intensities(&r.hid), vec![0]);
+}
+
+/// Sends within `window` when `run` gets a ramp of `SendIntensity` at `cmd_hz` for that long.
+fn sends_for_stream(rate: Option<u32>, cmd_hz: u32, window: Duration) -> usize {
+    let mut r = rig();
+    if let Some(hz) = rate {
+        r.clock.schedule(Duration::ZERO, HidCmd::SetSendRate(hz));
+    }
+    let n = cmd_hz * window.as_millis() as u32 / 1000;
+    for i in 0..n {
+        r.clock
+            .schedule(window * i / n, HidCmd::SendIntensity(10 + (i % 200) as u8));
+    }
+    let (tx, rx) = unbounded();
+    drop(tx);
+    r.session.run(&rx);
+    r.hid
+        .take_frames()
+        .iter()
+        .filter(|f| f.bytes[8] > 0 && f.at < window)
+        .count()
+}
+
+#[test]
+fn run_sends_at_the_configured_rate_not_the_command_rate() {
+    let window = Duration::from_secs(2);
+    let sent = sends_for_stream(None, 60, window);
+    assert!((99..=101).contains(&sent), "60 Hz sim at 50 Hz: {sent}");
+    let sent = sends_for_stream(Some(100), 250, window);
+    assert!(
+        (199..=201).contains(&sent),
+        "250 Hz stream at 100 Hz: {sent}"
+    );
+    let sent = sends_for_stream(Some(250), 250, window);
+    assert!((199..=201).contains(&sent), "capped by the device: {sent}");
+}
@@ -1,8 +1,15 @@
 use std::fs;
+use std::time::Duration;
 
-use ursa_minor_ffb::preset::{PresetKind, PresetStore};
+use ursa_minor_ffb::bindings::{Action, ButtonBinding, Effect};
+use ursa_minor_ffb::preset::{
+    load_aircraft_overrides, spawn_watcher, AircraftOverride, FileTarget, Location, PresetError,
+    PresetId, PresetKind, PresetPackage, PresetShared, PresetStore, RumbleIssue, SimVarIssue,
+    BACKUP_HISTORY,
+};
+use ursa_minor_ffb::rumble::wave::Shape;
 use ursa_minor_ffb::sim::parse::parse_main_elems;
-use ursa_minor_ffb::SimVarLayout;
+use ursa_minor_ffb::{LogBuffer, SimVarLayout, UiCmd};
 
 #[test]
 fn preset_store_bootstrap_creates_settings_only() {
@@ -22,7 +29,7 @@ fn preset_store_bootstrap_creates_settings_only() {
             "bootstrap should not create {}",
             path.display()
         );
-        let preset = store.load(kind);
+        let preset = store.load(kind).unwrap();
         assert_eq!(preset.kind, kind);
         assert_eq!(preset, kind.built_in_default());
     }
@@ -40,7 +47,7 @@ fn commercial_yaml_save_contains_rumble_only() {
     store.bootstrap().unwrap();
     store.save(&preset).unwrap();
 
-    let loaded = store.load(PresetKind::Commercial);
+    let loaded = store.load(PresetKind::Commercial).unwrap();
     let yaml = fs::read_to_string(dir.join("commercial.yml")).unwrap();
     assert!(!yaml.contains("simvars:"));
     assert_eq!(loaded.rumble, preset.rumble);
@@ -81,7 +88,7 @@ fn preset_store_save_and_load_overrides_code_defaults() {
     let text = fs::read_to_string(dir.join("commercial.yml")).unwrap();
     assert!(!text.contains("simvars:"));
 
-    let loaded = store.load(PresetKind::Commercial);
+    let loaded = store.load(PresetKind::Commercial).unwrap();
     assert_eq!(loaded.kind, PresetKind::Commercial);
     assert_eq!(loaded.rumble.base_airspeed, 99.0);
 
@@ -93,3 +100,834 @@ fn core_only_layout_has_eight_fields() {
     let layout = SimVarLayout::core_only();
     assert_eq!(layout.total_count(), 8);
 }
+
+#[test]
+fn settings_with_a_bad_button_binding_are_reported_and_never_rewritten() {
+    let dir = std::env::temp_dir().join(format!("ursa-bindings-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("settings.yml"),
+        "active: fighter\nmaster_gain_pct: 70\nbutton_bindings:\n  - { button: 2, action: toggle_hold }\n  - { button: 3, action: warp_drive }\n  - { button: 5, action: { mute: engine } }\n",
+    )
+    .unwrap();
+
+    let store = PresetStore::new(dir.clone());
+    let err = store.load_settings().unwrap_err();
+    assert!(matches!(err, PresetError::Syntax { .. }), "{err}");
+    assert!(err.to_string().contains("warp_drive"), "{err}");
+    assert_eq!(err.location().map(|l| l.line), Some(5));
+    let before = fs::read_to_string(dir.join("settings.yml")).unwrap();
+    assert!(store.update_settings(|s| s.master_gain_pct = 40).is_err());
+    assert_eq!(
+        fs::read_to_string(dir.join("settings.yml")).unwrap(),
+        before,
+        "a gain press must not erase the bindings"
+    );
+
+    fs::write(
+        dir.join("settings.yml"),
+        before.replace("warp_drive", "gain_up"),
+    )
+    .unwrap();
+    let settings = store.load_settings().unwrap();
+    assert_eq!(settings.active, PresetId::BuiltIn(PresetKind::Fighter));
+    assert_eq!(settings.master_gain_pct, 70);
+    assert_eq!(
+        settings.button_bindings,
+        vec![
+            ButtonBinding {
+                button: 2,
+                action: Action::ToggleHold,
+            },
+            ButtonBinding {
+                button: 3,
+                action: Action::GainUp,
+            },
+            ButtonBinding {
+                button: 5,
+                action: Action::Mute(Effect::Engine),
+            },
+        ]
+    );
+
+    store.save_settings(&settings).unwrap();
+    assert_eq!(store.load_settings().unwrap(), settings);
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn user_presets_create_duplicate_rename_delete() {
+    let dir = std::env::temp_dir().join(format!("ursa-user-presets-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let store = PresetStore::new(dir.clone());
+    store.bootstrap().unwrap();
+
+    let mut rumble = PresetKind::Commercial.built_in_default().rumble;
+    rumble.ground_roll = 71.0;
+    let pmdg = store
+        .create_user("PMDG 737", PresetKind::Commercial, rumble)
+        .unwrap();
+    assert_eq!(pmdg.id, PresetId::User("pmdg-737".to_string()));
+    assert_eq!(
+        pmdg.simvars,
+        PresetKind::Commercial.built_in_default().simvars
+    );
+    assert!(
+        store
+            .create_user("pmdg 737", PresetKind::Fighter, pmdg.rumble.clone())
+            .is_err(),
+        "names are unique regardless of case"
+    );
+    assert!(store
+        .create_user("Commercial", PresetKind::Fighter, pmdg.rumble.clone())
+        .is_err());
+
+    let fenix = store.duplicate(&pmdg, "Fenix A320").unwrap();
+    assert_eq!(store.load_id(&fenix.id).unwrap().rumble.ground_roll, 71.0);
+
+    let names: Vec<String> = store.all_presets().into_iter().map(|p| p.name).collect();
+    assert_eq!(
+        names,
+        [
+            "General Aviation",
+            "Commercial",
+            "Fighter",
+            "Fenix A320",
+            "PMDG 737"
+        ]
+    );
+    assert_eq!(
+        store.next_preset(&pmdg.id),
+        PresetId::BuiltIn(PresetKind::GeneralAviation)
+    );
+
+    store.save_active(fenix.id.clone()).unwrap();
+    let renamed = store.rename_user(&fenix.id, "Fenix A321").unwrap();
+    assert_eq!(renamed.id, PresetId::User("fenix-a321".to_string()));
+    assert!(!dir.join("user").join("fenix-a320.yml").exists());
+    assert_eq!(store.load_active(), renamed.id, "active follows the rename");
+    assert_eq!(store.load_id(&renamed.id).unwrap().name, "Fenix A321");
+
+    let fallback = store.delete_user(&renamed.id).unwrap();
+    assert_eq!(fallback, PresetId::BuiltIn(PresetKind::Commercial));
+    assert_eq!(store.load_active(), fallback);
+    assert_eq!(store.user_presets().len(), 1);
+    assert!(store.delete_user(&fallback).is_err());
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn preset_files_store_only_overrides_and_pick_up_new_defaults() {
+    let dir = std::env::temp_dir().join(format!("ursa-sparse-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let store = PresetStore::new(dir.clone());
+    store.bootstrap().unwrap();
+
+    let mut preset = PresetKind::Commercial.built_in_default();
+    preset.rumble.base_airspeed = 33.0;
+    store.save(&preset).unwrap();
+    let yaml = fs::read_to_string(dir.join("commercial.yml")).unwrap();
+    assert!(yaml.contains("base_airspeed: 33"), "{yaml}");
+    assert!(!yaml.contains("ground_roll"), "{yaml}");
+
+    fs::write(
+        dir.join("fighter.yml"),
+        "kind: fighter\nrumble:\n  ground_roll: 5.0\n",
+    )
+    .unwrap();
+    let default = PresetKind::Fighter.built_in_default().rumble;
+    let loaded = store.load(PresetKind::Fighter).unwrap().rumble;
+    assert_eq!(loaded.ground_roll, 5.0);
+    assert_eq!(loaded.base_airspeed, default.base_airspeed);
+
+    // Pre-inheritance user files named the family and held every slider.
+    fs::create_dir_all(dir.join("user")).unwrap();
+    let mut full = PresetKind::GeneralAviation.built_in_default().rumble;
+    full.stall_ceiling = 12.0;
+    let legacy = format!(
+        "name: Cub\nfamily: general_aviation\nrumble:\n{}",
+        serde_yaml::to_string(&full)
+            .unwrap()
+            .lines()
+            .map(|l| format!("  {l}\n"))
+            .collect::<String>()
+    );
+    fs::write(dir.join("user").join("cub.yml"), legacy).unwrap();
+    let cub = store.load_id(&PresetId::User("cub".to_string())).unwrap();
+    assert_eq!(cub.kind, PresetKind::GeneralAviation);
+    assert_eq!(cub.rumble, full);
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn user_preset_chain_follows_parents_and_survives_parent_delete() {
+    let dir = std::env::temp_dir().join(format!("ursa-extends-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let store = PresetStore::new(dir.clone());
+    store.bootstrap().unwrap();
+
+    let mut rumble = PresetKind::Commercial.built_in_default().rumble;
+    rumble.ground_roll = 71.0;
+    let airliner = store
+        .create_user("Airliner", PresetKind::Commercial, rumble.clone())
+        .unwrap();
+    rumble.stall_ceiling = 9.0;
+    let a320 = store
+        .create_user("A320", airliner.id.clone(), rumble)
+        .unwrap();
+    let text = fs::read_to_string(dir.join("user").join("a320.yml")).unwrap();
+    assert!(text.contains("extends: user:airliner"), "{text}");
+    assert!(!text.contains("ground_roll"), "{text}");
+
+    // Parent edits flow down to children that do not override them.
+    let mut edited = store.load_id(&airliner.id).unwrap();
+    edited.rumble.ground_roll = 40.0;
+    edited.rumble.base_airspeed = 3.0;
+    store.save(&edited).unwrap();
+    let child = store.load_id(&a320.id).unwrap();
+    assert_eq!(child.rumble.ground_roll, 40.0);
+    assert_eq!(child.rumble.stall_ceiling, 9.0);
+    assert_eq!(child.kind, PresetKind::Commercial);
+
+    // Renaming the parent keeps the child attached.
+    let renamed = store.rename_user(&airliner.id, "Jets").unwrap();
+    assert_eq!(store.load_id(&a320.id).unwrap().rumble.ground_roll, 40.0);
+
+    // Deleting it bakes its values into the child.
+    store.delete_user(&renamed.id).unwrap();
+    let child = store.load_id(&a320.id).unwrap();
+    assert_eq!(
+        child.extends,
+        Some(PresetId::BuiltIn(PresetKind::Commercial))
+    );
+    assert_eq!(child.rumble.ground_roll, 40.0);
+    assert_eq!(child.rumble.base_airspeed, 3.0);
+    assert_eq!(child.rumble.stall_ceiling, 9.0);
+
+    // Hand-edited cycles fall back instead of looping.
+    fs::write(
+        dir.join("user").join("loop.yml"),
+        "name: Loop\nextends: user:loop\nrumble: {}\n",
+    )
+    .unwrap();
+    assert_eq!(
+        store
+            .load_id(&PresetId::User("loop".to_string()))
+            .unwrap()
+            .kind,
+        PresetKind::default()
+    );
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn aircraft_override_sits_between_preset_and_session_tweaks() {
+    let preset = PresetKind::Commercial.built_in_default();
+    let shared = PresetShared::new(preset.clone());
+    let table: Vec<AircraftOverride> = serde_yaml::from_str(
+        "- title_contains: PMDG 737\n  rumble: { ground_roll: 60.0, stall_ceiling: 8.0 }\n",
+    )
+    .unwrap();
+    shared.set_aircraft_overrides(table);
+
+    shared.set_aircraft_title("Fenix A320");
+    assert_eq!(shared.rumble_config(), preset.rumble);
+
+    shared.set_aircraft_title("PMDG 737-800 BBJ");
+    assert_eq!(shared.rumble_config().ground_roll, 60.0);
+    assert_eq!(shared.rumble_config().stall_ceiling, 8.0);
+
+    // An unsaved slider tweak wins over the aircraft override; untouched fields keep it.
+    shared.with_mut_rumble(|r, kind| {
+        r.ground_roll = 20.0;
+        kind
+    });
+    assert_eq!(shared.rumble_config().ground_roll, 20.0);
+    assert_eq!(shared.rumble_config().stall_ceiling, 8.0);
+
+    shared.set_aircraft_title("");
+    assert_eq!(shared.rumble_config().ground_roll, 20.0);
+    assert_eq!(
+        shared.rumble_config().stall_ceiling,
+        preset.rumble.stall_ceiling
+    );
+}
+
+#[test]
+fn legacy_files_are_migrated_with_backup_and_report() {
+    let dir = std::env::temp_dir().join(format!("ursa-migrate-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let legacy_settings = "active: custom\nmaster_gain_pct: 70\n";
+    fs::write(dir.join("settings.yml"), legacy_settings).unwrap();
+    let legacy_preset =
+        "kind: commercial\nsimvars:\n  extra: []\nrumble:\n  ground_spoilers: 12.0\n";
+    fs::write(dir.join("commercial.yml"), legacy_preset).unwrap();
+    let store = PresetStore::new(dir.clone());
+
+    let settings = store.load_settings().unwrap();
+    assert_eq!(settings.active, PresetId::BuiltIn(PresetKind::Commercial));
+    assert_eq!(settings.master_gain_pct, 70);
+    let preset = store.load(PresetKind::Commercial).unwrap();
+    assert_eq!(preset.rumble.spoilers, 12.0);
+
+    assert_eq!(
+        fs::read_to_string(dir.join("settings.v0.bak")).unwrap(),
+        legacy_settings
+    );
+    assert_eq!(
+        fs::read_to_string(dir.join("commercial.v0.bak")).unwrap(),
+        legacy_preset
+    );
+    let rewritten = fs::read_to_string(dir.join("commercial.yml")).unwrap();
+    assert!(rewritten.starts_with("schema_version: 3\n"), "{rewritten}");
+    assert!(rewritten.contains("spoilers: 12.0"), "{rewritten}");
+    assert!(fs::read_to_string(dir.join("settings.yml"))
+        .unwrap()
+        .starts_with("schema_version: 1\n"));
+
+    let notes = store.take_notes();
+    assert_eq!(notes.len(), 2, "{notes:?}");
+    assert!(
+        notes[1].contains("renamed ground_spoilers to spoilers"),
+        "{notes:?}"
+    );
+    assert!(notes[1].contains("commercial.v0.bak"), "{notes:?}");
+
+    // Already current: nothing more to report.
+    store.load_settings().unwrap();
+    store.load(PresetKind::Commercial).unwrap();
+    assert!(store.take_notes().is_empty());
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn a_legacy_file_that_fails_to_load_is_left_alone_until_fixed() {
+    let dir = std::env::temp_dir().join(format!("ursa-migrate-broken-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let broken = "kind: commercial\nrumble:\n  ground_spoilers: 12.0\n  smoothing_alpha: 4.0\n";
+    fs::write(dir.join("commercial.yml"), broken).unwrap();
+    let store = PresetStore::new(dir.clone());
+
+    for _ in 0..2 {
+        assert!(matches!(
+            store.load(PresetKind::Commercial),
+            Err(PresetError::Invalid { .. })
+        ));
+        assert!(
+            store.take_notes().is_empty(),
+            "no note for a file that failed"
+        );
+    }
+    assert!(!dir.join("commercial.v0.bak").exists());
+    assert_eq!(
+        fs::read_to_string(dir.join("commercial.yml")).unwrap(),
+        broken
+    );
+
+    let fixed = broken.replace("  smoothing_alpha: 4.0\n", "");
+    fs::write(dir.join("commercial.yml"), &fixed).unwrap();
+    assert_eq!(
+        store.load(PresetKind::Commercial).unwrap().rumble.spoilers,
+        12.0
+    );
+    assert_eq!(store.take_notes().len(), 1);
+    assert_eq!(
+        fs::read_to_string(dir.join("commercial.v0.bak")).unwrap(),
+        fixed
+    );
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn settings_writers_on_two_threads_keep_each_others_changes() {
+    let dir = std::env::temp_dir().join(format!("ursa-settings-race-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let store = PresetStore::new(dir.clone());
+    store.bootstrap().unwrap();
+
+    let buttons = store.clone();
+    let gains = std::thread::spawn(move || {
+        for gain in 0..50 {
+            buttons
+                .update_settings(|s| s.master_gain_pct = gain)
+                .unwrap();
+        }
+    });
+    for hz in 0..50 {
+        store.update_settings(|s| s.hid_send_hz = 200 + hz).unwrap();
+    }
+    gains.join().unwrap();
+
+    let settings = store.load_settings().unwrap();
+    assert_eq!(settings.master_gain_pct, 49);
+    assert_eq!(settings.hid_send_hz, 249);
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn legacy_zeroed_engine_rpms_take_the_family_defaults() {
+    let dir = std::env::temp_dir().join(format!("ursa-zero-rpm-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("user")).unwrap();
+    let zeroed = "  eng_rpm_spool_min: 0.0\n  eng_rpm_startup_max: 0.0\n  eng_rpm_idle: 0.0\n  eng_rpm_max: 0.0\n";
+    fs::write(
+        dir.join("commercial.yml"),
+        format!("schema_version: 3\nkind: commercial\nrumble:\n  spoilers: 12.0\n{zeroed}"),
+    )
+    .unwrap();
+    fs::write(
+        dir.join("user").join("cub.yml"),
+        format!("schema_version: 3\nname: Cub\nextends: general_aviation\nrumble:\n  ground_roll: 40.0\n{zeroed}"),
+    )
+    .unwrap();
+    let store = PresetStore::new(dir.clone());
+
+    let preset = store.load(PresetKind::Commercial).unwrap();
+    let family = PresetKind::Commercial.built_in_default().rumble;
+    assert_eq!(preset.rumble.spoilers, 12.0, "tuning is kept");
+    assert_eq!(preset.rumble.eng_rpm_spool_min, family.eng_rpm_spool_min);
+    assert_eq!(
+        preset.rumble.eng_rpm_startup_max,
+        family.eng_rpm_startup_max
+    );
+    assert_eq!(preset.rumble.eng_rpm_idle, family.eng_rpm_idle);
+    assert_eq!(preset.rumble.eng_rpm_max, family.eng_rpm_max);
+
+    let cub = store.load_id(&PresetId::User("cub".to_string())).unwrap();
+    let family = PresetKind::GeneralAviation.built_in_default().rumble;
+    assert_eq!(cub.rumble.ground_roll, 40.0, "tuning is kept");
+    assert_eq!(cub.rumble.eng_rpm_idle, family.eng_rpm_idle);
+    assert_eq!(cub.rumble.eng_rpm_max, family.eng_rpm_max);
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn broken_files_report_where_and_are_never_overwritten() {
+    let dir = std::env::temp_dir().join(format!("ursa-errors-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let store = PresetStore::new(dir.clone());
+    store.bootstrap().unwrap();
+
+    let typo = "schema_version: 3\nkind: fighter\nrumble:\n  ground_roll: [5\n";
+    fs::write(dir.join("fighter.yml"), typo).unwrap();
+    let err = store.load(PresetKind::Fighter).unwrap_err();
+    assert!(matches!(err, PresetError::Syntax { .. }), "{err}");
+    assert!(err.location().is_some(), "{err}");
+    assert!(err.to_string().contains("fighter.yml:"), "{err}");
+
+    let unknown = "schema_version: 3\nkind: fighter\nrumble:\n  groud_roll: 5\n";
+    fs::write(dir.join("fighter.yml"), unknown).unwrap();
+    let err = store.load(PresetKind::Fighter).unwrap_err();
+    assert!(matches!(
+        &err,
+        PresetError::Invalid { issue: RumbleIssue::UnknownKey(k), .. } if k == "groud_roll"
+    ));
+    assert_eq!(err.location(), Some(Location { line: 4, column: 3 }));
+
+    let inverted = "schema_version: 3\nkind: fighter\nrumble:\n  taxi_start_kn: 90.0\n";
+    fs::write(dir.join("fighter.yml"), inverted).unwrap();
+    let err = store.load(PresetKind::Fighter).unwrap_err();
+    assert!(matches!(
+        err,
+        PresetError::Invalid {
+            issue: RumbleIssue::TaxiRange { .. },
+            ..
+        }
+    ));
+    assert_eq!(
+        fs::read_to_string(dir.join("fighter.yml")).unwrap(),
+        inverted
+    );
+
+    let backup = store.revert_file(&dir.join("fighter.yml")).unwrap();
+    assert_eq!(fs::read_to_string(backup).unwrap(), inverted);
+    assert_eq!(
+        store.load(PresetKind::Fighter).unwrap(),
+        PresetKind::Fighter.built_in_default()
+    );
+
+    let newer = "kind: fighter\nschema_version: 4\nrumble:\n  ground_roll: 5\n";
+    fs::write(dir.join("fighter.yml"), newer).unwrap();
+    let err = store.load(PresetKind::Fighter).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            PresetError::NewerSchema {
+                found: 4,
+                supported: 3,
+                ..
+            }
+        ),
+        "{err}"
+    );
+    assert_eq!(err.location(), Some(Location { line: 2, column: 1 }));
+    assert_eq!(fs::read_to_string(dir.join("fighter.yml")).unwrap(), newer);
+
+    let settings = "schema_version: 1\nactive: fighter\nmaster_gain_pct: [\n";
+    fs::write(dir.join("settings.yml"), settings).unwrap();
+    assert!(store.load_settings().is_err());
+    assert!(store.save_active(PresetKind::Commercial).is_err());
+    assert_eq!(
+        fs::read_to_string(dir.join("settings.yml")).unwrap(),
+        settings
+    );
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn reverting_a_broken_user_preset_keeps_name_and_parent() {
+    let dir = std::env::temp_dir().join(format!("ursa-revert-user-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let store = PresetStore::new(dir.clone());
+    store.bootstrap().unwrap();
+
+    let path = dir.join("user").join("cub.yml");
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(
+        &path,
+        "schema_version: 3\nname: Cub\nextends: general_aviation\nrumble:\n  smoothing_alpha: 4.0\n",
+    )
+    .unwrap();
+    let id = PresetId::User("cub".to_string());
+    let err = store.load_id(&id).unwrap_err();
+    assert!(matches!(
+        err,
+        PresetError::Invalid {
+            issue: RumbleIssue::SmoothingAlpha(_),
+            ..
+        }
+    ));
+    assert_eq!(
+        store.user_presets()[0].name,
+        "Cub",
+        "broken presets stay listed"
+    );
+
+    store.revert_file(&path).unwrap();
+    let cub = store.load_id(&id).unwrap();
+    assert_eq!(cub.name, "Cub");
+    assert_eq!(cub.kind, PresetKind::GeneralAviation);
+    assert_eq!(
+        cub.rumble,
+        PresetKind::GeneralAviation.built_in_default().rumble
+    );
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn watcher_reports_edited_store_files_once() {
+    let dir = std::env::temp_dir().join(format!("ursa-watch-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let store = PresetStore::new(dir.clone());
+    store.bootstrap().unwrap();
+    fs::create_dir_all(dir.join("user")).unwrap();
+
+    let (tx, rx) = crossbeam_channel::unbounded();
+    spawn_watcher(dir.clone(), tx, LogBuffer::default()).unwrap();
+    fs::write(dir.join("fighter.yml"), "kind: fighter\n").unwrap();
+    fs::write(dir.join("fighter.yml"), "kind: fighter\nrumble: {}\n").unwrap();
+    fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+    let first = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert!(matches!(
+        first,
+        UiCmd::FileChanged(FileTarget::Preset(PresetId::BuiltIn(PresetKind::Fighter)))
+    ));
+    assert!(
+        rx.recv_timeout(Duration::from_millis(800)).is_err(),
+        "burst of writes is reported once"
+    );
+
+    fs::write(dir.join("user").join("cub.yml"), "name: Cub\n").unwrap();
+    let next = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert!(matches!(
+        next,
+        UiCmd::FileChanged(FileTarget::Preset(PresetId::User(ref s))) if s == "cub"
+    ));
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn saves_keep_a_rolling_history_that_can_be_restored() {
+    let dir = std::env::temp_dir().join(format!("ursa-backups-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let store = PresetStore::new(dir.clone());
+    store.bootstrap().unwrap();
+    let id = PresetId::BuiltIn(PresetKind::Fighter);
+
+    let mut preset = PresetKind::Fighter.built_in_default();
+    for i in 0..BACKUP_HISTORY + 2 {
+        preset.rumble.ground_roll = i as f32;
+        store.save(&preset).unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    let backups = store.backups(&id);
+    assert_eq!(backups.len(), BACKUP_HISTORY, "oldest versions are pruned");
+    assert!(backups.windows(2).all(|w| w[0].saved_at > w[1].saved_at));
+    assert!(
+        fs::read_dir(&dir)
+            .unwrap()
+            .all(|e| e.unwrap().path().extension() != Some("tmp".as_ref())),
+        "no temp files left behind"
+    );
+
+    let restored = store.restore_backup(&id, &backups[0]).unwrap();
+    assert_eq!(restored.rumble.ground_roll, BACKUP_HISTORY as f32);
+    assert_eq!(store.load_id(&id).unwrap(), restored);
+    std::thread::sleep(Duration::from_millis(5));
+
+    // A reset can be undone too: the file it removed is the newest backup.
+    store.reset(&id).unwrap();
+    let newest = store.backups(&id).remove(0);
+    let undone = store.restore_backup(&id, &newest).unwrap();
+    assert_eq!(undone.rumble.ground_roll, BACKUP_HISTORY as f32);
+
+    // A backup that no longer loads is refused and the current file stays.
+    fs::write(&newest.path, "rumble: { smoothing_alpha: 4.0 }\n").unwrap();
+    assert!(store.restore_backup(&id, &newest).is_err());
+    assert_eq!(store.load_id(&id).unwrap(), undone);
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn exported_package_imports_into_another_store() {
+    let root = std::env::temp_dir().join(format!("ursa-share-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let alice = PresetStore::new(root.join("alice"));
+    alice.bootstrap().unwrap();
+    fs::write(
+        alice.aircraft_path(),
+        "- { title_contains: Cub, rumble: { ground_roll: 70.0 } }\n- { title_contains: Airbus, rumble: {} }\n",
+    )
+    .unwrap();
+    let mut rumble = PresetKind::GeneralAviation.built_in_default().rumble;
+    rumble.ground_roll = 33.0;
+    let cub = alice
+        .create_user("Bush Cub", PresetKind::GeneralAviation, rumble)
+        .unwrap();
+    let text = alice
+        .export_package(&cub, "Alice", vec!["Piper Cub".to_string()], true)
+        .to_yaml()
+        .unwrap();
+
+    let bob = PresetStore::new(root.join("bob"));
+    bob.bootstrap().unwrap();
+    let package = PresetPackage::parse(&text).unwrap();
+    assert_eq!(package.author, "Alice");
+    assert_eq!(
+        package.aircraft_overrides.len(),
+        1,
+        "only matching overrides"
+    );
+    let current = bob.load(PresetKind::Commercial).unwrap();
+    assert!(package
+        .preview(&current.rumble)
+        .iter()
+        .any(|c| c.key == "ground_roll"));
+
+    fs::write(bob.aircraft_path(), "- { title_contains: Cub, rumble: [\n").unwrap();
+    assert!(
+        bob.import_package(&package, "Bush Cub", true).is_err(),
+        "a broken aircraft.yml is not overwritten"
+    );
+    assert!(
+        bob.user_presets().is_empty(),
+        "failed import creates nothing"
+    );
+    fs::remove_file(bob.aircraft_path()).unwrap();
+
+    let imported = bob.import_package(&package, "Bush Cub", true).unwrap();
+    assert_eq!(imported.rumble, cub.rumble);
+    assert_eq!(imported.kind, PresetKind::GeneralAviation);
+    assert_eq!(bob.unused_name("bush cub"), "bush cub (2)");
+    let table = load_aircraft_overrides(&bob.aircraft_path()).unwrap();
+    assert_eq!(table.len(), 1);
+    assert_eq!(table[0].title_contains, "Cub");
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn presets_declare_extra_simvars_that_children_inherit() {
+    let dir = std::env::temp_dir().join(format!("ursa-extra-simvars-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let store = PresetStore::new(dir.clone());
+    store.bootstrap().unwrap();
+
+    fs::write(
+        dir.join("commercial.yml"),
+        "schema_version: 3\nkind: commercial\nextra_simvars:\n  - { name: SPOILERS LEFT POSITION, unit: Percent, key: spoilers_pct }\n",
+    )
+    .unwrap();
+    let parent = store.load(PresetKind::Commercial).unwrap();
+    let canonical = PresetKind::Commercial.built_in_default().simvars;
+    assert_eq!(
+        parent.simvars.extra.len(),
+        canonical.extra.len(),
+        "same key replaces"
+    );
+    let spoilers = parent
+        .simvars
+        .extra
+        .iter()
+        .find(|d| d.key == "spoilers_pct")
+        .unwrap();
+    assert_eq!(spoilers.name, "SPOILERS LEFT POSITION");
+
+    let mut child = store
+        .create_user("Add-on Jet", PresetKind::Commercial, parent.rumble.clone())
+        .unwrap();
+    fs::write(
+        dir.join("user").join("add-on-jet.yml"),
+        "schema_version: 3\nname: Add-on Jet\nextends: commercial\nextra_simvars:\n  - name: GENERAL ENG OIL PRESSURE:1\n    unit: psi\n    key: oil_psi_1\n",
+    )
+    .unwrap();
+    child = store.load_id(&child.id).unwrap();
+    assert_eq!(
+        child.extra_simvars.len(),
+        2,
+        "inherits the parent's declaration"
+    );
+    let oil = child.simvars.extra.last().unwrap();
+    assert_eq!(
+        (oil.name.as_str(), oil.datum_index),
+        ("GENERAL ENG OIL PRESSURE", 1)
+    );
+    assert!(child
+        .layout()
+        .extra_keys()
+        .contains(&"oil_psi_1".to_string()));
+
+    child.rumble.ground_roll = 10.0;
+    store.save(&child).unwrap();
+    let text = fs::read_to_string(dir.join("user").join("add-on-jet.yml")).unwrap();
+    assert!(
+        text.contains("oil_psi_1") && !text.contains("spoilers_pct"),
+        "{text}"
+    );
+    assert_eq!(store.load_id(&child.id).unwrap(), child);
+
+    let copy = store.duplicate(&child, "Copy").unwrap();
+    assert_eq!(
+        store.load_id(&copy.id).unwrap().extra_simvars,
+        child.extra_simvars
+    );
+
+    let bad = "schema_version: 3\nkind: fighter\nextra_simvars:\n  - name: X\n    unit: Bool\n    key: Bad-Key\n";
+    fs::write(dir.join("fighter.yml"), bad).unwrap();
+    let err = store.load(PresetKind::Fighter).unwrap_err();
+    assert!(matches!(
+        &err,
+        PresetError::InvalidSimVar { issue: SimVarIssue::BadKey(k), .. } if k == "Bad-Key"
+    ));
+    assert_eq!(err.location(), Some(Location { line: 6, column: 5 }));
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn custom_effects_load_with_the_preset_and_report_unknown_variables() {
+    let dir = std::env::temp_dir().join(format!("ursa-custom-effects-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let store = PresetStore::new(dir.clone());
+    store.bootstrap().unwrap();
+
+    let fighter = "schema_version: 3\nkind: fighter\nextra_simvars:\n  - { name: G FORCE, unit: GForce, key: g_force }\ncustom_effects:\n  - name: G buffet\n    intensity: clamp((g_force - 1.5) * 40, 0, 120)\n    trigger: \"!on_ground\"\n    waveform: { shape: sine, hz: 12 }\n";
+    fs::write(dir.join("fighter.yml"), fighter).unwrap();
+    let preset = store.load(PresetKind::Fighter).unwrap();
+    assert_eq!(preset.custom_effects.len(), 1);
+    assert_eq!(preset.custom_effects[0].name, "G buffet");
+
+    store.save(&preset).unwrap();
+    assert_eq!(
+        store.load(PresetKind::Fighter).unwrap(),
+        preset,
+        "round-trips"
+    );
+
+    let typo = fighter.replace("(g_force - 1.5)", "(g_forse - 1.5)");
+    fs::write(dir.join("fighter.yml"), typo).unwrap();
+    let err = store.load(PresetKind::Fighter).unwrap_err();
+    assert!(matches!(&err, PresetError::InvalidEffect { .. }), "{err}");
+    assert!(err.to_string().contains("g_forse"), "{err}");
+    assert_eq!(err.location(), Some(Location { line: 6, column: 5 }));
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn textures_reshape_built_in_effects_and_report_bad_values() {
+    let dir = std::env::temp_dir().join(format!("ursa-textures-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let store = PresetStore::new(dir.clone());
+    store.bootstrap().unwrap();
+
+    let fighter = "schema_version: 3\nkind: fighter\ntextures:\n  spoilers: { shape: noise, hz: 30, seed: 7 }\n  engine: { duty: 0.5 }\n";
+    fs::write(dir.join("fighter.yml"), fighter).unwrap();
+    let preset = store.load(PresetKind::Fighter).unwrap();
+    assert_eq!(preset.textures[&Effect::Spoilers].shape, Some(Shape::Noise));
+    assert_eq!(preset.textures[&Effect::Engine].duty, Some(0.5));
+    store.save(&preset).unwrap();
+    assert_eq!(
+        store.load(PresetKind::Fighter).unwrap(),
+        preset,
+        "round-trips"
+    );
+
+    fs::write(
+        dir.join("fighter.yml"),
+        fighter.replace("duty: 0.5", "duty: 1.5"),
+    )
+    .unwrap();
+    let err = store.load(PresetKind::Fighter).unwrap_err();
+    assert!(err.to_string().contains("Engine"), "{err}");
+    assert_eq!(err.location(), Some(Location { line: 3, column: 1 }));
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn patterns_attach_to_bumps_and_travel_in_packages() {
+    let dir = std::env::temp_dir().join(format!("ursa-patterns-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let store = PresetStore::new(dir.clone());
+    store.bootstrap().unwrap();
+
+    let fighter = "schema_version: 3\nkind: fighter\npatterns:\n  flaps:\n    keyframes:\n      - { at_ms: 0, level: 1 }\n      - { at_ms: 40, level: 0, curve: smooth }\n";
+    fs::write(dir.join("fighter.yml"), fighter).unwrap();
+    let preset = store.load(PresetKind::Fighter).unwrap();
+    let clunk = &preset.patterns[&Effect::Flaps];
+    assert_eq!(clunk.duration(), Duration::from_millis(40));
+    store.save(&preset).unwrap();
+    assert_eq!(store.load(PresetKind::Fighter).unwrap(), preset);
+
+    let package = PresetPackage::new(&preset, "", Vec::new());
+    let parsed = PresetPackage::parse(&package.to_yaml().unwrap()).unwrap();
+    assert_eq!(parsed.patterns, preset.patterns);
+
+    fs::write(
+        dir.join("fighter.yml"),
+        fighter.replace("flaps:", "spoilers:"),
+    )
+    .unwrap();
+    let err = store.load(PresetKind::Fighter).unwrap_err();
+    assert!(err.to_string().contains("flaps and gear"), "{err}");
+    assert_eq!(err.location(), Some(Location { line: 3, column: 1 }));
+
+    let _ = fs::remove_dir_all(&dir);
+}